@@ -1,10 +1,11 @@
 use pact_matching::models::*;
-use pact_matching::models::matchingrules::MatchingRules;
+use pact_matching::models::generators::{GeneratorCategory, Generators};
+use pact_matching::models::matchingrules::{MatchingRules, MatchingRule, RuleLogic, Category};
 #[cfg(test)]
 use regex::Regex;
-use std::collections::HashMap;
 
 use prelude::*;
+use super::multipart_builder::{MultipartBuilder, MULTIPART_BOUNDARY};
 
 #[cfg(test)]
 #[allow(unused_imports)]
@@ -20,7 +21,7 @@ pub trait HttpPartBuilder {
     /// `&mut` into two `&mut` pointing to sub-objects, which has to be done
     /// carefully in Rust.
     #[doc(hidden)]
-    fn headers_and_matching_rules_mut(&mut self) -> (&mut HashMap<String, String>, &mut MatchingRules);
+    fn headers_and_matching_rules_mut(&mut self) -> (&mut HeaderMap, &mut MatchingRules);
 
     /// (Implementation detail.) This function fetches the mutable state that's
     /// needed to update this builder's `body`. You should not need to use this
@@ -32,7 +33,23 @@ pub trait HttpPartBuilder {
     #[doc(hidden)]
     fn body_and_matching_rules_mut(&mut self) -> (&mut OptionalBody, &mut MatchingRules);
 
-    /// Specify a header pattern.
+    /// (Implementation detail.) This function fetches the generators of this
+    /// builder, which the patterns for headers and bodies add to. You should
+    /// not need to use this under normal circumstances.
+    #[doc(hidden)]
+    fn generators_mut(&mut self) -> &mut Generators;
+
+    /// Specify a header pattern. This can be a literal string or any pattern
+    /// which matches strings, like a `term!`, `timestamp!` or `includes!`
+    /// pattern. Other patterns can be wrapped using `StringPattern::pattern`.
+    ///
+    /// Calling this more than once with the same name (ignoring case) adds
+    /// another value to the header. The pact format only has one set of
+    /// matching rules and generators for each header, so a header which is
+    /// given more than once can only have literal values, and this panics if
+    /// any of them is a pattern with matching rules or generators. Use a
+    /// single pattern with all the values instead, or `set_header` to replace
+    /// a header.
     ///
     /// ```
     /// #[macro_use]
@@ -44,9 +61,16 @@ pub trait HttpPartBuilder {
     /// use regex::Regex;
     ///
     /// # fn main() {
-    /// RequestBuilder::default()
+    /// let request = RequestBuilder::default()
     ///     .header("X-Simple", "value")
-    ///     .header("X-Digits", term!("^[0-9]+$", "123"));
+    ///     .header("X-Digits", term!("^[0-9]+$", "123"))
+    ///     .header("If-Modified-Since", timestamp!("EEE, dd MMM yyyy HH:mm:ss z", "Wed, 21 Oct 2015 07:28:00 GMT"))
+    ///     .header("Accept", includes!("json", "application/json, text/plain"))
+    ///     .header("X-Tag", "a")
+    ///     .header("X-Tag", "b")
+    ///     .build();
+    /// assert_eq!(request.headers.clone().unwrap()["Accept"], vec!["application/json", "text/plain"]);
+    /// assert_eq!(request.headers.unwrap()["X-Tag"], vec!["a", "b"]);
     /// # }
     /// ```
     fn header<N, V>(&mut self, name: N, value: V) -> &mut Self
@@ -56,20 +80,62 @@ pub trait HttpPartBuilder {
     {
         let name = name.into();
         let value = value.into();
+        let (key, repeated) = match self.headers_and_matching_rules_mut().0.get_key_value(&name) {
+            Some((key, _)) => (key.clone(), true),
+            None => (name, false),
+        };
+        if repeated {
+            let mut value_rules = Category::default("header");
+            value.extract_matching_rules(&key, &mut value_rules);
+            let mut value_generators = Generators::default();
+            value.extract_generators(&key, &GeneratorCategory::HEADER, &mut value_generators);
+            let has_rules = self.headers_and_matching_rules_mut().1.rules.get("header")
+                .map(|rules| rules.rules.contains_key(&key))
+                .unwrap_or(false);
+            let has_generators = self.generators_mut().categories.get(&GeneratorCategory::HEADER)
+                .map(|generators| generators.contains_key(&key))
+                .unwrap_or(false);
+            if has_rules || has_generators || value_rules.is_not_empty() || value_generators.is_not_empty() {
+                panic!("header '{}' is given more than once, so its values can not have matching rules \
+                    or generators. Use a single pattern for all of its values instead.", key);
+            }
+        }
         {
             let (headers, rules) = self.headers_and_matching_rules_mut();
-            headers.insert(name.clone(), value.to_example());
-            value.extract_matching_rules(&name, rules.add_category("header"))
+            headers.add(key.clone(), parse_header(&key, &value.to_example()));
+            value.extract_matching_rules(&key, rules.add_category("header"));
         }
+        value.extract_generators(&key, &GeneratorCategory::HEADER, self.generators_mut());
         self
     }
 
+    /// Specify a header pattern, replacing any values and matching rules
+    /// already given for the header (ignoring case).
+    fn set_header<N, V>(&mut self, name: N, value: V) -> &mut Self
+    where
+        N: Into<String>,
+        V: Into<StringPattern>,
+    {
+        let name = name.into();
+        {
+            let (headers, rules) = self.headers_and_matching_rules_mut();
+            let lower_name = name.to_lowercase();
+            headers.remove(&name);
+            rules.add_category("header").rules.retain(|key, _| key.to_lowercase() != lower_name);
+        }
+        if let Some(generators) = self.generators_mut().categories.get_mut(&GeneratorCategory::HEADER) {
+            let lower_name = name.to_lowercase();
+            generators.retain(|key, _| key.to_lowercase() != lower_name);
+        }
+        self.header(name, value)
+    }
+
     /// Set the `Content-Type` header.
     fn content_type<CT>(&mut self, content_type: CT) -> &mut Self
     where
         CT: Into<StringPattern>,
     {
-        self.header("Content-Type", content_type)
+        self.set_header("Content-Type", content_type)
     }
 
     /// Set the `Content-Type` header to `text/html`.
@@ -134,6 +200,104 @@ pub trait HttpPartBuilder {
             *body_ref = OptionalBody::Present(body.to_example().to_string().into());
             body.extract_matching_rules("$", rules.add_category("body"));
         }
+        body.extract_generators("$", &GeneratorCategory::BODY, self.generators_mut());
+        self
+    }
+
+    /// Specify the body as an `XmlPattern`, possibly including special
+    /// matching rules. The body is written with an XML declaration, so it is
+    /// matched as XML.
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate pact_consumer;
+    ///
+    /// use pact_consumer::prelude::*;
+    /// use pact_consumer::builders::RequestBuilder;
+    ///
+    /// # fn main() {
+    /// RequestBuilder::default().xml_body(xml!(message(id = term!("^[0-9]+$", "1")) [
+    ///     "Hello"
+    /// ]));
+    /// # }
+    /// ```
+    fn xml_body<B: Into<XmlPattern>>(&mut self, body: B) -> &mut Self {
+        let body = body.into();
+        {
+            let (body_ref, rules) = self.body_and_matching_rules_mut();
+            let xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>{}", body.to_example());
+            *body_ref = OptionalBody::Present(xml.into());
+            body.extract_matching_rules("$", rules.add_category("body"));
+        }
+        body.extract_generators("$", &GeneratorCategory::BODY, self.generators_mut());
+        self
+    }
+
+    /// Specify a binary body, like a PDF or an image, and set the
+    /// `Content-Type` header. The body is matched by the content type that is
+    /// detected from its contents rather than byte for byte, so any document
+    /// of the same type will match.
+    ///
+    /// ```
+    /// use pact_consumer::prelude::*;
+    /// use pact_consumer::builders::ResponseBuilder;
+    ///
+    /// ResponseBuilder::default()
+    ///     .binary_body("application/pdf", &b"%PDF-1.4 ..."[..]);
+    /// ```
+    fn binary_body<C, B>(&mut self, content_type: C, body: B) -> &mut Self
+    where
+        C: Into<String>,
+        B: Into<Vec<u8>>,
+    {
+        let content_type = content_type.into();
+        self.content_type(content_type.clone());
+        {
+            let (body_ref, rules) = self.body_and_matching_rules_mut();
+            *body_ref = OptionalBody::Present(body.into());
+            rules.add_category("body").add_rule(&s!("$"), MatchingRule::ContentType(content_type),
+                &RuleLogic::And);
+        }
+        self
+    }
+
+    /// Specify a `multipart/form-data` body, and set the `Content-Type` header
+    /// to match any multipart boundary. Parts are added to the body by the
+    /// `build_fn`, and matching rules for a part are scoped to its name.
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate pact_consumer;
+    ///
+    /// use pact_consumer::prelude::*;
+    /// use pact_consumer::builders::RequestBuilder;
+    ///
+    /// # fn main() {
+    /// RequestBuilder::default().post().multipart_body(|body| {
+    ///     body.text_part("description", term!("^A ", "A picture"))
+    ///         .json_part("metadata", json_pattern!({ "id": like!(100) }))
+    ///         .file_part("file", "picture.png", "image/png", vec![0x89, 0x50, 0x4E, 0x47]);
+    /// });
+    /// # }
+    /// ```
+    fn multipart_body<F>(&mut self, build_fn: F) -> &mut Self
+    where
+        F: FnOnce(&mut MultipartBuilder),
+    {
+        let mut builder = MultipartBuilder::default();
+        build_fn(&mut builder);
+        self.content_type(term!(
+            "^multipart/form-data;\\s*boundary=.+$",
+            format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY)
+        ));
+        {
+            let (body_ref, rules) = self.body_and_matching_rules_mut();
+            *body_ref = OptionalBody::Present(builder.build());
+            let category = rules.add_category("body");
+            for (path, rule_list) in &builder.matching_rules().rules {
+                category.rules.insert(path.clone(), rule_list.clone());
+            }
+        }
         self
     }
 }
@@ -161,6 +325,98 @@ fn header_pattern() {
     assert_requests_do_not_match!(bad, pattern);
 }
 
+#[test]
+fn header_patterns_other_than_terms() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request
+                .header("X-Trace", includes!("trace=", "id=1;trace=abc"))
+                .header("X-Id", uuid!());
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request
+                .header("X-Trace", "trace=xyz")
+                .header("X-Id", "0f7e1b3c-6d0a-4a36-9f1e-3f1f8c5b2a10");
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request
+                .header("X-Trace", "id=2")
+                .header("X-Id", "0f7e1b3c-6d0a-4a36-9f1e-3f1f8c5b2a10");
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
+#[test]
+fn repeated_headers_are_multi_valued() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request
+                .header("X-Tag", "a")
+                .header("x-tag", "b")
+                .content_type("text/plain")
+                .content_type("text/html");
+        })
+        .build();
+    let headers = pattern.interactions[0].request.headers.clone().unwrap();
+    assert_eq!(headers["X-Tag"], vec!["a", "b"]);
+    assert_eq!(headers["Content-Type"], vec!["text/html"]);
+
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.header("X-Tag", "a").header("X-Tag", "b").content_type("text/html");
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.header("X-Tag", "b").content_type("text/html");
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
+#[test]
+fn built_headers_match_themselves() {
+    use pact_matching::match_request;
+    use super::request_builder::RequestBuilder;
+
+    let request = RequestBuilder::default()
+        .header("X-Simple", "value")
+        .header("X-Digits", term!("^[0-9]+$", "123"))
+        .header("If-Modified-Since", timestamp!("EEE, dd MMM yyyy HH:mm:ss z", "Wed, 21 Oct 2015 07:28:00 GMT"))
+        .header("Accept", includes!("json", "application/json, text/plain"))
+        .header("X-Tag", "a")
+        .header("x-tag", "b")
+        .build();
+    assert_eq!(match_request(request.clone(), request), vec![]);
+}
+
+#[test]
+#[should_panic(expected = "header 'Accept' is given more than once")]
+fn repeated_headers_can_not_have_matching_rules() {
+    use super::request_builder::RequestBuilder;
+
+    RequestBuilder::default()
+        .header("Accept", includes!("json", "application/json"))
+        .header("accept", "text/plain");
+}
+
+#[test]
+#[should_panic(expected = "header 'Accept' is given more than once")]
+fn repeated_header_values_can_not_have_matching_rules() {
+    use super::request_builder::RequestBuilder;
+
+    RequestBuilder::default()
+        .header("Accept", "text/plain")
+        .header("Accept", includes!("json", "application/json"));
+}
+
 #[test]
 fn body_literal() {
     let pattern = PactBuilder::new("C", "P")
@@ -198,3 +454,106 @@ fn json_body_pattern() {
     assert_requests_match!(good, pattern);
     assert_requests_do_not_match!(bad, pattern);
 }
+
+#[test]
+fn multipart_body_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().multipart_body(|body| {
+                body.text_part("description", term!("^A ", "A picture"))
+                    .json_part("metadata", json_pattern!({ "id": like!(100) }));
+            });
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().multipart_body(|body| {
+                body.text_part("description", "A photo")
+                    .json_part("metadata", json_pattern!({ "id": 200 }));
+            });
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().multipart_body(|body| {
+                body.text_part("description", "A photo")
+                    .json_part("metadata", json_pattern!({ "id": "200" }));
+            });
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
+#[test]
+fn binary_body_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().binary_body("image/png", &b"\x89PNG\r\n\x1a\nexpected"[..]);
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().binary_body("image/png", &b"\x89PNG\r\n\x1a\nactual"[..]);
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().binary_body("image/png", &b"GIF89a"[..]);
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
+#[test]
+fn xml_body_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().xml_body(xml!(order(id = term!("^[0-9]+$", "1001")) [
+                items [
+                    { EachLike::new(xml!(item(sku = Like::new("A-1".to_owned())))) }
+                ]
+            ]));
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().xml_body(xml!(order(id = "42") [
+                items [ item(sku = "B-2"), item(sku = "C-3") ]
+            ]));
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().xml_body(xml!(order(id = "A42") [
+                items [ item(sku = "B-2") ]
+            ]));
+        })
+        .build();
+    let empty = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().xml_body(xml!(order(id = "42") [items]));
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+    assert_requests_do_not_match!(empty, pattern);
+}
+
+#[test]
+fn xml_body_adds_generators() {
+    use pact_matching::generate_response_with_context;
+    use pact_matching::models::generators::Generator;
+    use super::response_builder::ResponseBuilder;
+
+    let response = ResponseBuilder::default()
+        .content_type("application/xml")
+        .xml_body(xml!(order(id = from_provider_state!("${id}", "1001"))))
+        .build();
+    assert_eq!(response.generators.categories[&GeneratorCategory::BODY]["$.order['@id']"],
+        Generator::ProviderStateGenerator(s!("${id}")));
+
+    let generated = generate_response_with_context(&response, &hashmap!{ s!("id") => json!(42) });
+    assert_eq!(generated.body.str_value(), "<?xml version='1.0'?><order id='42'/>");
+}
@@ -1,5 +1,7 @@
+use pact_matching::DiffConfig;
 use pact_matching::models::*;
 use pact_matching::models::provider_states::ProviderState;
+use serde_json::Value;
 
 use super::request_builder::RequestBuilder;
 use super::response_builder::ResponseBuilder;
@@ -9,6 +11,7 @@ use super::response_builder::ResponseBuilder;
 pub struct InteractionBuilder {
     description: String,
     provider_states: Vec<ProviderState>,
+    pub(crate) diff_config: Option<DiffConfig>,
 
     /// A builder for this interaction's `Request`.
     pub request: RequestBuilder,
@@ -23,6 +26,7 @@ impl InteractionBuilder {
         InteractionBuilder {
             description: description.into(),
             provider_states: vec![],
+            diff_config: None,
             request: RequestBuilder::default(),
             response: ResponseBuilder::default(),
         }
@@ -35,6 +39,39 @@ impl InteractionBuilder {
         self
     }
 
+    /// Specify a "provider state" with parameters, which must be a JSON
+    /// object. The parameters are passed to the provider when the pact is
+    /// verified, and can be used by `from_provider_state!` patterns.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate serde_json;
+    /// # extern crate pact_consumer;
+    /// # fn main() {
+    /// use pact_consumer::builders::InteractionBuilder;
+    ///
+    /// let interaction = InteractionBuilder::new("fetch an order")
+    ///     .given_with_params("an order exists", json!({ "id": 42 }))
+    ///     .build();
+    /// assert_eq!(interaction.provider_states[0].params["id"], json!(42));
+    /// # }
+    /// ```
+    pub fn given_with_params<G: Into<String>>(&mut self, given: G, params: Value) -> &mut Self {
+        let params = match params {
+            Value::Object(params) => params.into_iter().collect(),
+            other => panic!("provider state parameters must be a JSON object, not {}", other),
+        };
+        self.provider_states.push(ProviderState { name: given.into(), params });
+        self
+    }
+
+    /// Set whether mock servers allow keys in the request body which are not
+    /// in the pact, for this interaction only. This overrides
+    /// `PactBuilder::diff_config`.
+    pub fn diff_config(&mut self, config: DiffConfig) -> &mut Self {
+        self.diff_config = Some(config);
+        self
+    }
+
     /// The interaction we've built.
     pub fn build(&self) -> Interaction {
         Interaction {
@@ -45,3 +82,26 @@ impl InteractionBuilder {
         }
     }
 }
+
+#[test]
+#[should_panic]
+fn given_with_params_requires_an_object() {
+    InteractionBuilder::new("I").given_with_params("a state", json!([1, 2]));
+}
+
+#[test]
+fn provider_state_parameters_are_used_by_generators() {
+    use pact_matching::{generate_request_with_context, generate_response_with_context};
+    use prelude::*;
+
+    let mut builder = InteractionBuilder::new("I");
+    builder.given_with_params("an order exists", json!({ "id": 42 }));
+    builder.request.path(from_provider_state!("/orders/${id}", "/orders/1"));
+    builder.response.json_body(json_pattern!({ "id": from_provider_state!("${id}", 1) }));
+    let interaction = builder.build();
+
+    let context = interaction.provider_states[0].params.clone();
+    assert_eq!(generate_request_with_context(&interaction.request, &context).path, "/orders/42");
+    let response = generate_response_with_context(&interaction.response, &context);
+    assert_eq!(response.body.str_value(), "{\"id\":42}");
+}
@@ -4,12 +4,14 @@
 
 mod interaction_builder;
 mod http_part_builder;
+mod multipart_builder;
 mod pact_builder;
 mod request_builder;
 mod response_builder;
 
 pub use self::interaction_builder::*;
 pub use self::http_part_builder::*;
+pub use self::multipart_builder::*;
 pub use self::pact_builder::*;
 pub use self::request_builder::*;
 pub use self::response_builder::*;
@@ -0,0 +1,118 @@
+use pact_matching::models::matchingrules::Category;
+
+use prelude::*;
+
+/// The boundary used to separate the parts of the bodies we generate. The
+/// matching rules we add to the `Content-Type` header allow any boundary.
+pub const MULTIPART_BOUNDARY: &str = "----PactMultipartBoundaryZQ9k4TpF7mYwHh";
+
+/// The headers and contents of a part.
+type Part = (Vec<(String, String)>, Vec<u8>);
+
+/// Builder for `multipart/form-data` bodies. Normally created via
+/// `HttpPartBuilder::multipart_body`.
+///
+/// Matching rules for each part are scoped to the part name, so a rule on a
+/// field `id` of a JSON part named `metadata` applies to `$.metadata.id`.
+pub struct MultipartBuilder {
+    parts: Vec<Part>,
+    rules: Category,
+}
+
+impl MultipartBuilder {
+    /// Add a plain text part, which may use a pattern to match its value.
+    pub fn text_part<N, V>(&mut self, name: N, value: V) -> &mut Self
+    where
+        N: Into<String>,
+        V: Into<StringPattern>,
+    {
+        let name = name.into();
+        let value = value.into();
+        value.extract_matching_rules(&format!("$.{}", name), &mut self.rules);
+        self.push_part(&name, None, None, value.to_example().into_bytes())
+    }
+
+    /// Add a JSON part with a `Content-Type` of `application/json`, possibly
+    /// including special matching rules.
+    pub fn json_part<N, B>(&mut self, name: N, body: B) -> &mut Self
+    where
+        N: Into<String>,
+        B: Into<JsonPattern>,
+    {
+        let name = name.into();
+        let body = body.into();
+        body.extract_matching_rules(&format!("$.{}", name), &mut self.rules);
+        self.push_part(&name, None, Some("application/json"), body.to_example().to_string().into_bytes())
+    }
+
+    /// Add a file part with the given file name, content type and contents.
+    pub fn file_part<N, F, C, B>(&mut self, name: N, file_name: F, content_type: C, contents: B) -> &mut Self
+    where
+        N: Into<String>,
+        F: Into<String>,
+        C: Into<String>,
+        B: Into<Vec<u8>>,
+    {
+        let file_name = file_name.into();
+        let content_type = content_type.into();
+        self.push_part(&name.into(), Some(&file_name), Some(&content_type), contents.into())
+    }
+
+    fn push_part(&mut self, name: &str, file_name: Option<&str>, content_type: Option<&str>, body: Vec<u8>) -> &mut Self {
+        let mut disposition = format!("form-data; name=\"{}\"", name);
+        if let Some(file_name) = file_name {
+            disposition.push_str(&format!("; filename=\"{}\"", file_name));
+        }
+        let mut headers = vec![(s!("Content-Disposition"), disposition)];
+        if let Some(content_type) = content_type {
+            headers.push((s!("Content-Type"), s!(content_type)));
+        }
+        self.parts.push((headers, body));
+        self
+    }
+
+    /// The matching rules for the parts, in the `body` category.
+    pub fn matching_rules(&self) -> &Category {
+        &self.rules
+    }
+
+    /// Build the body, with the parts separated by `MULTIPART_BOUNDARY`.
+    pub fn build(&self) -> Vec<u8> {
+        let mut body = vec![];
+        for (headers, contents) in &self.parts {
+            body.extend_from_slice(format!("--{}\r\n", MULTIPART_BOUNDARY).as_bytes());
+            for (name, value) in headers {
+                body.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
+            }
+            body.extend_from_slice(b"\r\n");
+            body.extend_from_slice(contents);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", MULTIPART_BOUNDARY).as_bytes());
+        body
+    }
+}
+
+impl Default for MultipartBuilder {
+    fn default() -> Self {
+        MultipartBuilder {
+            parts: vec![],
+            rules: Category::default("body"),
+        }
+    }
+}
+
+#[test]
+fn multipart_body_contains_the_parts() {
+    let mut builder = MultipartBuilder::default();
+    builder
+        .text_part("name", "Fred")
+        .file_part("file", "a.txt", "text/plain", "some text");
+    let expected = format!(
+        "--{0}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nFred\r\n\
+         --{0}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\nsome text\r\n--{0}--\r\n",
+        MULTIPART_BOUNDARY
+    );
+    assert_eq!(String::from_utf8(builder.build()).unwrap(), expected);
+}
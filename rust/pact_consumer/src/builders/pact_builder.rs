@@ -1,5 +1,12 @@
+use pact_matching::DiffConfig;
 use pact_matching::models::*;
+use pact_mock_server::MatchingConfig;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::Arc;
 
+use mock_server::{BlockingTask, PactOutput, PactTest};
 use prelude::*;
 use super::interaction_builder::InteractionBuilder;
 
@@ -28,6 +35,8 @@ use super::interaction_builder::InteractionBuilder;
 /// ```
 pub struct PactBuilder {
     pact: Pact,
+    output: PactOutput,
+    matching: MatchingConfig,
 }
 
 impl PactBuilder {
@@ -41,7 +50,7 @@ impl PactBuilder {
         let mut pact = Pact::default();
         pact.consumer = Consumer { name: consumer.into() };
         pact.provider = Provider { name: provider.into() };
-        PactBuilder { pact }
+        PactBuilder { pact, output: PactOutput::default(), matching: MatchingConfig::default() }
     }
 
     /// Add a new `Interaction` to the `Pact`.
@@ -52,7 +61,12 @@ impl PactBuilder {
     {
         let mut interaction = InteractionBuilder::new(description.into());
         build_fn(&mut interaction);
-        self.push_interaction(interaction.build())
+        let diff_config = interaction.diff_config;
+        let interaction = interaction.build();
+        if let Some(config) = diff_config {
+            self.matching.set_diff_config_for(&interaction, config);
+        }
+        self.push_interaction(interaction)
     }
 
     /// Directly add a pre-built `Interaction` to our `Pact`. Normally it's
@@ -62,14 +76,112 @@ impl PactBuilder {
         self
     }
 
+    /// Set the directory mock servers write the pact file to. By default, this
+    /// is the `PACT_OUTPUT_DIR` environment variable, or `target/pacts`.
+    pub fn output_dir<P: Into<PathBuf>>(&mut self, dir: P) -> &mut Self {
+        self.output.dir = Some(dir.into());
+        self
+    }
+
+    /// Set the version of the pact specification mock servers write the pact
+    /// file as. This defaults to V3.
+    pub fn specification(&mut self, specification: PactSpecification) -> &mut Self {
+        self.output.specification = specification;
+        self
+    }
+
+    /// Set whether mock servers merge the pact into an existing pact file, or
+    /// replace it. By default they merge, and fail if any of the interactions
+    /// conflict.
+    pub fn write_mode(&mut self, mode: PactWriteMode) -> &mut Self {
+        self.output.mode = mode;
+        self
+    }
+
+    /// Set whether mock servers allow keys in request bodies which are not in
+    /// the pact. By default, they are mismatches. This can be overridden for
+    /// each interaction with `InteractionBuilder::diff_config`.
+    pub fn diff_config(&mut self, config: DiffConfig) -> &mut Self {
+        self.matching.diff_config = config;
+        self
+    }
+
     /// Return the `Pact` we've built.
     pub fn build(&self) -> Pact {
         self.pact.clone()
     }
+
+    /// Start a mock server for the `Pact` we've built, and run `test` against
+    /// it. This returns a future which completes when the future returned by
+    /// `test` does. It then verifies the mock server, writes out the pact if
+    /// there were no mismatches, and resolves to either the output of `test` or
+    /// a `ShutdownError`. The pact is not written if `test` panics.
+    ///
+    /// ```ignore
+    /// let result = PactBuilder::new("Consumer", "Alice Service")
+    ///     .interaction("a retrieve Mallory request", |i| {
+    ///         i.request.path("/mallory");
+    ///         i.response.body("That is some good Mallory.");
+    ///     })
+    ///     .run(|server| async move {
+    ///         let body = my_client::fetch(server.path("/mallory")).await;
+    ///         assert_eq!(body, "That is some good Mallory.");
+    ///     })
+    ///     .await;
+    /// assert!(result.is_ok());
+    /// ```
+    pub fn run<F, Fut>(&self, test: F) -> PactTest<Fut>
+    where
+        F: FnOnce(Arc<ValidatingMockServer>) -> Fut,
+        Fut: Future,
+    {
+        PactTest::new(self.start_mock_server(), test)
+    }
+
+    /// Like `run`, but for tests which are not async. This starts a mock
+    /// server, calls `test` with it, and then verifies the mock server and
+    /// writes out the pact if there were no mismatches. If `test` panics, the
+    /// panic is passed on and the pact is not written. This is what the
+    /// `#[pact_test]` attribute uses.
+    ///
+    /// ```
+    /// use pact_consumer::prelude::*;
+    ///
+    /// let result = PactBuilder::new("Consumer", "Alice Service")
+    ///     .interaction("a retrieve Mallory request", |i| {
+    ///         i.request.path("/mallory");
+    ///     })
+    ///     .run_blocking(|server| {
+    ///         // We never make the request, so verification fails.
+    ///         server.path("/mallory")
+    ///     });
+    /// match result {
+    ///     Err(ShutdownError::Mismatches(report)) => assert_eq!(report.mismatches.len(), 1),
+    ///     other => panic!("unexpected result {:?}", other),
+    /// }
+    /// ```
+    pub fn run_blocking<F, T>(&self, test: F) -> Result<T, ShutdownError>
+    where
+        F: FnOnce(&ValidatingMockServer) -> T,
+    {
+        let server = self.start_mock_server();
+        match panic::catch_unwind(AssertUnwindSafe(|| test(&server))) {
+            Ok(output) => server.shutdown().map(|()| output),
+            Err(panic) => {
+                server.abandon();
+                panic::resume_unwind(panic)
+            }
+        }
+    }
 }
 
 impl StartMockServer for PactBuilder {
     fn start_mock_server(&self) -> ValidatingMockServer {
-        ValidatingMockServer::new(self.build())
+        ValidatingMockServer::with_config(self.build(), self.output.clone(), self.matching.clone())
+    }
+
+    fn start_mock_server_async(&self) -> BlockingTask<ValidatingMockServer> {
+        let (pact, output, matching) = (self.build(), self.output.clone(), self.matching.clone());
+        BlockingTask::spawn(move || ValidatingMockServer::with_config(pact, output, matching))
     }
 }
@@ -1,4 +1,5 @@
 use pact_matching::models::*;
+use pact_matching::models::generators::{GeneratorCategory, Generators};
 use pact_matching::models::matchingrules::MatchingRules;
 #[cfg(test)]
 use regex::Regex;
@@ -53,6 +54,24 @@ impl RequestBuilder {
     }
 
     /// Specify the request path. Defaults to `"/"`.
+    ///
+    /// Paths with variable parts can be given as a template, with a pattern
+    /// for each of the variables.
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate pact_consumer;
+    ///
+    /// use pact_consumer::prelude::*;
+    /// use pact_consumer::builders::RequestBuilder;
+    ///
+    /// # fn main() {
+    /// RequestBuilder::default()
+    ///     .path(path_template!("/orders/{id}/items/{itemId}",
+    ///         id => term!("^[0-9]+$", "1001"),
+    ///         itemId => Like::new("abc".to_owned())));
+    /// # }
+    /// ```
     pub fn path<P: Into<StringPattern>>(&mut self, path: P) -> &mut Self {
         let path = path.into();
         self.request.path = path.to_example();
@@ -60,6 +79,11 @@ impl RequestBuilder {
             "",
             self.request.matching_rules.add_category("path"),
         );
+        path.extract_generators(
+            "",
+            &GeneratorCategory::PATH,
+            &mut self.request.generators,
+        );
         self
     }
 
@@ -101,11 +125,70 @@ impl RequestBuilder {
             .or_insert_with(Default::default)
             .push(value.to_example());
 
-        // Extract our matching rules.
+        // Extract our matching rules and generators.
         value.extract_matching_rules(
             &key,
             self.request.matching_rules.add_category("query"),
         );
+        value.extract_generators(
+            &key,
+            &GeneratorCategory::QUERY,
+            &mut self.request.generators,
+        );
+
+        self
+    }
+
+    /// Specify a form post body, and set the `Content-Type` header to
+    /// `application/x-www-form-urlencoded`. Each field may be given a literal
+    /// value or a pattern, and a field can be repeated by passing it more than
+    /// once.
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate pact_consumer;
+    /// extern crate regex;
+    ///
+    /// use pact_consumer::prelude::*;
+    /// use pact_consumer::builders::RequestBuilder;
+    /// use regex::Regex;
+    ///
+    /// # fn main() {
+    /// RequestBuilder::default()
+    ///     .post()
+    ///     .form_body(vec![
+    ///         ("name", StringPattern::from("Fred")),
+    ///         ("age", term!("^[0-9]+$", "42").into()),
+    ///     ]);
+    /// # }
+    /// ```
+    pub fn form_body<I, K, V>(&mut self, fields: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<StringPattern>,
+    {
+        self.content_type("application/x-www-form-urlencoded");
+
+        let mut params: HashMap<String, Vec<String>> = HashMap::new();
+        for (key, value) in fields {
+            let key = key.into();
+            let value = value.into();
+            params.entry(key.clone())
+                .or_insert_with(Default::default)
+                .push(value.to_example());
+            let path = format!("$.{}", key);
+            value.extract_matching_rules(
+                &path,
+                self.request.matching_rules.add_category("body"),
+            );
+            value.extract_generators(
+                &path,
+                &GeneratorCategory::BODY,
+                &mut self.request.generators,
+            );
+        }
+        self.request.body = OptionalBody::Present(build_query_string(params).into());
 
         self
     }
@@ -123,7 +206,7 @@ impl Default for RequestBuilder {
 }
 
 impl HttpPartBuilder for RequestBuilder {
-    fn headers_and_matching_rules_mut(&mut self) -> (&mut HashMap<String, String>, &mut MatchingRules) {
+    fn headers_and_matching_rules_mut(&mut self) -> (&mut HeaderMap, &mut MatchingRules) {
         (
             self.request.headers.get_defaulting(),
             &mut self.request.matching_rules,
@@ -136,6 +219,10 @@ impl HttpPartBuilder for RequestBuilder {
             &mut self.request.matching_rules,
         )
     }
+
+    fn generators_mut(&mut self) -> &mut Generators {
+        &mut self.request.generators
+    }
 }
 
 #[test]
@@ -156,6 +243,64 @@ fn path_pattern() {
     assert_requests_do_not_match!(bad, pattern);
 }
 
+#[test]
+fn path_template_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.path(path_template!("/orders/{id}/items/{itemId}",
+                id => term!("^[0-9]+$", "1001"),
+                itemId => Like::new("abc".to_owned())));
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", |i| { i.request.path("/orders/12/items/xyz"); })
+        .build();
+    let bad1 = PactBuilder::new("C", "P")
+        .interaction("I", |i| { i.request.path("/orders/twelve/items/xyz"); })
+        .build();
+    let bad2 = PactBuilder::new("C", "P")
+        .interaction("I", |i| { i.request.path("/orders/12/items/xyz/notes"); })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad1, pattern);
+    assert_requests_do_not_match!(bad2, pattern);
+}
+
+#[test]
+fn patterns_add_generators() {
+    use pact_matching::models::generators::Generator;
+    use std::str::FromStr;
+
+    let pact = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request
+                .post()
+                .path(uuid!())
+                .query_param("since", date!("yyyy-MM-dd", "2018-06-01"))
+                .header("X-Request-Id", uuid!())
+                .json_body(json_pattern!({
+                    "id": integer!(),
+                    "count": integer!(12),
+                    "items": each_like!({ "created": timestamp!("yyyy-MM-dd'T'HH:mm:ss", "2018-06-01T10:15:30") }),
+                }));
+            i.response.json_body(json_pattern!({ "price": decimal!() }));
+        })
+        .build();
+    let interaction = &pact.interactions[0];
+    assert_eq!(interaction.request.generators, generators!{
+        "PATH" => { "" => Generator::Uuid },
+        "QUERY" => { "since" => Generator::Date(Some(s!("yyyy-MM-dd"))) },
+        "HEADER" => { "X-Request-Id" => Generator::Uuid },
+        "BODY" => {
+            "$.id" => Generator::RandomInt(0, i32::MAX),
+            "$.items[*].created" => Generator::Timestamp(Some(s!("yyyy-MM-dd'T'HH:mm:ss")))
+        }
+    });
+    assert_eq!(interaction.response.generators, generators!{
+        "BODY" => { "$.price" => Generator::RandomDecimal(10) }
+    });
+}
+
 #[test]
 fn query_param_pattern() {
     let pattern = PactBuilder::new("C", "P")
@@ -173,6 +318,30 @@ fn query_param_pattern() {
     assert_requests_do_not_match!(bad, pattern);
 }
 
+#[test]
+fn form_body_pattern() {
+    let pattern = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().form_body(vec![
+                ("name", StringPattern::from("Fred")),
+                ("age", term!("^[0-9]+$", "42").into()),
+            ]);
+        })
+        .build();
+    let good = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().form_body(vec![("age", "7"), ("name", "Fred")]);
+        })
+        .build();
+    let bad = PactBuilder::new("C", "P")
+        .interaction("I", |i| {
+            i.request.post().form_body(vec![("name", "Fred"), ("age", "old")]);
+        })
+        .build();
+    assert_requests_match!(good, pattern);
+    assert_requests_do_not_match!(bad, pattern);
+}
+
 #[test]
 fn query_param_with_underscore() {
     let pattern = PactBuilder::new("C", "P")
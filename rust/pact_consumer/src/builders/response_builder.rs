@@ -1,6 +1,6 @@
 use pact_matching::models::*;
+use pact_matching::models::generators::Generators;
 use pact_matching::models::matchingrules::MatchingRules;
-use std::collections::HashMap;
 
 use prelude::*;
 use util::GetDefaulting;
@@ -11,17 +11,25 @@ pub struct ResponseBuilder {
 }
 
 impl ResponseBuilder {
-    /// Set the status code for the response. Defaults to `200`.
+    /// Set the status code for the response. Defaults to `200`. This can also
+    /// be a `StatusCode` pattern, which matches a whole class of status codes.
     ///
     /// ```
     /// use pact_consumer::builders::ResponseBuilder;
+    /// use pact_consumer::patterns::StatusCode;
     /// use pact_consumer::prelude::*;
     ///
     /// let response = ResponseBuilder::default().status(404).build();
     /// assert_eq!(response.status, 404);
+    ///
+    /// let response = ResponseBuilder::default().status(StatusCode::success()).build();
+    /// assert_eq!(response.status, 200);
     /// ```
-    pub fn status(&mut self, status: u16) -> &mut Self {
-        self.response.status = status;
+    pub fn status<S: Pattern<Matches = u16>>(&mut self, status: S) -> &mut Self {
+        self.response.status = status.to_example();
+        let rules = self.response.matching_rules.add_category("status");
+        rules.rules.clear();
+        status.extract_matching_rules("", rules);
         self
     }
 
@@ -71,7 +79,7 @@ impl Default for ResponseBuilder {
 }
 
 impl HttpPartBuilder for ResponseBuilder {
-    fn headers_and_matching_rules_mut(&mut self) -> (&mut HashMap<String, String>, &mut MatchingRules) {
+    fn headers_and_matching_rules_mut(&mut self) -> (&mut HeaderMap, &mut MatchingRules) {
         (
             self.response.headers.get_defaulting(),
             &mut self.response.matching_rules,
@@ -85,4 +93,25 @@ impl HttpPartBuilder for ResponseBuilder {
         )
 
     }
+
+    fn generators_mut(&mut self) -> &mut Generators {
+        &mut self.response.generators
+    }
+}
+
+#[test]
+fn status_class_pattern() {
+    use pact_matching::match_response;
+    use patterns::StatusCode;
+
+    let pattern = ResponseBuilder::default().status(StatusCode::success()).build();
+    let good = ResponseBuilder::default().status(204).build();
+    let bad = ResponseBuilder::default().not_found().build();
+    assert!(match_response(pattern.clone(), good).is_empty());
+    assert!(!match_response(pattern, bad).is_empty());
+
+    // Setting a literal status code replaces the status class.
+    let pattern = ResponseBuilder::default().status(StatusCode::success()).ok().build();
+    let other = ResponseBuilder::default().status(204).build();
+    assert!(!match_response(pattern, other).is_empty());
 }
@@ -100,6 +100,31 @@
 //! # }
 //! ```
 //!
+//! You can also let the `#[pact_test]` attribute build the pact, start the
+//! mock server and check it for you. The pact is only written out if the test
+//! passes.
+//!
+//! ```ignore
+//! use pact_consumer::pact_test;
+//! use pact_consumer::prelude::*;
+//!
+//! fn interactions(pact: &mut PactBuilder) {
+//!     pact.interaction("a retrieve Mallory request", |i| {
+//!         i.request.path("/mallory");
+//!         i.response.body("That is some good Mallory.");
+//!     });
+//! }
+//!
+//! #[pact_test(consumer = "Consumer", provider = "Alice Service", interactions = interactions)]
+//! fn fetches_mallory(alice_service: &ValidatingMockServer) {
+//!     let mallory_url = alice_service.path("/mallory");
+//!     let mut response = reqwest::get(mallory_url).expect("could not fetch URL");
+//!     let mut body = String::new();
+//!     response.read_to_string(&mut body).expect("could not read response body");
+//!     assert_eq!(body, "That is some good Mallory.");
+//! }
+//! ```
+//!
 //! ## Matching using patterns
 //!
 //! You can also use patterns like `like!`, `each_like!` or `term!` to allow
@@ -154,6 +179,9 @@
 //! fixtures. This can be done using the string passed to `given` in the
 //! examples above.
 //!
+//! XML bodies can use the same patterns, built with the `xml!` macro and
+//! passed to `xml_body`.
+//!
 //! ## Testing using domain objects
 //!
 //! Normally, it's best to generate your JSON using your actual domain objects.
@@ -220,6 +248,7 @@ extern crate maplit;
 #[allow(unused_imports)] #[macro_use]
 extern crate pact_matching;
 extern crate pact_mock_server;
+extern crate pact_consumer_macros;
 extern crate regex;
 #[cfg(test)]
 #[macro_use]
@@ -243,6 +272,8 @@ pub mod builders;
 pub mod mock_server;
 pub mod util;
 
+pub use pact_consumer_macros::pact_test;
+
 /// A "prelude" or a default list of import types to include. This includes
 /// the basic DSL, but it avoids including rarely-used types.
 ///
@@ -251,8 +282,8 @@ pub mod util;
 /// ```
 pub mod prelude {
     pub use builders::{HttpPartBuilder, PactBuilder};
-    pub use patterns::{Pattern, JsonPattern, StringPattern};
-    pub use patterns::{EachLike, Like, Term};
-    pub use mock_server::{StartMockServer, ValidatingMockServer};
+    pub use patterns::{Pattern, JsonPattern, StringPattern, XmlElement, XmlPattern};
+    pub use patterns::{EachLike, Like, PathTemplate, Term};
+    pub use mock_server::{MismatchReport, ShutdownError, StartMockServer, ValidatingMockServer};
     pub use util::strip_null_fields;
 }
@@ -3,9 +3,16 @@
 use pact_matching::models::*;
 use pact_mock_server::*;
 use std::{
-  fmt::Write as FmtWrite,
+  error::Error,
+  fmt,
   env,
+  future::Future,
   io::{self, prelude::*},
+  panic::{self, AssertUnwindSafe},
+  path::PathBuf,
+  pin::Pin,
+  sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
+  task::{Context, Poll, Waker},
   thread
 };
 use url::Url;
@@ -15,20 +22,132 @@ use uuid::Uuid;
 pub trait StartMockServer {
     /// Start a mock server running.
     fn start_mock_server(&self) -> ValidatingMockServer;
+
+    /// Start a mock server running on another thread, so that an async
+    /// runtime is not blocked while it starts. The default implementation
+    /// calls `start_mock_server` on the current thread, and returns a task
+    /// which has already completed.
+    fn start_mock_server_async(&self) -> BlockingTask<ValidatingMockServer> {
+        BlockingTask::ready(self.start_mock_server())
+    }
 }
 
 impl StartMockServer for Pact {
     fn start_mock_server(&self) -> ValidatingMockServer {
         ValidatingMockServer::new(self.clone())
     }
+
+    fn start_mock_server_async(&self) -> BlockingTask<ValidatingMockServer> {
+        let pact = self.clone();
+        BlockingTask::spawn(move || ValidatingMockServer::new(pact))
+    }
+}
+
+/// Where and how a `ValidatingMockServer` writes out its pact.
+#[derive(Debug, Clone)]
+pub struct PactOutput {
+    /// The directory to write the pact file to. If this is not set, we use
+    /// the `PACT_OUTPUT_DIR` environment variable, or `target/pacts`.
+    pub dir: Option<PathBuf>,
+    /// The version of the pact specification to write the pact as.
+    pub specification: PactSpecification,
+    /// Whether to merge the pact into an existing pact file, or replace it.
+    pub mode: PactWriteMode,
+}
+
+impl PactOutput {
+    /// The path of the file to write `pact` to.
+    fn path(&self, pact: &Pact) -> PathBuf {
+        let dir = self.dir.clone().unwrap_or_else(|| {
+            PathBuf::from(env::var("PACT_OUTPUT_DIR").unwrap_or("target/pacts".to_owned()))
+        });
+        dir.join(pact.default_file_name())
+    }
+}
+
+impl Default for PactOutput {
+    fn default() -> PactOutput {
+        PactOutput {
+            dir: None,
+            specification: PactSpecification::V3,
+            mode: PactWriteMode::Merge,
+        }
+    }
+}
+
+/// The mismatches found when verifying a mock server.
+#[derive(Debug, Clone)]
+pub struct MismatchReport {
+    /// A description of the mock server, as `consumer/provider`.
+    pub description: String,
+    /// The requests which did not match an interaction, and the interactions
+    /// which were expected but never occurred.
+    pub mismatches: Vec<MatchResult>,
+}
+
+impl fmt::Display for MismatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "mock server {} failed verification:", self.description)?;
+        for mismatch in &self.mismatches {
+            match *mismatch {
+                MatchResult::RequestMatch(_) => {
+                    unreachable!("list of mismatches contains a match");
+                }
+                MatchResult::RequestMismatch(ref interaction, ref mismatches) => {
+                    writeln!(f, "- interaction {:?}:", interaction.description)?;
+                    for m in mismatches {
+                        writeln!(f, "  - {}", m.description())?;
+                    }
+                }
+                MatchResult::RequestNotFound(ref request) => {
+                    writeln!(f, "- received unexpected request:")?;
+                    writeln!(f, "{:#?}", request)?;
+                }
+                MatchResult::MissingRequest(ref interaction) => {
+                    writeln!(
+                        f,
+                        "- interaction {:?} expected, but never occurred",
+                        interaction.description,
+                    )?;
+                    writeln!(f, "{:#?}", interaction.request)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for MismatchReport {}
+
+/// The ways that shutting down a `ValidatingMockServer` can fail.
+#[derive(Debug, Clone)]
+pub enum ShutdownError {
+    /// The mock server received requests which did not match the pact, so
+    /// the pact was not written out.
+    Mismatches(MismatchReport),
+    /// The requests matched, but the pact could not be written out.
+    WritePact(String),
+}
+
+impl fmt::Display for ShutdownError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShutdownError::Mismatches(ref report) => report.fmt(f),
+            ShutdownError::WritePact(ref msg) => write!(f, "{}", msg),
+        }
+    }
 }
 
+impl Error for ShutdownError {}
+
 /// A mock HTTP server that handles the requests described in a `Pact`, intended
 /// for use in tests, and validates that the requests made to that server are
 /// correct.
 ///
 /// Because this is intended for use in tests, it will panic if something goes
-/// wrong.
+/// wrong. When it is dropped, it verifies the requests it received, and then
+/// either writes out the pact or panics. Call `verify` to check the requests
+/// yourself, and `shutdown` to finish without panicking.
 pub struct ValidatingMockServer {
     // A description of our mock server, for use in error messages.
     description: String,
@@ -36,15 +155,33 @@ pub struct ValidatingMockServer {
     port: i32,
     // The URL of our mock server.
     url: Url,
+    // The pact we write out if verification succeeds.
+    pact: Pact,
+    // Where and how we write out the pact.
+    output: PactOutput,
+    // Set once we have been shut down, so that `drop` has nothing to do.
+    finished: AtomicBool,
 }
 
 impl ValidatingMockServer {
     /// Create a new mock server which handles requests as described in the
     /// pact.
     pub fn new(pact: Pact) -> ValidatingMockServer {
+        ValidatingMockServer::with_output(pact, PactOutput::default())
+    }
+
+    /// Create a new mock server which handles requests as described in the
+    /// pact, and writes out the pact as described by `output`.
+    pub fn with_output(pact: Pact, output: PactOutput) -> ValidatingMockServer {
+        ValidatingMockServer::with_config(pact, output, MatchingConfig::default())
+    }
+
+    /// Create a new mock server like `with_output`, which matches the requests
+    /// it receives as described by `config`.
+    pub fn with_config(pact: Pact, output: PactOutput, config: MatchingConfig) -> ValidatingMockServer {
         let description = format!("{}/{}", pact.consumer.name, pact.provider.name);
         let uuid = Uuid::new_v4().simple().to_string();
-        let port = start_mock_server(uuid, pact, 0)
+        let port = start_mock_server_with_config(uuid, pact.clone(), 0, config)
             .expect("error starting mock server");
         let url_str = lookup_mock_server_by_port(port, &|ms| ms.url())
             .expect("could not find mock server");
@@ -52,6 +189,9 @@ impl ValidatingMockServer {
             description,
             port,
             url: url_str.parse().expect("invalid mock server URL"),
+            pact,
+            output,
+            finished: AtomicBool::new(false),
         }
     }
 
@@ -72,58 +212,79 @@ impl ValidatingMockServer {
         self.url.join(path.as_ref()).expect("could not parse URL")
     }
 
+    /// Check the requests this mock server has received so far, returning a
+    /// report of any mismatches.
+    pub fn verify(&self) -> Result<(), MismatchReport> {
+        let mismatches = self.mismatches().unwrap_or_else(|msg| panic!("{}", msg));
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(MismatchReport {
+                description: self.description.clone(),
+                mismatches,
+            })
+        }
+    }
+
+    /// Verify the requests this mock server has received, write out the pact
+    /// if there were no mismatches, and shut the mock server down. Unlike
+    /// dropping the mock server, this returns any mismatches, or the error
+    /// from writing the pact, instead of panicking.
+    pub fn shutdown(self) -> Result<(), ShutdownError> {
+        self.finish()
+    }
+
+    /// Like `shutdown`, but runs on another thread, so that an async runtime
+    /// is not blocked while the pact is written.
+    pub fn shutdown_async(self) -> BlockingTask<Result<(), ShutdownError>> {
+        BlockingTask::spawn(move || self.finish())
+    }
+
+    fn mismatches(&self) -> Result<Vec<MatchResult>, String> {
+        lookup_mock_server_by_port(self.port, &|ms| ms.mismatches())
+            .ok_or_else(|| "unable to find mock server".to_owned())
+    }
+
+    fn write_pact(&self) -> Result<(), String> {
+        let path = self.output.path(&self.pact);
+        self.pact.write_pact_with_mode(&path, self.output.specification.clone(), self.output.mode)
+            .map_err(|err| format!("error writing pact to {}: {}", path.display(), err))
+    }
+
+    /// Verify, write out the pact if there were no mismatches, and shut down.
+    fn finish(&self) -> Result<(), ShutdownError> {
+        self.finished.store(true, Ordering::SeqCst);
+        let result = self.verify()
+            .map_err(ShutdownError::Mismatches)
+            .and_then(|()| self.write_pact().map_err(ShutdownError::WritePact));
+        shutdown_mock_server_by_port(self.port);
+        result
+    }
+
+    /// Shut down without verifying or writing out the pact, because the test
+    /// failed or was cancelled.
+    pub(crate) fn abandon(&self) {
+        if !self.finished.swap(true, Ordering::SeqCst) {
+            shutdown_mock_server_by_port(self.port);
+        }
+    }
+
     /// Helper function called by our `drop` implementation. This basically exists
     /// so that it can return `Err(message)` whenever needed without making the
     /// flow control in `drop` ultra-complex.
     fn drop_helper(&mut self) -> Result<(), String> {
         // Look up any mismatches which occurred.
-        let mismatches = lookup_mock_server_by_port(self.port, &|ms| ms.mismatches())
-            .ok_or_else(|| "unable to find mock server".to_owned())?;
+        let mismatches = self.mismatches()?;
 
         if mismatches.is_empty() {
             // Success! Write out the generated pact file.
-            lookup_mock_server_by_port(self.port, &|ms| {
-                ms.write_pact(&Some(env::var("PACT_OUTPUT_DIR").unwrap_or("target/pacts".to_owned())))
-            })
-                .ok_or_else(|| "unable to find mock server".to_owned())?
-                .map_err(|err| format!("error writing pact: {}", err))?;
-            Ok(())
+            self.write_pact()
         } else {
             // Failure. Format our errors.
-            let mut msg = format!(
-                "mock server {} failed verification:\n",
-                self.description,
-            );
-            for mismatch in mismatches {
-                match mismatch {
-                    MatchResult::RequestMatch(_) => {
-                        unreachable!("list of mismatches contains a match");
-                    }
-                    MatchResult::RequestMismatch(interaction, mismatches) => {
-                        let _ = writeln!(
-                            &mut msg,
-                            "- interaction {:?}:",
-                            interaction.description,
-                        );
-                        for m in mismatches {
-                            let _ = writeln!(&mut msg, "  - {}", m.description());
-                        }
-                    }
-                    MatchResult::RequestNotFound(request) => {
-                        let _ = writeln!(&mut msg, "- received unexpected request:");
-                        let _ = writeln!(&mut msg, "{:#?}", request);
-                    }
-                    MatchResult::MissingRequest(interaction) => {
-                        let _ = writeln!(
-                            &mut msg,
-                            "- interaction {:?} expected, but never occurred",
-                            interaction.description,
-                        );
-                        let _ = writeln!(&mut msg, "{:#?}", interaction.request);
-                    }
-                }
-            }
-            Err(msg)
+            Err(MismatchReport {
+                description: self.description.clone(),
+                mismatches,
+            }.to_string())
         }
     }
 }
@@ -144,6 +305,9 @@ fn panic_or_print_error(msg: &str) {
 
 impl Drop for ValidatingMockServer {
     fn drop(&mut self) {
+        if self.finished.load(Ordering::SeqCst) {
+            return;
+        }
         let result = self.drop_helper();
         shutdown_mock_server_by_port(self.port);
         if let Err(msg) = result {
@@ -151,3 +315,246 @@ impl Drop for ValidatingMockServer {
         }
     }
 }
+
+/// A future for work which blocks, like starting a mock server, which runs the
+/// work on its own thread so that it doesn't hold up an async runtime. If the
+/// work panics, the panic is passed on to whoever awaits the result.
+pub struct BlockingTask<T> {
+    state: Arc<Mutex<BlockingTaskState<T>>>,
+}
+
+struct BlockingTaskState<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+impl<T: Send + 'static> BlockingTask<T> {
+    pub(crate) fn spawn<F>(work: F) -> BlockingTask<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(BlockingTaskState { result: None, waker: None }));
+        let thread_state = state.clone();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(work));
+            let mut state = thread_state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        BlockingTask { state }
+    }
+
+    pub(crate) fn ready(value: T) -> BlockingTask<T> {
+        let state = BlockingTaskState { result: Some(Ok(value)), waker: None };
+        BlockingTask { state: Arc::new(Mutex::new(state)) }
+    }
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        let result = {
+            let mut state = self.state.lock().unwrap();
+            if state.result.is_none() {
+                state.waker = Some(cx.waker().clone());
+            }
+            state.result.take()
+        };
+        match result {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(panic)) => panic::resume_unwind(panic),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// The future returned by `PactBuilder::run`. It runs the test, and then
+/// verifies the mock server, writes out the pact if there were no mismatches,
+/// and shuts the mock server down. If the test panics or is dropped before it
+/// completes, the mock server is shut down without writing the pact.
+pub struct PactTest<Fut> {
+    server: Option<Arc<ValidatingMockServer>>,
+    test: Pin<Box<Fut>>,
+}
+
+impl<Fut: Future> PactTest<Fut> {
+    pub(crate) fn new<F>(server: ValidatingMockServer, test: F) -> PactTest<Fut>
+    where
+        F: FnOnce(Arc<ValidatingMockServer>) -> Fut,
+    {
+        let server = Arc::new(server);
+        let test = Box::pin(test(server.clone()));
+        PactTest { server: Some(server), test }
+    }
+}
+
+impl<Fut: Future> Future for PactTest<Fut> {
+    type Output = Result<Fut::Output, ShutdownError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let output = match self.test.as_mut().poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        let server = self.server.take().expect("PactTest polled after it completed");
+        Poll::Ready(server.finish().map(|()| output))
+    }
+}
+
+impl<Fut> Drop for PactTest<Fut> {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.abandon();
+        }
+    }
+}
+
+#[test]
+fn verify_reports_mismatches_without_panicking() {
+    use prelude::*;
+
+    let server = PactBuilder::new("C", "P")
+        .interaction("I", |i| { i.request.path("/expected"); })
+        .start_mock_server();
+    let report = server.verify().unwrap_err();
+    assert_eq!(report.description, "C/P");
+    match report.mismatches[..] {
+        [MatchResult::MissingRequest(ref interaction)] => assert_eq!(interaction.description, "I"),
+        ref mismatches => panic!("unexpected mismatches {:?}", mismatches),
+    }
+    assert!(report.to_string().contains("- interaction \"I\" expected, but never occurred"));
+    match server.shutdown() {
+        Err(ShutdownError::Mismatches(ref report)) => assert_eq!(report.mismatches.len(), 1),
+        ref other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn run_writes_the_pact_only_on_success() {
+    use std::fs;
+    use prelude::*;
+    use test_support::block_on;
+
+    let output_dir = env::temp_dir().join(format!("pact_consumer_run_{}", Uuid::new_v4().simple()));
+
+    let result = block_on(PactBuilder::new("C", "Passing")
+        .output_dir(&output_dir)
+        .run(|_| ::std::future::ready(42)));
+    assert_eq!(result.unwrap(), 42);
+    assert!(output_dir.join("C-Passing.json").exists());
+
+    let result = block_on(PactBuilder::new("C", "Failing")
+        .output_dir(&output_dir)
+        .interaction("I", |i| { i.request.path("/expected"); })
+        .run(|server| ::std::future::ready(server.path("/expected"))));
+    let exists = output_dir.join("C-Failing.json").exists();
+    fs::remove_dir_all(&output_dir).unwrap_or(());
+    match result {
+        Err(ShutdownError::Mismatches(report)) => assert_eq!(report.mismatches.len(), 1),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(!exists);
+}
+
+#[test]
+fn shutdown_returns_pact_write_failures() {
+    use std::fs::File;
+    use prelude::*;
+
+    // A file where the output directory should be, so the pact can't be written.
+    let output_dir = env::temp_dir().join(format!("pact_consumer_not_a_dir_{}", Uuid::new_v4().simple()));
+    File::create(&output_dir).unwrap();
+    let result = PactBuilder::new("C", "P")
+        .output_dir(&output_dir)
+        .start_mock_server()
+        .shutdown();
+    ::std::fs::remove_file(&output_dir).unwrap_or(());
+    match result {
+        Err(ShutdownError::WritePact(msg)) => assert!(msg.contains("error writing pact")),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn mock_servers_can_be_started_and_shut_down_asynchronously() {
+    use prelude::*;
+    use test_support::block_on;
+
+    let server = block_on(PactBuilder::new("C", "P").start_mock_server_async());
+    assert!(server.url().as_str().starts_with("http://"));
+    assert!(block_on(server.shutdown_async()).is_ok());
+}
+
+#[test]
+fn pact_output_is_configured_by_the_builder() {
+    use std::fs;
+    use prelude::*;
+
+    let output_dir = env::temp_dir().join(format!("pact_consumer_output_{}", Uuid::new_v4().simple()));
+    let path = output_dir.join("C-P.json");
+    PactBuilder::new("C", "P")
+        .interaction("I", |_| {})
+        .build()
+        .write_pact(&path, PactSpecification::V3)
+        .unwrap();
+
+    PactBuilder::new("C", "P")
+        .output_dir(&output_dir)
+        .specification(PactSpecification::V2)
+        .write_mode(PactWriteMode::Overwrite)
+        .start_mock_server()
+        .shutdown()
+        .unwrap();
+
+    let written = Pact::read_pact(&path).unwrap();
+    fs::remove_dir_all(&output_dir).unwrap_or(());
+    assert!(written.interactions.is_empty());
+    assert_eq!(written.specification_version, PactSpecification::V2);
+}
+
+#[test]
+fn diff_config_is_configured_by_the_builder() {
+    use std::net::TcpStream;
+    use pact_matching::DiffConfig;
+    use prelude::*;
+
+    let server = PactBuilder::new("C", "P")
+        .diff_config(DiffConfig::AllowUnexpectedKeys)
+        .interaction("lenient", |i| {
+            i.request.method("POST").path("/lenient").json_body(json_pattern!({ "a": 1 }));
+        })
+        .interaction("strict", |i| {
+            i.diff_config(DiffConfig::NoUnexpectedKeys);
+            i.request.method("POST").path("/strict").json_body(json_pattern!({ "a": 1 }));
+        })
+        .start_mock_server();
+    for path in &["/lenient", "/strict"] {
+        let body = r#"{"a":1,"b":2}"#;
+        let mut stream = TcpStream::connect((server.url().host_str().unwrap(), server.url().port().unwrap()))
+            .expect("could not connect to mock server");
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path, body.len(), body,
+        ).unwrap();
+        stream.read_to_end(&mut vec![]).unwrap();
+    }
+
+    // Only the strict interaction fails.
+    let report = match server.shutdown() {
+        Err(ShutdownError::Mismatches(report)) => report,
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert!(!report.mismatches.is_empty());
+    for mismatch in &report.mismatches {
+        match *mismatch {
+            MatchResult::RequestNotFound(ref request) => assert_eq!(request.path, "/strict"),
+            MatchResult::RequestMismatch(ref interaction, _) |
+            MatchResult::MissingRequest(ref interaction) => assert_eq!(interaction.description, "strict"),
+            MatchResult::RequestMatch(_) => unreachable!(),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! Our `JsonPattern` type and supporting code.
 
+use pact_matching::models::generators::{GeneratorCategory, Generators};
 use pact_matching::models::matchingrules::Category;
 use serde_json;
 use std::borrow::Cow;
@@ -93,6 +94,32 @@ impl Pattern for JsonPattern {
             }
         }
     }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        match *self {
+            JsonPattern::Json(_) => {}
+            JsonPattern::Array(ref arr) => {
+                for (i, val) in arr.into_iter().enumerate() {
+                    let val_path = format!("{}[{}]", path, i);
+                    val.extract_generators(&val_path, category, generators_out);
+                }
+            }
+            JsonPattern::Object(ref obj) => {
+                for (key, val) in obj {
+                    let val_path = format!("{}{}", path, obj_key_for_path(key));
+                    val.extract_generators(&val_path, category, generators_out);
+                }
+            }
+            JsonPattern::Pattern(ref pattern) => {
+                pattern.extract_generators(path, category, generators_out);
+            }
+        }
+    }
 }
 
 #[test]
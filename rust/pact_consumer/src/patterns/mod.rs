@@ -1,6 +1,7 @@
-//! JSON "patterns", which can be used to either generate JSON documents or
+//! JSON and XML "patterns", which can be used to either generate documents or
 //! match them.
 
+use pact_matching::models::generators::{GeneratorCategory, Generators};
 use pact_matching::models::matchingrules::Category;
 use std::fmt::Debug;
 
@@ -9,10 +10,21 @@ mod json_macros;
 mod json_pattern;
 #[macro_use]
 mod special_rules;
+#[macro_use]
+mod path_template;
+#[macro_use]
+mod typed_rules;
+#[macro_use]
+mod xml_pattern;
+mod status_pattern;
 mod string_pattern;
 
 pub use self::json_pattern::*;
 pub use self::special_rules::*;
+pub use self::path_template::*;
+pub use self::typed_rules::*;
+pub use self::xml_pattern::*;
+pub use self::status_pattern::*;
 pub use self::string_pattern::*;
 
 /// Abstract interface to types which can:
@@ -24,9 +36,9 @@ pub use self::string_pattern::*;
 /// For an overview of how the matching rules work, and what kinds of special
 /// matching rules exist, see the [`pact_matching` documentation][spec].
 ///
-/// The current version of this API will only work for `JsonPattern` and
-/// `serde_json::Value`. Extending this scheme to work for XML would require
-/// parameterizing the input and output types, and possibly other changes.
+/// Patterns match `serde_json::Value` (`JsonPattern`), strings
+/// (`StringPattern` for headers and query parameters, and `XmlPattern` for XML
+/// documents), or response status codes (`u16` and `StatusCode`).
 ///
 /// [spec]: https://docs.rs/pact_matching/0.2.2/pact_matching/
 pub trait Pattern: Debug {
@@ -51,4 +63,19 @@ pub trait Pattern: Debug {
     /// [ruby]:
     /// https://github.com/pact-foundation/pact-support/blob/master/lib/pact/matching_rules/extract.rb
     fn extract_matching_rules(&self, path: &str, rules_out: &mut Category);
+
+    /// Extract the generators from this `Matchable`, and insert them into
+    /// `generators_out` under `category`, using `path` as the base path (in
+    /// the same way as `extract_matching_rules`).
+    ///
+    /// The default implementation does nothing, which is right for patterns
+    /// without generators. Patterns which contain other patterns need to pass
+    /// the call on to them.
+    fn extract_generators(
+        &self,
+        _path: &str,
+        _category: &GeneratorCategory,
+        _generators_out: &mut Generators,
+    ) {
+    }
 }
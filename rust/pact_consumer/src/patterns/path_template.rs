@@ -0,0 +1,296 @@
+//! Support for path templates like `/orders/{id}/items/{itemId}`, where each
+//! variable can have its own matching rule.
+
+use pact_matching::models::generators::{GeneratorCategory, Generators};
+use pact_matching::models::matchingrules::{MatchingRule, Category, RuleLogic};
+use regex::quote;
+use std::collections::HashMap;
+
+use super::Pattern;
+use super::string_pattern::StringPattern;
+
+/// A part of a parsed path template.
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    /// Literal text, which must match exactly.
+    Literal(String),
+    /// A named variable, written as `{name}` in the template.
+    Variable(String),
+}
+
+/// Splits a template into its literal and variable parts. Panics if the
+/// template has an unterminated or empty variable.
+fn parse_template(template: &str) -> Vec<TemplatePart> {
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => panic!("path template {:?} has an unterminated variable", template),
+        };
+        let name = rest[start + 1..end].trim();
+        if name.is_empty() {
+            panic!("path template {:?} has a variable with no name", template);
+        }
+        if start > 0 {
+            parts.push(TemplatePart::Literal(rest[..start].to_string()));
+        }
+        parts.push(TemplatePart::Variable(name.to_string()));
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(TemplatePart::Literal(rest.to_string()));
+    }
+    parts
+}
+
+/// Returns the byte offsets of the `^` and `$` anchors in a regex, skipping
+/// escaped characters and character classes (like `[^/]`).
+fn anchor_positions(regex: &str) -> Vec<usize> {
+    let mut positions = vec![];
+    let mut class_depth = 0;
+    let mut chars = regex.char_indices();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\\' => { chars.next(); },
+            '[' => class_depth += 1,
+            ']' if class_depth > 0 => class_depth -= 1,
+            '^' | '$' if class_depth == 0 => positions.push(i),
+            _ => (),
+        }
+    }
+    positions
+}
+
+/// Removes the `^` and `$` anchors from the start and end of a variable's
+/// regex, so it can be embedded in the regex for the whole path. Panics if
+/// the regex has anchors anywhere else (like `^a$|^b$`), as they can not be
+/// removed without changing what the regex matches.
+fn embeddable_regex(name: &str, regex: &str) -> String {
+    let mut anchors = anchor_positions(regex);
+    let start = if anchors.first() == Some(&0) { anchors.remove(0); 1 } else { 0 };
+    let end = if !regex.is_empty() && anchors.last() == Some(&(regex.len() - 1)) {
+        anchors.pop();
+        regex.len() - 1
+    } else {
+        regex.len()
+    };
+    if !anchors.is_empty() {
+        panic!("regex {:?} for path template variable '{}' can only have anchors at its start and end",
+            regex, name);
+    }
+    format!("(?:{})", &regex[start..end.max(start)])
+}
+
+/// Returns the regex that a variable's value must match, based on the matching
+/// rule of its pattern. Values without a rule must match the example exactly.
+fn variable_regex(name: &str, example: &str, rule: Option<&MatchingRule>) -> String {
+    match rule {
+        None | Some(MatchingRule::Equality) => quote(example),
+        Some(MatchingRule::Regex(regex)) => embeddable_regex(name, regex),
+        Some(MatchingRule::Type) => s!("[^/]+"),
+        Some(MatchingRule::Include(value)) => format!("[^/]*{}[^/]*", quote(value)),
+        Some(MatchingRule::Integer) => s!("-?\\d+"),
+        Some(MatchingRule::Decimal) => s!("-?\\d+\\.\\d+"),
+        Some(MatchingRule::Number) => s!("-?\\d+(?:\\.\\d+)?"),
+        Some(rule) => panic!("{:?} can not be used for path template variable '{}'", rule, name),
+    }
+}
+
+/// Match and generate request paths from a template like
+/// `/orders/{id}/items/{itemId}`. Each variable is given a pattern which
+/// generates its example value and which determines how that part of the path
+/// is matched.
+///
+/// As the pact specification only supports a single matching rule for the
+/// path, the template is written to the pact file as one regex rule for the
+/// whole path, so other implementations can verify it.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # use pact_consumer::prelude::*;
+/// # fn main() {
+/// let template = PathTemplate::new("/orders/{id}/items/{itemId}")
+///     .var("id", term!("^[0-9]+$", "1001"))
+///     .var("itemId", Like::new("abc".to_owned()));
+/// assert_eq!(template.to_example(), "/orders/1001/items/abc");
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PathTemplate {
+    template: String,
+    parts: Vec<TemplatePart>,
+    variables: HashMap<String, StringPattern>,
+}
+
+impl PathTemplate {
+    /// Construct a new `PathTemplate`. Variables are written in the template
+    /// as `{name}`, and their values are set with `var`. Panics if the
+    /// template is invalid.
+    pub fn new<S: Into<String>>(template: S) -> Self {
+        let template = template.into();
+        let parts = parse_template(&template);
+        PathTemplate {
+            template,
+            parts,
+            variables: HashMap::new(),
+        }
+    }
+
+    /// Set the pattern for the variable `name`. Panics if the template does
+    /// not have a variable with that name, or if the pattern has a generator
+    /// (like `uuid!()` or `from_provider_state!`). Generators for the path
+    /// replace the whole path, so they can not be used for one variable.
+    pub fn var<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<StringPattern>,
+    {
+        let name = name.into();
+        if !self.parts.contains(&TemplatePart::Variable(name.clone())) {
+            panic!("path template {:?} has no variable '{}'", self.template, name);
+        }
+        let value = value.into();
+        let mut generators = Generators::default();
+        value.extract_generators("", &GeneratorCategory::PATH, &mut generators);
+        if !generators.is_empty() {
+            panic!("variable '{}' of path template {:?} has a generator, which is not supported",
+                name, self.template);
+        }
+        self.variables.insert(name, value);
+        self
+    }
+
+    fn variable(&self, name: &str) -> &StringPattern {
+        match self.variables.get(name) {
+            Some(value) => value,
+            None => panic!("no value was given for variable '{}' of path template {:?}",
+                name, self.template),
+        }
+    }
+
+    /// Returns the regex that matches all the paths for this template.
+    pub fn to_regex(&self) -> String {
+        let mut regex = s!("^");
+        for part in &self.parts {
+            match *part {
+                TemplatePart::Literal(ref text) => regex.push_str(&quote(text)),
+                TemplatePart::Variable(ref name) => {
+                    let value = self.variable(name);
+                    let mut rules = Category::default("path");
+                    value.extract_matching_rules("", &mut rules);
+                    // Nested patterns add their rules after the enclosing
+                    // ones, so the last rule is the most specific.
+                    let rule = rules.rules.get("").and_then(|rules| rules.rules.last());
+                    regex.push_str(&variable_regex(name, &value.to_example(), rule));
+                }
+            }
+        }
+        regex.push('$');
+        regex
+    }
+}
+
+impl Pattern for PathTemplate {
+    type Matches = String;
+
+    fn to_example(&self) -> String {
+        self.parts.iter().map(|part| match *part {
+            TemplatePart::Literal(ref text) => text.clone(),
+            TemplatePart::Variable(ref name) => self.variable(name).to_example(),
+        }).collect()
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(
+            &path.to_string(),
+            MatchingRule::Regex(self.to_regex()),
+            &RuleLogic::And
+        );
+    }
+}
+
+impl_from_for_pattern!(PathTemplate, StringPattern);
+
+/// Builds a `PathTemplate` from a template and the patterns for each of its
+/// variables.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # use pact_consumer::prelude::*;
+/// # fn main() {
+/// let template = path_template!("/orders/{id}/items/{itemId}",
+///     id => term!("^[0-9]+$", "1001"),
+///     itemId => "abc");
+/// assert_eq!(template.to_example(), "/orders/1001/items/abc");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! path_template {
+    ($template:expr $(, $name:ident => $value:expr)* $(,)*) => {
+        {
+            $crate::patterns::PathTemplate::new($template)
+                $(.var(stringify!($name), $value))*
+        }
+    }
+}
+
+#[test]
+fn path_template_is_pattern() {
+    use super::special_rules::Like;
+
+    let matchable = path_template!("/orders/{id}/items/{itemId}",
+        id => term!("^[0-9]+$", "1001"),
+        itemId => Like::new("abc".to_owned()));
+    assert_eq!(matchable.to_example(), "/orders/1001/items/abc");
+
+    let mut rules = Category::default("path");
+    matchable.extract_matching_rules("", &mut rules);
+    let expected_rules = hashmap!(
+        s!("$.path.") => json!({ "match": "regex", "regex": "^/orders/(?:[0-9]+)/items/[^/]+$" })
+    );
+    assert_eq!(rules.to_v2_json(), expected_rules);
+}
+
+#[test]
+fn path_template_quotes_literals_and_plain_values() {
+    let matchable = path_template!("/files/{name}.json", name => "a+b");
+    assert_eq!(matchable.to_example(), "/files/a+b.json");
+    assert_eq!(matchable.to_regex(), "^/files/a\\+b\\.json$");
+}
+
+#[test]
+fn path_template_only_removes_the_outer_anchors_of_variable_regexes() {
+    let matchable = path_template!("/files/{name}",
+        name => term!("^(?:[^/]+\\.json|a\\$)$", "a.json"));
+    assert_eq!(matchable.to_regex(), "^/files/(?:(?:[^/]+\\.json|a\\$))$");
+
+    let matchable = path_template!("/files/{name}", name => term!("a|b", "a"));
+    assert_eq!(matchable.to_regex(), "^/files/(?:a|b)$");
+}
+
+#[test]
+#[should_panic(expected = "can only have anchors at its start and end")]
+fn path_template_rejects_variable_regexes_with_inner_anchors() {
+    path_template!("/files/{name}", name => term!("^a$|^b$", "a")).to_regex();
+}
+
+#[test]
+#[should_panic]
+fn path_template_requires_known_variables() {
+    path_template!("/orders/{id}", orderId => "1");
+}
+
+#[test]
+#[should_panic]
+fn path_template_requires_values_for_all_variables() {
+    path_template!("/orders/{id}").to_example();
+}
+
+#[test]
+#[should_panic(expected = "has a generator, which is not supported")]
+fn path_template_rejects_variables_with_generators() {
+    use super::typed_rules::FromProviderState;
+
+    path_template!("/orders/{id}", id => FromProviderState::<StringPattern>::new("${id}", "1001"));
+}
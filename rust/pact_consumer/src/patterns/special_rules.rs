@@ -1,14 +1,15 @@
 //! Special matching rules, including `Like`, `Term`, etc.
 
+use pact_matching::models::generators::{GeneratorCategory, Generators};
 use pact_matching::models::matchingrules::{MatchingRule, Category, RuleLogic};
 use regex::Regex;
 use serde_json;
-use std::iter::repeat;
 use std::marker::PhantomData;
 
 use super::Pattern;
 use super::json_pattern::JsonPattern;
 use super::string_pattern::StringPattern;
+use super::xml_pattern::XmlPattern;
 
 macro_rules! impl_from_for_pattern {
     ($from:ty, $pattern:ident) => {
@@ -44,6 +45,15 @@ impl<Nested: Pattern> Pattern for Like<Nested> {
         rules_out.add_rule(&path.to_string(), MatchingRule::Type, &RuleLogic::And);
         self.example.extract_matching_rules(path, rules_out);
     }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        self.example.extract_generators(path, category, generators_out);
+    }
 }
 
 impl_from_for_pattern!(Like<JsonPattern>, JsonPattern);
@@ -90,43 +100,85 @@ macro_rules! like {
     }
 }
 
-/// Match an array with the specified "shape".
+/// Match an array with the specified "shape". For XML, this matches the
+/// repeated child elements of an element (see `XmlPattern`).
 #[derive(Debug)]
-pub struct EachLike {
-    example_element: JsonPattern,
+pub struct EachLike<Nested: Pattern = JsonPattern> {
+    example_element: Nested,
     min_len: usize,
+    max_len: Option<usize>,
 }
 
-impl EachLike {
+impl<Nested: Pattern> EachLike<Nested> {
     /// Match arrays containing elements like `example_element`.
-    pub fn new(example_element: JsonPattern) -> EachLike {
+    pub fn new(example_element: Nested) -> EachLike<Nested> {
         EachLike {
             example_element,
             min_len: 1,
+            max_len: None,
         }
     }
 
     /// Use this after `new` to set a minimum length for the matching array.
-    pub fn with_min_len(mut self, min_len: usize) -> EachLike {
+    /// Panics if it is more than the maximum length.
+    pub fn with_min_len(mut self, min_len: usize) -> EachLike<Nested> {
         self.min_len = min_len;
+        self.check_bounds();
+        self
+    }
+
+    /// Use this after `new` to set a maximum length for the matching array.
+    /// Combined with `with_min_len`, this matches arrays with a length between
+    /// the two. Panics if it is zero, or less than the minimum length.
+    pub fn with_max_len(mut self, max_len: usize) -> EachLike<Nested> {
+        self.max_len = Some(max_len);
+        self.check_bounds();
         self
     }
+
+    fn check_bounds(&self) {
+        if let Some(max_len) = self.max_len {
+            if max_len == 0 {
+                panic!("the maximum length of an each_like must be at least 1");
+            }
+            if max_len < self.min_len {
+                panic!("the maximum length of an each_like ({}) is less than its minimum length ({})",
+                    max_len, self.min_len);
+            }
+        }
+    }
+
+    /// The number of elements in our example. We always generate at least one
+    /// element, so there is something to match the actual elements against.
+    fn example_len(&self) -> usize {
+        let len = self.min_len.max(1);
+        self.max_len.map_or(len, |max_len| len.min(max_len))
+    }
+
+    /// The rule which checks the length of the matching array.
+    fn length_rule(&self) -> MatchingRule {
+        match self.max_len {
+            None => MatchingRule::MinType(self.min_len),
+            Some(max_len) if self.min_len == 0 => MatchingRule::MaxType(max_len),
+            Some(max_len) => MatchingRule::MinMaxType(self.min_len, max_len),
+        }
+    }
 }
 
 impl_from_for_pattern!(EachLike, JsonPattern);
 
-impl Pattern for EachLike {
+impl Pattern for EachLike<JsonPattern> {
     type Matches = serde_json::Value;
 
     fn to_example(&self) -> serde_json::Value {
         let element = self.example_element.to_example();
-        serde_json::Value::Array(repeat(element).take(self.min_len).collect())
+        serde_json::Value::Array(vec![element; self.example_len()])
     }
 
     fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
         rules_out.add_rule(
             &path.to_string(),
-            MatchingRule::MinType(self.min_len),
+            self.length_rule(),
             &RuleLogic::And
         );
         rules_out.add_rule(
@@ -140,6 +192,58 @@ impl Pattern for EachLike {
             rules_out,
         );
     }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        let new_path = format!("{}[*]", path);
+        self.example_element.extract_generators(
+            &new_path,
+            category,
+            generators_out,
+        );
+    }
+}
+
+impl Pattern for EachLike<XmlPattern> {
+    type Matches = String;
+
+    fn to_example(&self) -> String {
+        self.example_element.to_example().repeat(self.example_len())
+    }
+
+    /// The XML matcher treats the children of an element like an array, so
+    /// `path` is the path of the contents of the parent element.
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(
+            &path.to_string(),
+            self.length_rule(),
+            &RuleLogic::And
+        );
+        let new_path = format!("{}[*]", path);
+        rules_out.add_rule(&new_path, MatchingRule::Type, &RuleLogic::And);
+        self.example_element.extract_matching_rules(
+            &new_path,
+            rules_out,
+        );
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        let new_path = format!("{}[*]", path);
+        self.example_element.extract_generators(
+            &new_path,
+            category,
+            generators_out,
+        );
+    }
 }
 
 #[test]
@@ -164,6 +268,32 @@ fn each_like_is_pattern() {
     assert_eq!(rules.to_v2_json(), expected_rules);
 }
 
+#[test]
+fn each_like_with_max_len_is_pattern() {
+    let matchable = EachLike::new(json_pattern!("hello")).with_min_len(2).with_max_len(3);
+    assert_eq!(matchable.to_example(), json!(["hello", "hello"]));
+
+    let mut rules = Category::default("body");
+    matchable.extract_matching_rules("$", &mut rules);
+    assert_eq!(rules.to_v2_json().get("$.body"), Some(&json!({"match": "type", "min": 2, "max": 3})));
+    assert_eq!(rules.to_v3_json()["$"], json!({
+        "combine": "AND",
+        "matchers": [ { "match": "type", "min": 2, "max": 3 } ]
+    }));
+
+    let matchable = EachLike::new(json_pattern!("hello")).with_min_len(0).with_max_len(3);
+    assert_eq!(matchable.to_example(), json!(["hello"]));
+    let mut rules = Category::default("body");
+    matchable.extract_matching_rules("$", &mut rules);
+    assert_eq!(rules.to_v2_json().get("$.body"), Some(&json!({"match": "type", "max": 3})));
+}
+
+#[test]
+#[should_panic]
+fn each_like_max_len_must_not_be_less_than_min_len() {
+    EachLike::new(json_pattern!("hello")).with_min_len(3).with_max_len(2);
+}
+
 // A hidden macro which does the hard work of expanding `each_like!`. We don't
 // include this in the docs because it reveals a bunch of implementation
 // details.
@@ -202,6 +332,22 @@ macro_rules! each_like_helper {
             .with_min_len($min_len)
     };
 
+    // We're done parsing, and we did find `max`.
+    (@expand [$($pattern:tt)*] [max = $max_len:expr]) => {
+        $crate::patterns::EachLike::new(json_pattern!($($pattern)*))
+            .with_max_len($max_len)
+    };
+
+    // We're done parsing, and we found both `min` and `max`, in either order.
+    (@expand [$($pattern:tt)*] [min = $min_len:expr, max = $max_len:expr]) => {
+        $crate::patterns::EachLike::new(json_pattern!($($pattern)*))
+            .with_min_len($min_len)
+            .with_max_len($max_len)
+    };
+    (@expand [$($pattern:tt)*] [max = $max_len:expr, min = $min_len:expr]) => {
+        each_like_helper!(@expand [$($pattern)*] [min = $min_len, max = $max_len])
+    };
+
     // Entry point. Must come last, because it matches anything.
     ($($tokens:tt)+) => (each_like_helper!(@parse [] $($tokens)+));
 }
@@ -223,6 +369,12 @@ macro_rules! each_like_helper {
 ///   "people": each_like!({
 ///     "name": "J. Smith",
 ///   }, min=2),
+///
+///   // Expect between one and three addresses.
+///   "addresses": each_like!({ "city": "Cambridge" }, max=3),
+///
+///   // Expect between two and five phone numbers.
+///   "phones": each_like!("555-0100", min=2, max=5),
 /// });
 /// # }
 /// ```
@@ -249,6 +401,14 @@ fn each_like_macro_parsing() {
     let with_min = each_like!(json!(Point { x: 1, y: 2 }), min = 2 + 1);
     assert_eq!(with_min.example_element.to_example(), json!({ "x": 1, "y": 2 }));
     assert_eq!(with_min.min_len, 3);
+
+    let with_max = each_like!(json!(Point { x: 1, y: 2 }), max = 4);
+    assert_eq!(with_max.min_len, 1);
+    assert_eq!(with_max.max_len, Some(4));
+
+    let with_both = each_like!(json!(Point { x: 1, y: 2 }), max = 4, min = 2);
+    assert_eq!(with_both.min_len, 2);
+    assert_eq!(with_both.max_len, Some(4));
 }
 
 /// Match and generate strings that match a regular expression.
@@ -0,0 +1,129 @@
+//! Patterns which match the status code of a response.
+
+use pact_matching::models::matchingrules::{Category, HttpStatus, MatchingRule, RuleLogic};
+
+use super::Pattern;
+
+/// A literal status code matches and generates itself.
+impl Pattern for u16 {
+    type Matches = u16;
+
+    fn to_example(&self) -> u16 {
+        *self
+    }
+
+    fn extract_matching_rules(&self, _path: &str, _rules_out: &mut Category) {}
+}
+
+/// Match any status code in a class, like all successful responses, or any of
+/// a list of status codes.
+///
+/// ```
+/// use pact_consumer::builders::ResponseBuilder;
+/// use pact_consumer::patterns::StatusCode;
+///
+/// let response = ResponseBuilder::default()
+///     .status(StatusCode::client_error())
+///     .build();
+/// assert_eq!(response.status, 400);
+/// ```
+#[derive(Debug)]
+pub struct StatusCode {
+    status: HttpStatus,
+    example: u16,
+}
+
+impl StatusCode {
+    /// Match status codes in `status`, and generate `example`. Panics if
+    /// `example` is not in `status`.
+    pub fn new(status: HttpStatus, example: u16) -> Self {
+        if !status.matches(example) {
+            panic!("example {} is not a {:?} status code", example, status);
+        }
+        StatusCode { status, example }
+    }
+
+    /// Match informational responses (1xx), and generate `100`.
+    pub fn information() -> Self {
+        StatusCode::new(HttpStatus::Information, 100)
+    }
+
+    /// Match successful responses (2xx), and generate `200`.
+    pub fn success() -> Self {
+        StatusCode::new(HttpStatus::Success, 200)
+    }
+
+    /// Match redirects (3xx), and generate `302`.
+    pub fn redirect() -> Self {
+        StatusCode::new(HttpStatus::Redirect, 302)
+    }
+
+    /// Match client errors (4xx), and generate `400`.
+    pub fn client_error() -> Self {
+        StatusCode::new(HttpStatus::ClientError, 400)
+    }
+
+    /// Match server errors (5xx), and generate `500`.
+    pub fn server_error() -> Self {
+        StatusCode::new(HttpStatus::ServerError, 500)
+    }
+
+    /// Match any status code which is not an error (1xx to 3xx), and generate
+    /// `200`.
+    pub fn non_error() -> Self {
+        StatusCode::new(HttpStatus::NonError, 200)
+    }
+
+    /// Match any error (4xx and 5xx), and generate `500`.
+    pub fn error() -> Self {
+        StatusCode::new(HttpStatus::Error, 500)
+    }
+
+    /// Match any of `codes`, and generate the first one. Panics if `codes` is
+    /// empty.
+    pub fn codes(codes: &[u16]) -> Self {
+        let example = *codes.first().expect("no status codes given");
+        StatusCode::new(HttpStatus::StatusCodes(codes.to_vec()), example)
+    }
+
+    /// Generate `example` instead of the default example for this class.
+    /// Panics if `example` is not in the class.
+    pub fn with_example(self, example: u16) -> Self {
+        StatusCode::new(self.status, example)
+    }
+}
+
+impl Pattern for StatusCode {
+    type Matches = u16;
+
+    fn to_example(&self) -> u16 {
+        self.example
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(
+            &path.to_string(),
+            MatchingRule::StatusCode(self.status.clone()),
+            &RuleLogic::And
+        );
+    }
+}
+
+#[test]
+fn status_code_is_pattern() {
+    let pattern = StatusCode::success().with_example(201);
+    assert_eq!(pattern.to_example(), 201);
+
+    let mut rules = Category::default("status");
+    pattern.extract_matching_rules("", &mut rules);
+    assert_eq!(rules.to_v3_json(), json!({
+        "combine": "AND",
+        "matchers": [{ "match": "statusCode", "status": "success" }]
+    }));
+}
+
+#[test]
+#[should_panic]
+fn status_code_example_must_be_in_the_class() {
+    StatusCode::redirect().with_example(200);
+}
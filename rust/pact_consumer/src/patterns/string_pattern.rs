@@ -1,5 +1,6 @@
 //! Support for patterns which match only strings, not JSON.
 
+use pact_matching::models::generators::{GeneratorCategory, Generators};
 use pact_matching::models::matchingrules::Category;
 use std::borrow::Cow;
 
@@ -42,6 +43,20 @@ impl Pattern for StringPattern {
             }
         }
     }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        match *self {
+            StringPattern::String(_) => {},
+            StringPattern::Pattern(ref p) => {
+                p.extract_generators(path, category, generators_out);
+            }
+        }
+    }
 }
 
 #[test]
@@ -68,6 +83,12 @@ fn string_pattern_is_pattern() {
     assert_eq!(rules.to_v2_json(), expected_rules);
 }
 
+impl From<Box<Pattern<Matches = String>>> for StringPattern {
+    fn from(pattern: Box<Pattern<Matches = String>>) -> Self {
+        StringPattern::Pattern(pattern)
+    }
+}
+
 impl<'a> From<String> for StringPattern {
     fn from(s: String) -> Self {
         StringPattern::String(s)
@@ -0,0 +1,712 @@
+//! Matching rules which check the kind of value, like `Integer`, `Timestamp`,
+//! `Include`, etc. Some of these also have a generator, which is used to
+//! replace the example value with a new one when the pact is verified.
+
+use pact_matching::models::generators::{Generator, GeneratorCategory, Generators};
+use pact_matching::models::matchingrules::{MatchingRule, Category, RuleLogic};
+use serde_json;
+use std::marker::PhantomData;
+
+use super::Pattern;
+use super::json_pattern::JsonPattern;
+use super::string_pattern::StringPattern;
+
+/// The example used by `Uuid` when no example is given.
+const UUID_EXAMPLE: &str = "e2490de5-5bd3-43d5-b7c4-526e33f71304";
+
+/// The regex used by `Uuid` to match values.
+const UUID_REGEX: &str = "^[0-9a-f]{8}(-[0-9a-f]{4}){3}-[0-9a-f]{12}$";
+
+/// Generator for the patterns which are created without an example value.
+fn random_int() -> Generator {
+    Generator::RandomInt(0, i32::MAX)
+}
+
+/// Adds the generator of a pattern (if it has one) at `path`.
+fn add_generator(
+    generator: Option<Generator>,
+    path: &str,
+    category: &GeneratorCategory,
+    generators_out: &mut Generators,
+) {
+    if let Some(generator) = generator {
+        generators_out.add_generator_with_subcategory(category, path, generator);
+    }
+}
+
+/// Match any integer. Created with an example value, or with `Integer::random`
+/// to also generate a random value.
+#[derive(Debug)]
+pub struct Integer {
+    example: i64,
+    generator: Option<Generator>,
+}
+
+impl Integer {
+    /// Match all integers, and generate `example`.
+    pub fn new(example: i64) -> Self {
+        Integer { example, generator: None }
+    }
+
+    /// Match all integers, and generate random ones.
+    pub fn random() -> Self {
+        Integer { example: 100, generator: Some(random_int()) }
+    }
+
+    /// The generator for the value, if there is one.
+    pub fn generator(&self) -> Option<Generator> {
+        self.generator.clone()
+    }
+}
+
+impl Pattern for Integer {
+    type Matches = serde_json::Value;
+
+    fn to_example(&self) -> serde_json::Value {
+        serde_json::Value::from(self.example)
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(&path.to_string(), MatchingRule::Integer, &RuleLogic::And);
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        add_generator(self.generator(), path, category, generators_out);
+    }
+}
+
+impl_from_for_pattern!(Integer, JsonPattern);
+
+/// Match any decimal number (i.e. one with a fractional part). Created with an
+/// example value, or with `Decimal::random` to also generate a random value.
+#[derive(Debug)]
+pub struct Decimal {
+    example: f64,
+    generator: Option<Generator>,
+}
+
+impl Decimal {
+    /// Match all decimal numbers, and generate `example`.
+    pub fn new(example: f64) -> Self {
+        Decimal { example, generator: None }
+    }
+
+    /// Match all decimal numbers, and generate random ones.
+    pub fn random() -> Self {
+        Decimal { example: 100.0, generator: Some(Generator::RandomDecimal(10)) }
+    }
+
+    /// The generator for the value, if there is one.
+    pub fn generator(&self) -> Option<Generator> {
+        self.generator.clone()
+    }
+}
+
+impl Pattern for Decimal {
+    type Matches = serde_json::Value;
+
+    fn to_example(&self) -> serde_json::Value {
+        serde_json::Value::from(self.example)
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(&path.to_string(), MatchingRule::Decimal, &RuleLogic::And);
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        add_generator(self.generator(), path, category, generators_out);
+    }
+}
+
+impl_from_for_pattern!(Decimal, JsonPattern);
+
+/// Match any number, either integer or decimal. Created with an example value,
+/// or with `Number::random` to also generate a random value.
+#[derive(Debug)]
+pub struct Number {
+    example: serde_json::Value,
+    generator: Option<Generator>,
+}
+
+impl Number {
+    /// Match all numbers, and generate `example`. Panics if `example` is not a
+    /// number.
+    pub fn new<N: Into<serde_json::Value>>(example: N) -> Self {
+        let example = example.into();
+        if !example.is_number() {
+            panic!("{} is not a number", example);
+        }
+        Number { example, generator: None }
+    }
+
+    /// Match all numbers, and generate random ones.
+    pub fn random() -> Self {
+        Number { example: serde_json::Value::from(100), generator: Some(random_int()) }
+    }
+
+    /// The generator for the value, if there is one.
+    pub fn generator(&self) -> Option<Generator> {
+        self.generator.clone()
+    }
+}
+
+impl Pattern for Number {
+    type Matches = serde_json::Value;
+
+    fn to_example(&self) -> serde_json::Value {
+        self.example.clone()
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(&path.to_string(), MatchingRule::Number, &RuleLogic::And);
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        add_generator(self.generator(), path, category, generators_out);
+    }
+}
+
+impl_from_for_pattern!(Number, JsonPattern);
+
+/// Match only null values.
+#[derive(Debug, Default)]
+pub struct Null;
+
+impl Pattern for Null {
+    type Matches = serde_json::Value;
+
+    fn to_example(&self) -> serde_json::Value {
+        serde_json::Value::Null
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(&path.to_string(), MatchingRule::Null, &RuleLogic::And);
+    }
+}
+
+impl_from_for_pattern!(Null, JsonPattern);
+
+// The date and time patterns only differ in their matching rule and generator,
+// so they are all defined by this macro.
+macro_rules! date_time_pattern {
+    ($name:ident, $kind:expr) => {
+        #[doc = "Match and generate strings which are a "]
+        #[doc = $kind]
+        #[doc = " in the given format. The format is the one used by Java's"]
+        #[doc = " `SimpleDateFormat` (i.e. `yyyy-MM-dd'T'HH:mm:ss`), and a"]
+        #[doc = " generator is added so a new value is generated for each test."]
+        #[derive(Debug)]
+        pub struct $name<Nested: Pattern> {
+            format: String,
+            example: String,
+            phantom: PhantomData<Nested>,
+        }
+
+        impl<Nested: Pattern> $name<Nested> {
+            /// Match values in `format`, and generate `example`.
+            pub fn new<F: Into<String>, E: Into<String>>(format: F, example: E) -> Self {
+                $name {
+                    format: format.into(),
+                    example: example.into(),
+                    phantom: PhantomData,
+                }
+            }
+
+            /// The generator for the value.
+            pub fn generator(&self) -> Option<Generator> {
+                Some(Generator::$name(Some(self.format.clone())))
+            }
+        }
+
+        impl<Nested> Pattern for $name<Nested>
+        where
+            Nested: Pattern,
+            Nested::Matches: From<String>,
+        {
+            type Matches = Nested::Matches;
+
+            fn to_example(&self) -> Self::Matches {
+                From::from(self.example.clone())
+            }
+
+            fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+                rules_out.add_rule(
+                    &path.to_string(),
+                    MatchingRule::$name(self.format.clone()),
+                    &RuleLogic::And
+                );
+            }
+
+            fn extract_generators(
+                &self,
+                path: &str,
+                category: &GeneratorCategory,
+                generators_out: &mut Generators,
+            ) {
+                add_generator(self.generator(), path, category, generators_out);
+            }
+        }
+
+        impl_from_for_pattern!($name<JsonPattern>, JsonPattern);
+        impl_from_for_pattern!($name<StringPattern>, StringPattern);
+    }
+}
+
+date_time_pattern!(Timestamp, "timestamp");
+date_time_pattern!(Date, "date");
+date_time_pattern!(Time, "time");
+
+/// Match strings which include a substring.
+#[derive(Debug)]
+pub struct Include<Nested: Pattern> {
+    value: String,
+    example: String,
+    phantom: PhantomData<Nested>,
+}
+
+impl<Nested: Pattern> Include<Nested> {
+    /// Match all strings which include `value`, and generate `example`. Panics
+    /// if `example` does not include `value`.
+    pub fn new<V: Into<String>, E: Into<String>>(value: V, example: E) -> Self {
+        let value = value.into();
+        let example = example.into();
+        if !example.contains(&value) {
+            panic!("example {:?} does not include {:?}", example, value);
+        }
+        Include { value, example, phantom: PhantomData }
+    }
+}
+
+impl<Nested> Pattern for Include<Nested>
+where
+    Nested: Pattern,
+    Nested::Matches: From<String>,
+{
+    type Matches = Nested::Matches;
+
+    fn to_example(&self) -> Self::Matches {
+        From::from(self.example.clone())
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(
+            &path.to_string(),
+            MatchingRule::Include(self.value.clone()),
+            &RuleLogic::And
+        );
+    }
+}
+
+impl_from_for_pattern!(Include<JsonPattern>, JsonPattern);
+impl_from_for_pattern!(Include<StringPattern>, StringPattern);
+
+/// Match UUID strings. A generator is added so a new UUID is generated for each
+/// test.
+#[derive(Debug)]
+pub struct Uuid<Nested: Pattern> {
+    example: String,
+    phantom: PhantomData<Nested>,
+}
+
+impl<Nested: Pattern> Uuid<Nested> {
+    /// Match all UUIDs, and generate `example`.
+    pub fn new<E: Into<String>>(example: E) -> Self {
+        Uuid { example: example.into(), phantom: PhantomData }
+    }
+
+    /// Match all UUIDs, and generate a fixed example UUID.
+    pub fn random() -> Self {
+        Uuid::new(UUID_EXAMPLE)
+    }
+
+    /// The generator for the value.
+    pub fn generator(&self) -> Option<Generator> {
+        Some(Generator::Uuid)
+    }
+}
+
+impl<Nested> Pattern for Uuid<Nested>
+where
+    Nested: Pattern,
+    Nested::Matches: From<String>,
+{
+    type Matches = Nested::Matches;
+
+    fn to_example(&self) -> Self::Matches {
+        From::from(self.example.clone())
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        rules_out.add_rule(
+            &path.to_string(),
+            MatchingRule::Regex(s!(UUID_REGEX)),
+            &RuleLogic::And
+        );
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        add_generator(self.generator(), path, category, generators_out);
+    }
+}
+
+impl_from_for_pattern!(Uuid<JsonPattern>, JsonPattern);
+impl_from_for_pattern!(Uuid<StringPattern>, StringPattern);
+
+/// Generate a value from the provider state parameters when the pact is
+/// verified, using an expression like `${id}` or `/orders/${id}`. The value is
+/// matched by the matching rules of the example, so a plain example must be
+/// matched exactly, and an example like `like!(1)` matches any value of the
+/// same type.
+#[derive(Debug)]
+pub struct FromProviderState<Nested: Pattern> {
+    expression: String,
+    example: Nested,
+}
+
+impl<Nested: Pattern> FromProviderState<Nested> {
+    /// Generate a value from `expression` when the pact is verified, and use
+    /// `example` until then.
+    pub fn new<X: Into<String>, E: Into<Nested>>(expression: X, example: E) -> Self {
+        FromProviderState { expression: expression.into(), example: example.into() }
+    }
+
+    /// The generator for the value.
+    pub fn generator(&self) -> Option<Generator> {
+        Some(Generator::ProviderStateGenerator(self.expression.clone()))
+    }
+}
+
+impl<Nested: Pattern> Pattern for FromProviderState<Nested> {
+    type Matches = Nested::Matches;
+
+    fn to_example(&self) -> Self::Matches {
+        self.example.to_example()
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        self.example.extract_matching_rules(path, rules_out);
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        add_generator(self.generator(), path, category, generators_out);
+    }
+}
+
+impl_from_for_pattern!(FromProviderState<JsonPattern>, JsonPattern);
+impl_from_for_pattern!(FromProviderState<StringPattern>, StringPattern);
+
+/// Generates the example integer (or a random one if there is no example), and
+/// matches any integer.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({
+///   "id": integer!(),
+///   "count": integer!(12),
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! integer {
+    () => { $crate::patterns::Integer::random() };
+    ($example:expr) => { $crate::patterns::Integer::new($example) };
+}
+
+/// Generates the example decimal number (or a random one if there is no
+/// example), and matches any decimal number.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({
+///   "price": decimal!(12.5),
+///   "discount": decimal!(),
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! decimal {
+    () => { $crate::patterns::Decimal::random() };
+    ($example:expr) => { $crate::patterns::Decimal::new($example) };
+}
+
+/// Generates the example number (or a random one if there is no example), and
+/// matches any number.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({
+///   "weight": number!(12),
+///   "height": number!(1.5),
+///   "age": number!(),
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! number {
+    () => { $crate::patterns::Number::random() };
+    ($example:expr) => { $crate::patterns::Number::new($example) };
+}
+
+/// Generates `null`, and matches only `null`. (This can't be called `null!`, as
+/// `json_pattern!` treats `null` as the JSON value.)
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({ "deleted_at": null_value!() });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! null_value {
+    () => { $crate::patterns::Null };
+}
+
+/// Generates `$example`, and matches any timestamp in the format `$format`.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({
+///   "created_at": timestamp!("yyyy-MM-dd'T'HH:mm:ss", "2018-06-01T10:15:30"),
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! timestamp {
+    ($format:expr, $example:expr) => {
+        $crate::patterns::Timestamp::new($format, $example)
+    };
+}
+
+/// Generates `$example`, and matches any date in the format `$format`.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({ "birthday": date!("yyyy-MM-dd", "2000-02-29") });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! date {
+    ($format:expr, $example:expr) => {
+        $crate::patterns::Date::new($format, $example)
+    };
+}
+
+/// Generates `$example`, and matches any time in the format `$format`.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({ "opens_at": time!("HH:mm", "09:30") });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! time {
+    ($format:expr, $example:expr) => {
+        $crate::patterns::Time::new($format, $example)
+    };
+}
+
+/// Generates `$example` (or `$value` if there is no example), and matches any
+/// string which includes `$value`. (This can't be called `include!`, as that is
+/// already a standard macro.)
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({
+///   "greeting": includes!("Hello"),
+///   "farewell": includes!("bye", "Goodbye, World"),
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! includes {
+    ($value:expr) => { $crate::patterns::Include::new($value, $value) };
+    ($value:expr, $example:expr) => { $crate::patterns::Include::new($value, $example) };
+}
+
+/// Generates the example UUID (or a fixed one if there is no example), and
+/// matches any UUID.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # fn main() {
+/// json_pattern!({
+///   "id": uuid!(),
+///   "parent_id": uuid!("b3e2a6e4-2f3b-4e36-bb7d-cf1c8d9b4a10"),
+/// });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! uuid {
+    () => { $crate::patterns::Uuid::random() };
+    ($example:expr) => { $crate::patterns::Uuid::new($example) };
+}
+
+/// Generates `$example`, and when the pact is verified, replaces it with a
+/// value built from the provider state parameters by `$expression`. The value
+/// is matched using the matching rules of `$example`, so use a pattern like
+/// `like!` or `term!` for the example when the generated value will differ
+/// from it.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # #[macro_use] extern crate serde_json;
+/// # fn main() {
+/// use pact_consumer::prelude::*;
+///
+/// PactBuilder::new("orders client", "orders service")
+///     .interaction("fetch an order", |i| {
+///         i.given_with_params("an order exists", json!({ "id": 42 }));
+///         i.request.path(from_provider_state!("/orders/${id}", term!("^/orders/[0-9]+$", "/orders/1")));
+///         i.response.json_body(json_pattern!({
+///             "id": from_provider_state!("${id}", like!(1)),
+///         }));
+///     });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! from_provider_state {
+    ($expression:expr, $example:expr) => {
+        $crate::patterns::FromProviderState::new($expression, $example)
+    };
+}
+
+#[test]
+fn number_patterns() {
+    let pattern = json_pattern!({
+        "id": integer!(),
+        "count": integer!(12),
+        "price": decimal!(12.5),
+        "weight": number!(3),
+        "deleted_at": null_value!(),
+    });
+    assert_eq!(pattern.to_example(), json!({
+        "id": 100,
+        "count": 12,
+        "price": 12.5,
+        "weight": 3,
+        "deleted_at": null,
+    }));
+
+    let mut rules = Category::default("body");
+    pattern.extract_matching_rules("$", &mut rules);
+    assert_eq!(rules.to_v2_json(), hashmap!(
+        s!("$.body.id") => json!({ "match": "integer" }),
+        s!("$.body.count") => json!({ "match": "integer" }),
+        s!("$.body.price") => json!({ "match": "decimal" }),
+        s!("$.body.weight") => json!({ "match": "number" }),
+        s!("$.body.deleted_at") => json!({ "match": "null" }),
+    ));
+
+    assert_eq!(integer!().generator(), Some(Generator::RandomInt(0, i32::MAX)));
+    assert_eq!(integer!(12).generator(), None);
+    assert_eq!(decimal!().generator(), Some(Generator::RandomDecimal(10)));
+}
+
+#[test]
+#[should_panic]
+fn number_requires_a_number() {
+    number!("12");
+}
+
+#[test]
+fn string_patterns() {
+    let pattern = json_pattern!({
+        "created_at": timestamp!("yyyy-MM-dd'T'HH:mm:ss", "2018-06-01T10:15:30"),
+        "birthday": date!("yyyy-MM-dd", "2000-02-29"),
+        "opens_at": time!("HH:mm", "09:30"),
+        "greeting": includes!("Hello", "Hello, World"),
+        "id": uuid!(),
+    });
+    assert_eq!(pattern.to_example(), json!({
+        "created_at": "2018-06-01T10:15:30",
+        "birthday": "2000-02-29",
+        "opens_at": "09:30",
+        "greeting": "Hello, World",
+        "id": UUID_EXAMPLE,
+    }));
+
+    let mut rules = Category::default("body");
+    pattern.extract_matching_rules("$", &mut rules);
+    assert_eq!(rules.to_v2_json(), hashmap!(
+        s!("$.body.created_at") => json!({ "match": "timestamp", "timestamp": "yyyy-MM-dd'T'HH:mm:ss" }),
+        s!("$.body.birthday") => json!({ "match": "date", "date": "yyyy-MM-dd" }),
+        s!("$.body.opens_at") => json!({ "match": "time", "time": "HH:mm" }),
+        s!("$.body.greeting") => json!({ "match": "include", "value": "Hello" }),
+        s!("$.body.id") => json!({ "match": "regex", "regex": UUID_REGEX }),
+    ));
+
+    let date: Date<JsonPattern> = date!("yyyy-MM-dd", "2000-02-29");
+    assert_eq!(date.generator(), Some(Generator::Date(Some(s!("yyyy-MM-dd")))));
+    let uuid: Uuid<StringPattern> = uuid!();
+    assert_eq!(uuid.generator(), Some(Generator::Uuid));
+}
+
+#[test]
+fn string_patterns_into() {
+    // The string patterns can also be used for query parameters, headers, etc.
+    let _: StringPattern = timestamp!("yyyy-MM-dd'T'HH:mm:ss", "2018-06-01T10:15:30").into();
+    let _: StringPattern = includes!("Hello").into();
+    let _: StringPattern = uuid!().into();
+}
+
+#[test]
+#[should_panic]
+fn include_requires_the_example_to_include_the_value() {
+    let _: Include<JsonPattern> = includes!("Hello", "Goodbye");
+}
+
+#[test]
+fn from_provider_state_pattern() {
+    let pattern = json_pattern!({
+        "id": from_provider_state!("${id}", like!(1)),
+        "name": from_provider_state!("${name}", "Fred"),
+    });
+    assert_eq!(pattern.to_example(), json!({ "id": 1, "name": "Fred" }));
+
+    // The matching rules come from the example.
+    let mut rules = Category::default("body");
+    pattern.extract_matching_rules("$", &mut rules);
+    assert_eq!(rules.to_v2_json(), hashmap!(
+        s!("$.body.id") => json!({ "match": "type" }),
+    ));
+
+    let mut generators = Generators::default();
+    pattern.extract_generators("$", &GeneratorCategory::BODY, &mut generators);
+    assert_eq!(generators.categories[&GeneratorCategory::BODY]["$.id"],
+        Generator::ProviderStateGenerator(s!("${id}")));
+
+    let path: StringPattern = from_provider_state!("/orders/${id}", "/orders/1").into();
+    assert_eq!(path.to_example(), "/orders/1");
+}
@@ -0,0 +1,426 @@
+//! Our `XmlPattern` type and supporting code.
+//!
+//! The XML matcher in `pact_matching` uses different paths from JSON. An
+//! element is matched at the path of its position in the document (`$` for
+//! the root element, or `$.parent[0]` for the first child element of
+//! `parent`), and its contents are under that path plus its name, so the
+//! attributes of the root element `order` are at `$.order['@id']`, and its
+//! text at `$.order['#text']`.
+
+use pact_matching::models::generators::{GeneratorCategory, Generators};
+use pact_matching::models::matchingrules::Category;
+
+use super::Pattern;
+use super::special_rules::{EachLike, Like, Term};
+use super::string_pattern::StringPattern;
+use util::obj_key_for_path;
+
+/// Escapes the special characters in XML text and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// The XML matcher ignores namespace prefixes, so our paths do as well.
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// A pattern which matches or generates an XML element. Attributes and text
+/// can use any `StringPattern`, and child elements can be wrapped in `Like` or
+/// `EachLike`.
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # use pact_consumer::prelude::*;
+/// # fn main() {
+/// let order = XmlElement::new("order")
+///     .attribute("id", term!("^[0-9]+$", "1001"))
+///     .child(XmlElement::new("note").text("Leave at the door"));
+/// assert_eq!(order.to_example(),
+///     "<order id=\"1001\"><note>Leave at the door</note></order>");
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct XmlElement {
+    name: String,
+    attributes: Vec<(String, StringPattern)>,
+    children: Vec<XmlPattern>,
+}
+
+impl XmlElement {
+    /// Construct a new element with no attributes or children.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        XmlElement {
+            name: name.into(),
+            attributes: vec![],
+            children: vec![],
+        }
+    }
+
+    /// Set the attribute `name`, replacing any previous value.
+    pub fn attribute<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<StringPattern>,
+    {
+        let name = name.into();
+        self.attributes.retain(|(key, _)| *key != name);
+        self.attributes.push((name, value.into()));
+        self
+    }
+
+    /// Add a child to this element. An `EachLike` child should be the only
+    /// child element, as it matches all the children of this element.
+    pub fn child<C: Into<XmlPattern>>(mut self, child: C) -> Self {
+        self.children.push(child.into());
+        self
+    }
+
+    /// Add text to this element. The XML matcher compares all the text of an
+    /// element as one value.
+    pub fn text<T: Into<StringPattern>>(self, text: T) -> Self {
+        self.child(XmlPattern::Text(text.into()))
+    }
+}
+
+impl Pattern for XmlElement {
+    type Matches = String;
+
+    fn to_example(&self) -> String {
+        let mut xml = format!("<{}", self.name);
+        for (name, value) in &self.attributes {
+            xml.push_str(&format!(" {}=\"{}\"", name, escape(&value.to_example())));
+        }
+        if self.children.is_empty() {
+            xml.push_str("/>");
+        } else {
+            xml.push('>');
+            for child in &self.children {
+                xml.push_str(&child.to_example());
+            }
+            xml.push_str(&format!("</{}>", self.name));
+        }
+        xml
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        let contents_path = format!("{}{}", path, obj_key_for_path(local_name(&self.name)));
+        for (name, value) in &self.attributes {
+            let attribute = format!("@{}", local_name(name));
+            let attribute_path = format!("{}{}", contents_path, obj_key_for_path(&attribute));
+            value.extract_matching_rules(&attribute_path, rules_out);
+        }
+        let mut index = 0;
+        for child in &self.children {
+            match *child {
+                XmlPattern::Text(ref text) => {
+                    let text_path = format!("{}{}", contents_path, obj_key_for_path("#text"));
+                    text.extract_matching_rules(&text_path, rules_out);
+                }
+                XmlPattern::EachLike(ref each_like) => {
+                    each_like.extract_matching_rules(&contents_path, rules_out);
+                }
+                XmlPattern::Element(_) | XmlPattern::Pattern(_) => {
+                    let child_path = format!("{}[{}]", contents_path, index);
+                    child.extract_matching_rules(&child_path, rules_out);
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        let contents_path = format!("{}{}", path, obj_key_for_path(local_name(&self.name)));
+        for (name, value) in &self.attributes {
+            let attribute = format!("@{}", local_name(name));
+            let attribute_path = format!("{}{}", contents_path, obj_key_for_path(&attribute));
+            value.extract_generators(&attribute_path, category, generators_out);
+        }
+        let mut index = 0;
+        for child in &self.children {
+            match *child {
+                XmlPattern::Text(ref text) => {
+                    let text_path = format!("{}{}", contents_path, obj_key_for_path("#text"));
+                    text.extract_generators(&text_path, category, generators_out);
+                }
+                XmlPattern::EachLike(ref each_like) => {
+                    each_like.extract_generators(&contents_path, category, generators_out);
+                }
+                XmlPattern::Element(_) | XmlPattern::Pattern(_) => {
+                    let child_path = format!("{}[{}]", contents_path, index);
+                    child.extract_generators(&child_path, category, generators_out);
+                    index += 1;
+                }
+            }
+        }
+    }
+}
+
+/// A pattern which can be used to either generate an XML document, or to
+/// match one. See the `xml!` macro for a convenient way to build one.
+///
+/// The path given to `extract_matching_rules` depends on the variant. For an
+/// element, it is the path of the element's position, for text it is the path
+/// of the text, and for `EachLike` it is the path of the contents of the
+/// parent element. `XmlElement` works these out for its children.
+#[derive(Debug)]
+pub enum XmlPattern {
+    /// An element, with attributes and children.
+    Element(XmlElement),
+    /// The text of an element. May contain nested matching rules.
+    Text(StringPattern),
+    /// Repeated child elements, which are all matched like an example.
+    EachLike(Box<EachLike<XmlPattern>>),
+    /// An element with a special matching rule, like `Like`.
+    Pattern(Box<Pattern<Matches = String>>),
+}
+
+impl XmlPattern {
+    /// Construct an XML pattern from a type implementing `Pattern`.
+    pub fn pattern<P>(pattern: P) -> XmlPattern
+    where
+        P: Pattern<Matches = String> + 'static,
+    {
+        XmlPattern::Pattern(Box::new(pattern))
+    }
+}
+
+impl Pattern for XmlPattern {
+    type Matches = String;
+
+    fn to_example(&self) -> String {
+        match *self {
+            XmlPattern::Element(ref element) => element.to_example(),
+            XmlPattern::Text(ref text) => escape(&text.to_example()),
+            XmlPattern::EachLike(ref each_like) => each_like.to_example(),
+            XmlPattern::Pattern(ref pattern) => pattern.to_example(),
+        }
+    }
+
+    fn extract_matching_rules(&self, path: &str, rules_out: &mut Category) {
+        match *self {
+            XmlPattern::Element(ref element) => element.extract_matching_rules(path, rules_out),
+            XmlPattern::Text(ref text) => text.extract_matching_rules(path, rules_out),
+            XmlPattern::EachLike(ref each_like) => each_like.extract_matching_rules(path, rules_out),
+            XmlPattern::Pattern(ref pattern) => pattern.extract_matching_rules(path, rules_out),
+        }
+    }
+
+    fn extract_generators(
+        &self,
+        path: &str,
+        category: &GeneratorCategory,
+        generators_out: &mut Generators,
+    ) {
+        match *self {
+            XmlPattern::Element(ref element) => element.extract_generators(path, category, generators_out),
+            XmlPattern::Text(ref text) => text.extract_generators(path, category, generators_out),
+            XmlPattern::EachLike(ref each_like) => each_like.extract_generators(path, category, generators_out),
+            XmlPattern::Pattern(ref pattern) => pattern.extract_generators(path, category, generators_out),
+        }
+    }
+}
+
+impl From<XmlElement> for XmlPattern {
+    fn from(element: XmlElement) -> Self {
+        XmlPattern::Element(element)
+    }
+}
+
+impl From<StringPattern> for XmlPattern {
+    fn from(text: StringPattern) -> Self {
+        XmlPattern::Text(text)
+    }
+}
+
+impl<'a> From<&'a str> for XmlPattern {
+    fn from(text: &'a str) -> Self {
+        XmlPattern::Text(text.into())
+    }
+}
+
+impl From<String> for XmlPattern {
+    fn from(text: String) -> Self {
+        XmlPattern::Text(text.into())
+    }
+}
+
+impl From<Term<StringPattern>> for XmlPattern {
+    fn from(term: Term<StringPattern>) -> Self {
+        XmlPattern::Text(term.into())
+    }
+}
+
+impl From<EachLike<XmlPattern>> for XmlPattern {
+    fn from(each_like: EachLike<XmlPattern>) -> Self {
+        XmlPattern::EachLike(Box::new(each_like))
+    }
+}
+
+impl_from_for_pattern!(Like<XmlPattern>, XmlPattern);
+
+/// Construct an `XmlPattern` for an element. The element name is followed by
+/// optional attributes in parentheses, and optional children in square
+/// brackets. A child is either a nested element, a string literal for text, or
+/// a Rust expression in braces which supports `Into<XmlPattern>`:
+///
+/// ```
+/// # #[macro_use] extern crate pact_consumer;
+/// # use pact_consumer::prelude::*;
+/// # fn main() {
+/// xml!(order(id = term!("^[0-9]+$", "1001"), "xml:lang" = "en") [
+///     note ["Leave at the door"],
+///     items [
+///         { EachLike::new(xml!(item(sku = Like::new("A-1".to_owned())))).with_min_len(2) }
+///     ],
+///     total [{ term!("^[0-9]+\\.[0-9]{2}$", "10.00") }],
+///     gift
+/// ]);
+/// # }
+/// ```
+///
+/// Elements whose names are not Rust identifiers can be built with
+/// `XmlElement` and included in braces.
+#[macro_export]
+macro_rules! xml {
+    ($($tokens:tt)+) => {
+        $crate::patterns::XmlPattern::from(xml_helper!(@element $($tokens)+))
+    };
+}
+
+// A hidden macro which does the work of expanding `xml!`, by munching the
+// attributes and children of each element one at a time.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! xml_helper {
+    // Elements, with or without attributes and children.
+    (@element $name:ident ( $($attributes:tt)* ) [ $($children:tt)* ]) => {
+        xml_helper!(@children
+            xml_helper!(@attributes $crate::patterns::XmlElement::new(stringify!($name)); $($attributes)*);
+            $($children)*)
+    };
+    (@element $name:ident ( $($attributes:tt)* )) => {
+        xml_helper!(@element $name ( $($attributes)* ) [])
+    };
+    (@element $name:ident [ $($children:tt)* ]) => {
+        xml_helper!(@element $name () [ $($children)* ])
+    };
+    (@element $name:ident) => {
+        xml_helper!(@element $name () [])
+    };
+
+    // Attributes, named by an identifier or a string literal.
+    (@attributes $element:expr; ) => { $element };
+    (@attributes $element:expr; , $($rest:tt)*) => {
+        xml_helper!(@attributes $element; $($rest)*)
+    };
+    (@attributes $element:expr; $name:ident = $value:expr) => {
+        $element.attribute(stringify!($name), $value)
+    };
+    (@attributes $element:expr; $name:ident = $value:expr, $($rest:tt)*) => {
+        xml_helper!(@attributes $element.attribute(stringify!($name), $value); $($rest)*)
+    };
+    (@attributes $element:expr; $name:literal = $value:expr) => {
+        $element.attribute($name, $value)
+    };
+    (@attributes $element:expr; $name:literal = $value:expr, $($rest:tt)*) => {
+        xml_helper!(@attributes $element.attribute($name, $value); $($rest)*)
+    };
+
+    // Children: text, expressions and nested elements.
+    (@children $element:expr; ) => { $element };
+    (@children $element:expr; , $($rest:tt)*) => {
+        xml_helper!(@children $element; $($rest)*)
+    };
+    (@children $element:expr; $text:literal $($rest:tt)*) => {
+        xml_helper!(@children $element.text($text); $($rest)*)
+    };
+    (@children $element:expr; { $child:expr } $($rest:tt)*) => {
+        xml_helper!(@children $element.child($child); $($rest)*)
+    };
+    (@children $element:expr; $name:ident ( $($attributes:tt)* ) [ $($children:tt)* ] $($rest:tt)*) => {
+        xml_helper!(@children
+            $element.child(xml_helper!(@element $name ( $($attributes)* ) [ $($children)* ]));
+            $($rest)*)
+    };
+    (@children $element:expr; $name:ident ( $($attributes:tt)* ) $($rest:tt)*) => {
+        xml_helper!(@children
+            $element.child(xml_helper!(@element $name ( $($attributes)* )));
+            $($rest)*)
+    };
+    (@children $element:expr; $name:ident [ $($children:tt)* ] $($rest:tt)*) => {
+        xml_helper!(@children
+            $element.child(xml_helper!(@element $name [ $($children)* ]));
+            $($rest)*)
+    };
+    (@children $element:expr; $name:ident $($rest:tt)*) => {
+        xml_helper!(@children $element.child(xml_helper!(@element $name)); $($rest)*)
+    };
+}
+
+#[test]
+fn xml_pattern_is_pattern() {
+    let pattern = xml!(order(id = term!("^[0-9]+$", "1001")) [
+        note [{ StringPattern::from(Like::new("Leave at the door".to_owned())) }],
+        items [
+            { EachLike::new(xml!(item(sku = "A-1"))).with_min_len(2) }
+        ],
+        { Like::new(xml!(gift)) }
+    ]);
+    assert_eq!(pattern.to_example(), "<order id=\"1001\"><note>Leave at the door</note>\
+        <items><item sku=\"A-1\"/><item sku=\"A-1\"/></items><gift/></order>");
+
+    let mut rules = Category::default("body");
+    pattern.extract_matching_rules("$", &mut rules);
+    let expected_rules = hashmap!(
+        s!("$.body.order['@id']") => json!({ "match": "regex", "regex": "^[0-9]+$" }),
+        s!("$.body.order[0].note['#text']") => json!({ "match": "type" }),
+        s!("$.body.order[1].items") => json!({ "match": "type", "min": 2 }),
+        s!("$.body.order[1].items[*]") => json!({ "match": "type" }),
+        s!("$.body.order[2]") => json!({ "match": "type" })
+    );
+    assert_eq!(rules.to_v2_json(), expected_rules);
+}
+
+#[test]
+fn xml_pattern_extracts_generators() {
+    use pact_matching::models::generators::Generator;
+
+    let pattern = xml!(order(id = from_provider_state!("${id}", "1001")) [
+        note [{ StringPattern::from(from_provider_state!("${note}", "Leave at the door")) }],
+        items [
+            { EachLike::new(xml!(item(sku = from_provider_state!("${sku}", "A-1")))) }
+        ]
+    ]);
+    let mut generators = Generators::default();
+    pattern.extract_generators("$", &GeneratorCategory::BODY, &mut generators);
+    let expected = hashmap!{
+        s!("$.order['@id']") => Generator::ProviderStateGenerator(s!("${id}")),
+        s!("$.order[0].note['#text']") => Generator::ProviderStateGenerator(s!("${note}")),
+        s!("$.order[1].items[*].item['@sku']") => Generator::ProviderStateGenerator(s!("${sku}"))
+    };
+    assert_eq!(generators.categories[&GeneratorCategory::BODY], expected);
+}
+
+#[test]
+fn xml_pattern_escapes_text_and_attributes() {
+    let pattern = xml!(note("xml:lang" = "\"en\"") ["Fish & <chips>"]);
+    assert_eq!(pattern.to_example(),
+        "<note xml:lang=\"&quot;en&quot;\">Fish &amp; &lt;chips&gt;</note>");
+}
@@ -0,0 +1,135 @@
+//! The `pact_consumer_macros` crate provides the `#[pact_test]` attribute for
+//! writing consumer pact tests. It is re-exported by the `pact_consumer` crate,
+//! which is where you should normally use it from.
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate pact_consumer;
+//!
+//! use pact_consumer::pact_test;
+//! use pact_consumer::prelude::*;
+//!
+//! fn interactions(pact: &mut PactBuilder) {
+//!     pact.interaction("a retrieve Mallory request", |i| {
+//!         i.request.path("/mallory");
+//!         i.response.body("That is some good Mallory.");
+//!     });
+//! }
+//!
+//! #[pact_test(consumer = "Consumer", provider = "Alice Service", interactions = interactions)]
+//! fn fetches_mallory(server: &ValidatingMockServer) {
+//!     // You would use your actual client code here.
+//!     let body = my_client::fetch(server.path("/mallory"));
+//!     assert_eq!(body, "That is some good Mallory.");
+//! }
+//! ```
+#![warn(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use syn::parse::{Error, Parse, ParseStream, Result};
+use syn::{Ident, ItemFn, LitStr, Path};
+
+/// The arguments of the `#[pact_test]` attribute.
+struct PactTestArgs {
+    consumer: LitStr,
+    provider: LitStr,
+    interactions: Option<Path>,
+}
+
+impl Parse for PactTestArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut consumer = None;
+        let mut provider = None;
+        let mut interactions = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "consumer" => consumer = Some(input.parse()?),
+                "provider" => provider = Some(input.parse()?),
+                "interactions" => interactions = Some(input.parse()?),
+                _ => return Err(Error::new(
+                    key.span(),
+                    "expected `consumer`, `provider` or `interactions`",
+                )),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        let missing = |name| Error::new(
+            Span::call_site(),
+            format!("missing `{} = \"...\"` in #[pact_test]", name),
+        );
+        Ok(PactTestArgs {
+            consumer: consumer.ok_or_else(|| missing("consumer"))?,
+            provider: provider.ok_or_else(|| missing("provider"))?,
+            interactions,
+        })
+    }
+}
+
+/// Turns a function into a consumer pact test.
+///
+/// The attribute takes the names of the `consumer` and `provider`, and
+/// optionally the path of a function which adds the `interactions` to a
+/// `&mut PactBuilder`. The test function is passed the started
+/// `&ValidatingMockServer`. When it returns, the mock server is verified, and
+/// the pact is written out if there were no mismatches. If there were, the test
+/// fails with a report of them. If the test panics, the pact is not written.
+#[proc_macro_attribute]
+pub fn pact_test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as PactTestArgs);
+    let function = parse_macro_input!(item as ItemFn);
+
+    if let Some(asyncness) = function.asyncness {
+        return Error::new(
+            asyncness.span,
+            "#[pact_test] does not support async functions, use `PactBuilder::run` instead",
+        ).to_compile_error().into();
+    }
+    if !function.decl.generics.params.is_empty() {
+        return Error::new(
+            function.ident.span(),
+            "#[pact_test] does not support generic functions",
+        ).to_compile_error().into();
+    }
+
+    let ItemFn { attrs, ident, decl, block, .. } = function;
+    let inputs = &decl.inputs;
+    let output = &decl.output;
+    let consumer = &args.consumer;
+    let provider = &args.provider;
+    let interactions = args.interactions.iter();
+    // The test may leave out the mock server if it doesn't need it.
+    let test = if inputs.is_empty() {
+        quote!(|_| #ident())
+    } else {
+        quote!(#ident)
+    };
+
+    let expanded = quote! {
+        #[test]
+        #(#attrs)*
+        fn #ident() #output {
+            fn #ident(#inputs) #output #block
+
+            #[allow(unused_mut)]
+            let mut pact = ::pact_consumer::builders::PactBuilder::new(#consumer, #provider);
+            #( #interactions(&mut pact); )*
+            match pact.run_blocking(#test) {
+                Ok(output) => output,
+                Err(report) => panic!("{}", report),
+            }
+        }
+    };
+    expanded.into()
+}
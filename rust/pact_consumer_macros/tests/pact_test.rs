@@ -0,0 +1,41 @@
+extern crate pact_consumer;
+
+use pact_consumer::pact_test;
+use pact_consumer::prelude::*;
+use std::io::prelude::*;
+use std::net::TcpStream;
+
+/// Make a minimal HTTP GET request, and return the response body.
+fn get(server: &ValidatingMockServer, path: &str) -> String {
+    let url = server.url();
+    let mut stream = TcpStream::connect((url.host_str().unwrap(), url.port().unwrap()))
+        .expect("could not connect to mock server");
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response.split("\r\n\r\n").nth(1).unwrap_or("").to_owned()
+}
+
+fn greeting(pact: &mut PactBuilder) {
+    pact.interaction("a greeting request", |i| {
+        i.request.path("/greeting");
+        i.response.body("Hello");
+    });
+}
+
+#[pact_test(consumer = "pact_test consumer", provider = "greeting provider", interactions = greeting)]
+fn the_mock_server_is_passed_to_the_test(server: &ValidatingMockServer) {
+    assert_eq!(get(server, "/greeting"), "Hello");
+}
+
+#[pact_test(consumer = "pact_test consumer", provider = "empty provider")]
+fn the_mock_server_can_be_left_out() {}
+
+#[pact_test(consumer = "pact_test consumer", provider = "result provider")]
+fn tests_can_return_results(_server: &ValidatingMockServer) -> Result<(), String> {
+    Ok(())
+}
+
+#[pact_test(consumer = "pact_test consumer", provider = "missing provider", interactions = greeting)]
+#[should_panic(expected = "a greeting request")]
+fn the_test_fails_when_an_interaction_is_missing(_server: &ValidatingMockServer) {}
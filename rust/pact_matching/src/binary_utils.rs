@@ -0,0 +1,88 @@
+use std::str;
+use serde_json::{self, Value};
+
+/// Magic bytes at the start of the data for well known binary formats
+static MAGIC_BYTES: [(&[u8], &str); 12] = [
+    (b"%PDF-", "application/pdf"),
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"II*\x00", "image/tiff"),
+    (b"MM\x00*", "image/tiff"),
+    (b"BM", "image/bmp"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1F\x8B", "application/gzip"),
+    (b"\x00\x00\x01\x00", "image/x-icon"),
+    (b"OggS", "audio/ogg")
+];
+
+/// Detects the content type of the data from its magic bytes. Data that is not a known binary format
+/// is detected as JSON, XML or plain text if it is valid UTF-8, otherwise `application/octet-stream`.
+pub fn detect_content_type_from_bytes(data: &[u8]) -> String {
+    if let Some(&(_, content_type)) = MAGIC_BYTES.iter().find(|&&(magic, _)| data.starts_with(magic)) {
+        return s!(content_type);
+    }
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return s!("image/webp");
+    }
+    match str::from_utf8(data) {
+        Ok(text) => {
+            let text = text.trim_start();
+            if (text.starts_with('{') || text.starts_with('[')) && serde_json::from_str::<Value>(text).is_ok() {
+                s!("application/json")
+            } else if text.starts_with("<?xml") {
+                s!("application/xml")
+            } else if text.to_lowercase().starts_with("<!doctype html") || text.to_lowercase().starts_with("<html") {
+                s!("text/html")
+            } else {
+                s!("text/plain")
+            }
+        },
+        Err(_) => s!("application/octet-stream")
+    }
+}
+
+/// Checks that the content type detected from the data is the expected content type. Any
+/// parameters of the expected content type (like the charset) are ignored.
+pub fn match_content_type(data: &[u8], expected_content_type: &str) -> Result<(), String> {
+    let expected = expected_content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    let detected = detect_content_type_from_bytes(data);
+    if expected == detected {
+        Ok(())
+    } else {
+        Err(format!("Expected binary contents to have content type '{}' but detected contents was '{}'",
+            expected_content_type, detected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn detects_binary_content_from_the_magic_bytes() {
+        expect!(detect_content_type_from_bytes(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3")).to(be_equal_to(s!("application/pdf")));
+        expect!(detect_content_type_from_bytes(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR")).to(be_equal_to(s!("image/png")));
+        expect!(detect_content_type_from_bytes(b"\xFF\xD8\xFF\xE0\x00\x10JFIF")).to(be_equal_to(s!("image/jpeg")));
+        expect!(detect_content_type_from_bytes(b"RIFF\x24\x00\x00\x00WEBPVP8 ")).to(be_equal_to(s!("image/webp")));
+        expect!(detect_content_type_from_bytes(b"\x00\x01\x02\xFE")).to(be_equal_to(s!("application/octet-stream")));
+    }
+
+    #[test]
+    fn detects_text_content() {
+        expect!(detect_content_type_from_bytes(b" {\"a\": 1}")).to(be_equal_to(s!("application/json")));
+        expect!(detect_content_type_from_bytes(b"<?xml version=\"1.0\"?><a/>")).to(be_equal_to(s!("application/xml")));
+        expect!(detect_content_type_from_bytes(b"<!DOCTYPE html><html></html>")).to(be_equal_to(s!("text/html")));
+        expect!(detect_content_type_from_bytes(b"{ not json")).to(be_equal_to(s!("text/plain")));
+    }
+
+    #[test]
+    fn match_content_type_compares_the_detected_content_type() {
+        expect!(match_content_type(b"%PDF-1.4", "application/pdf")).to(be_ok());
+        expect!(match_content_type(b"hello", "text/plain; charset=utf-8")).to(be_ok());
+        expect!(match_content_type(b"GIF89a", "application/pdf")).to(be_err().value(
+            s!("Expected binary contents to have content type 'application/pdf' but detected contents was 'image/gif'")));
+    }
+}
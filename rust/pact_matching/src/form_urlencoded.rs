@@ -0,0 +1,142 @@
+use super::Mismatch;
+use super::DiffConfig;
+use super::{ParameterKind, match_parameter_values, find_parameter};
+use std::collections::HashMap;
+use models::parse_query_string;
+use models::matchingrules::*;
+use path_exp::DocPath;
+
+fn parse_form(body: &[u8]) -> HashMap<String, Vec<String>> {
+    parse_query_string(&String::from_utf8_lossy(body).into_owned()).unwrap_or_default()
+}
+
+fn field_path(key: &str) -> DocPath {
+    DocPath::root().join(key)
+}
+
+/// Form fields are matched the same way as query parameters, with the rules from the body category
+const FORM_FIELD: ParameterKind = ParameterKind {
+    category: "body",
+    name: "form post parameter",
+    mismatch: |key, expected, actual, mismatch| Mismatch::BodyMismatch { path: field_path(key),
+        expected: Some(expected.into()), actual: Some(actual.into()), mismatch }
+};
+
+pub fn match_form_urlencoded(expected: &[u8], actual: &[u8], config: DiffConfig,
+    mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
+    let expected_form = parse_form(expected);
+    let actual_form = parse_form(actual);
+
+    for (key, value) in &expected_form {
+        match find_parameter(&actual_form, key) {
+            Some(actual_value) => match_parameter_values(&FORM_FIELD, key, value, actual_value, mismatches, matchers),
+            None => mismatches.push(Mismatch::BodyMismatch { path: field_path(key),
+                expected: Some(format!("{:?}", value).into()),
+                actual: None,
+                mismatch: format!("Expected form post parameter '{}' but was missing", key) })
+        }
+    }
+    if config == DiffConfig::NoUnexpectedKeys {
+        for (key, value) in &actual_form {
+            if find_parameter(&expected_form, key).is_none() {
+                mismatches.push(Mismatch::BodyMismatch { path: field_path(key),
+                    expected: None,
+                    actual: Some(format!("{:?}", value).into()),
+                    mismatch: format!("Unexpected form post parameter '{}' received", key) });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    fn match_bodies(expected: &str, actual: &str, config: DiffConfig, matchers: &MatchingRules) -> Vec<Mismatch> {
+        let mut mismatches = vec![];
+        match_form_urlencoded(expected.as_bytes(), actual.as_bytes(), config,
+            &mut mismatches, matchers);
+        mismatches
+    }
+
+    #[test]
+    fn matches_form_bodies_regardless_of_field_order() {
+        let mismatches = match_bodies("a=1&b=2", "b=2&a=1", DiffConfig::AllowUnexpectedKeys,
+            &MatchingRules::default());
+        expect!(mismatches.iter()).to(be_empty());
+    }
+
+    #[test]
+    fn compares_the_decoded_field_values() {
+        let mismatches = match_bodies("name=a+b&c=%26", "name=a%20b&c=%26", DiffConfig::AllowUnexpectedKeys,
+            &MatchingRules::default());
+        expect!(mismatches.iter()).to(be_empty());
+        let mismatches = match_bodies("name=a+b", "name=c", DiffConfig::AllowUnexpectedKeys,
+            &MatchingRules::default());
+        expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+            s!("$.name -> Expected 'a b' to be equal to 'c'")
+        ]));
+    }
+
+    #[test]
+    fn reports_missing_fields_and_value_counts() {
+        let mismatches = match_bodies("a=1&a=2&b=3", "a=1", DiffConfig::AllowUnexpectedKeys,
+            &MatchingRules::default());
+        let mut descriptions = mismatches.iter().map(|m| m.description()).collect::<Vec<String>>();
+        descriptions.sort();
+        expect!(descriptions).to(be_equal_to(vec![
+            s!("$.a -> Expected form post parameter 'a' value '2' but was missing"),
+            s!("$.a -> Expected form post parameter 'a' with 2 value(s) but received 1 value(s)"),
+            s!("$.b -> Expected form post parameter 'b' but was missing")
+        ]));
+    }
+
+    #[test]
+    fn unexpected_fields_are_only_mismatches_if_not_allowed() {
+        let mismatches = match_bodies("a=1", "a=1&b=2", DiffConfig::AllowUnexpectedKeys,
+            &MatchingRules::default());
+        expect!(mismatches.iter()).to(be_empty());
+        let mismatches = match_bodies("a=1", "a=1&b=2", DiffConfig::NoUnexpectedKeys,
+            &MatchingRules::default());
+        expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+            s!("$.b -> Unexpected form post parameter 'b' received")
+        ]));
+    }
+
+    #[test]
+    fn applies_matching_rules_to_the_fields() {
+        let matchers = matchingrules!{
+            "body" => {
+                "$.id" => [ MatchingRule::Regex(s!("^\\d+$")) ]
+            }
+        };
+        let mismatches = match_bodies("id=100&name=a", "id=2&name=a", DiffConfig::AllowUnexpectedKeys, &matchers);
+        expect!(mismatches.iter()).to(be_empty());
+        let mismatches = match_bodies("id=100&name=a", "id=x&name=a", DiffConfig::AllowUnexpectedKeys, &matchers);
+        expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+            s!("$.id -> Expected 'x' to match '^\\d+$'")
+        ]));
+    }
+
+    #[test]
+    fn matches_the_field_values_like_query_parameters() {
+        let unordered = matchingrules!{
+            "body" => { "$.ids" => [ MatchingRule::Unordered ] }
+        };
+        expect!(match_bodies("ids=1&ids=2", "ids=2&ids=1", DiffConfig::AllowUnexpectedKeys, &unordered).iter())
+            .to(be_empty());
+        expect!(match_bodies("ids[]=1&ids[]=2", "ids=1&ids=2", DiffConfig::NoUnexpectedKeys,
+            &MatchingRules::default()).iter()).to(be_empty());
+
+        let min_max = matchingrules!{
+            "body" => { "$.ids" => [ MatchingRule::MinMaxType(1, 2) ] }
+        };
+        expect!(match_bodies("ids=1", "ids=1&ids=1", DiffConfig::AllowUnexpectedKeys, &min_max).iter())
+            .to(be_empty());
+        expect!(match_bodies("ids=1", "ids=1&ids=1&ids=1", DiffConfig::AllowUnexpectedKeys, &min_max).iter()
+            .map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+            s!("$.ids -> Expected form post parameter 'ids' with at most 2 value(s) but received 3 value(s)")
+        ]));
+    }
+}
@@ -1,7 +1,9 @@
 use super::*;
 use expectest::prelude::*;
 use models::{Request, Response, OptionalBody, DetectedContentType};
-use models::generators::{JsonHandler, ContentTypeHandler};
+use models::generators::{JsonHandler, FormUrlEncodedHandler, ContentTypeHandler, register_content_type_handler};
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::str::FromStr;
 use serde_json::Value;
 
@@ -22,16 +24,16 @@ fn applies_status_generator_for_status_to_the_copy_of_the_response() {
 #[test]
 fn applies_header_generator_for_headers_to_the_copy_of_the_response() {
   let response = Response { headers: Some(hashmap!{
-      s!("A") => s!("a"),
-      s!("B") => s!("b")
-    }), generators: generators! {
+      s!("A") => vec![s!("a")],
+      s!("B") => vec![s!("b")]
+    }.into()), generators: generators! {
       "HEADER" => {
         "A" => Generator::Uuid
       }
     }, .. Response::default_response()
   };
   let headers = generate_response(&response).headers.unwrap().clone();
-  expect!(headers.get("A").unwrap()).to_not(be_equal_to("a"));
+  expect!(headers.get("A").unwrap()[0].as_str()).to_not(be_equal_to("a"));
 }
 
 #[test]
@@ -51,16 +53,16 @@ fn applies_path_generator_for_the_path_to_the_copy_of_the_request() {
 #[test]
 fn applies_header_generator_for_headers_to_the_copy_of_the_request() {
   let request = Request { headers: Some(hashmap!{
-      s!("A") => s!("a"),
-      s!("B") => s!("b")
-    }), generators: generators! {
+      s!("A") => vec![s!("a")],
+      s!("B") => vec![s!("b")]
+    }.into()), generators: generators! {
       "HEADER" => {
         "A" => Generator::Uuid
       }
     }, .. Request::default_request()
   };
   let headers = generate_request(&request).headers.unwrap().clone();
-  expect!(headers.get("A").unwrap()).to_not(be_equal_to("a"));
+  expect!(headers.get("A").unwrap()[0].as_str()).to_not(be_equal_to("a"));
 }
 
 #[test]
@@ -82,23 +84,23 @@ fn applies_query_generator_for_query_parameters_to_the_copy_of_the_request() {
 #[test]
 fn apply_generator_to_empty_body_test() {
   let generators = Generators::default();
-  expect!(generators.apply_body_generators(&OptionalBody::Empty, DetectedContentType::Text)).to(be_equal_to(OptionalBody::Empty));
-  expect!(generators.apply_body_generators(&OptionalBody::Null, DetectedContentType::Text)).to(be_equal_to(OptionalBody::Null));
-  expect!(generators.apply_body_generators(&OptionalBody::Missing, DetectedContentType::Text)).to(be_equal_to(OptionalBody::Missing));
+  expect!(generators.apply_body_generators(&OptionalBody::Empty, DetectedContentType::Text, &hashmap!{})).to(be_equal_to(OptionalBody::Empty));
+  expect!(generators.apply_body_generators(&OptionalBody::Null, DetectedContentType::Text, &hashmap!{})).to(be_equal_to(OptionalBody::Null));
+  expect!(generators.apply_body_generators(&OptionalBody::Missing, DetectedContentType::Text, &hashmap!{})).to(be_equal_to(OptionalBody::Missing));
 }
 
 #[test]
 fn do_not_apply_generators_if_there_are_no_body_generators() {
   let generators = Generators::default();
   let body = OptionalBody::Present("{\"a\": 100, \"b\": \"B\"}".into());
-  expect!(generators.apply_body_generators(&body, DetectedContentType::Json)).to(be_equal_to(body));
+  expect!(generators.apply_body_generators(&body, DetectedContentType::Json, &hashmap!{})).to(be_equal_to(body));
 }
 
 #[test]
 fn apply_generator_to_text_body_test() {
   let generators = Generators::default();
   let body = OptionalBody::Present("some text".into());
-  expect!(generators.apply_body_generators(&body, DetectedContentType::Text)).to(be_equal_to(body));
+  expect!(generators.apply_body_generators(&body, DetectedContentType::Text, &hashmap!{})).to(be_equal_to(body));
 }
 
 #[test]
@@ -134,7 +136,7 @@ fn applies_body_generator_to_the_copy_of_the_response() {
 fn does_not_change_body_if_there_are_no_generators() {
   let body = OptionalBody::Present("{\"a\": 100, \"b\": \"B\"}".into());
   let generators = generators!{};
-  let processed = generators.apply_body_generators(&body, DetectedContentType::Json);
+  let processed = generators.apply_body_generators(&body, DetectedContentType::Json, &hashmap!{});
   expect!(processed).to(be_equal_to(body));
 }
 
@@ -143,7 +145,7 @@ fn applies_the_generator_to_a_json_map_entry() {
   let map = json!({"a": 100, "b": "B", "c": "C"});
   let mut json_handler = JsonHandler { value: map };
 
-  json_handler.apply_key(&s!("$.b"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$.b"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(&json_handler.value["b"]).to_not(be_equal_to(&json!("B")));
 }
@@ -153,7 +155,7 @@ fn json_generator_handles_invalid_path_expressions() {
   let map = json!({"a": 100, "b": "B", "c": "C"});
   let mut json_handler = JsonHandler { value: map };
   
-  json_handler.apply_key(&s!("$["), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$["), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(json_handler.value).to(be_equal_to(json!({"a": 100, "b": "B", "c": "C"})));
 }
@@ -163,7 +165,7 @@ fn does_not_apply_the_generator_when_field_is_not_in_map() {
   let map = json!({"a": 100, "b": "B", "c": "C"});
   let mut json_handler = JsonHandler { value: map };
   
-  json_handler.apply_key(&s!("$.d"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$.d"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(json_handler.value).to(be_equal_to(json!({"a": 100, "b": "B", "c": "C"})));
 }
@@ -173,7 +175,7 @@ fn does_not_apply_the_generator_when_not_a_map() {
   let map = json!(100);
   let mut json_handler = JsonHandler { value: map };
   
-  json_handler.apply_key(&s!("$.d"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$.d"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(json_handler.value).to(be_equal_to(json!(100)));
 }
@@ -183,7 +185,7 @@ fn applies_the_generator_to_a_list_item() {
   let list = json!([100, 200, 300]);
   let mut json_handler = JsonHandler { value: list };
 
-  json_handler.apply_key(&s!("$[1]"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$[1]"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(&json_handler.value[1]).to_not(be_equal_to(&json!(200)));
 }
@@ -193,7 +195,7 @@ fn does_not_apply_the_generator_when_index_is_not_in_list() {
   let list = json!([100, 200, 300]);
   let mut json_handler = JsonHandler { value: list };
   
-  json_handler.apply_key(&s!("$[3]"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$[3]"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(json_handler.value).to(be_equal_to(json!([100, 200, 300])));
 }
@@ -203,7 +205,7 @@ fn does_not_apply_the_generator_when_not_a_list() {
   let list = json!(100);
   let mut json_handler = JsonHandler { value: list };
   
-  json_handler.apply_key(&s!("$[3]"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$[3]"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(json_handler.value).to(be_equal_to(json!(100)));
 }
@@ -213,7 +215,7 @@ fn applies_the_generator_to_the_root() {
   let value = json!(100);
   let mut json_handler = JsonHandler { value };
 
-  json_handler.apply_key(&s!("$"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(&json_handler.value).to_not(be_equal_to(&json!(100)));
 }
@@ -227,7 +229,7 @@ fn applies_the_generator_to_the_object_graph() {
   });
   let mut json_handler = JsonHandler { value };
 
-  json_handler.apply_key(&s!("$.a[1].b['2']"), &Generator::RandomInt(3, 10));
+  json_handler.apply_key(&s!("$.a[1].b['2']"), &Generator::RandomInt(3, 10), &hashmap!{});
 
   expect!(&json_handler.value["a"][1]["b"]["2"]).to_not(be_equal_to(&json!("2")));
 }
@@ -241,7 +243,7 @@ fn does_not_apply_the_generator_to_the_object_graph_when_the_expression_does_not
   });
   let mut json_handler = JsonHandler { value };
 
-  json_handler.apply_key(&s!("$.a[1].b['2']"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$.a[1].b['2']"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(&json_handler.value).to(be_equal_to(&json!({
     "a": "A",
@@ -259,7 +261,7 @@ fn applies_the_generator_to_all_map_entries() {
   });
   let mut json_handler = JsonHandler { value };
 
-  json_handler.apply_key(&s!("$.*"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$.*"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(&json_handler.value["a"]).to_not(be_equal_to(&json!("A")));
   expect!(&json_handler.value["b"]).to_not(be_equal_to(&json!("B")));
@@ -271,7 +273,7 @@ fn applies_the_generator_to_all_list_items() {
   let value = json!(["A", "B", "C"]);
   let mut json_handler = JsonHandler { value };
 
-  json_handler.apply_key(&s!("$[*]"), &Generator::RandomInt(0, 10));
+  json_handler.apply_key(&s!("$[*]"), &Generator::RandomInt(0, 10), &hashmap!{});
 
   expect!(&json_handler.value[0]).to_not(be_equal_to(&json!("A")));
   expect!(&json_handler.value[1]).to_not(be_equal_to(&json!("B")));
@@ -287,7 +289,7 @@ fn applies_the_generator_to_the_object_graph_with_wildcard() {
   });
   let mut json_handler = JsonHandler { value };
 
-  json_handler.apply_key(&s!("$.*[1].b[*]"), &Generator::RandomInt(3, 10));
+  json_handler.apply_key(&s!("$.*[1].b[*]"), &Generator::RandomInt(3, 10), &hashmap!{});
 
   p!(json_handler.value);
   expect!(&json_handler.value["a"][0]).to(be_equal_to(&json!("A")));
@@ -299,3 +301,106 @@ fn applies_the_generator_to_the_object_graph_with_wildcard() {
   expect!(&json_handler.value["b"]).to(be_equal_to(&json!("B")));
   expect!(&json_handler.value["c"]).to(be_equal_to(&json!("C")));
 }
+
+#[test]
+fn applies_the_generator_to_all_the_values_of_a_form_field() {
+  let mut form_handler = FormUrlEncodedHandler {
+    params: hashmap!{ s!("a") => vec![s!("1"), s!("2")], s!("b") => vec![s!("B")] }
+  };
+
+  form_handler.apply_key(&s!("$.a"), &Generator::ProviderStateGenerator(s!("id")), &hashmap!{ s!("id") => json!(100) });
+
+  expect!(form_handler.params).to(be_equal_to(hashmap!{ s!("a") => vec![s!("100"), s!("100")], s!("b") => vec![s!("B")] }));
+}
+
+#[test]
+fn applies_the_generator_to_a_form_field_value_by_index() {
+  let mut form_handler = FormUrlEncodedHandler {
+    params: hashmap!{ s!("a") => vec![s!("1"), s!("2")] }
+  };
+
+  form_handler.apply_key(&s!("$.a[1]"), &Generator::ProviderStateGenerator(s!("id")), &hashmap!{ s!("id") => json!(100) });
+  form_handler.apply_key(&s!("$.c"), &Generator::ProviderStateGenerator(s!("id")), &hashmap!{ s!("id") => json!(100) });
+
+  expect!(form_handler.params).to(be_equal_to(hashmap!{ s!("a") => vec![s!("1"), s!("100")] }));
+}
+
+#[test]
+fn applies_body_generators_to_a_form_post_body() {
+  let request = Request {
+    headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/x-www-form-urlencoded")] }.into()),
+    body: OptionalBody::Present("name=a+b&id=1".into()),
+    generators: generators! {
+      "BODY" => {
+        "$.id" => Generator::ProviderStateGenerator(s!("id"))
+      }
+    }, .. Request::default_request()
+  };
+  let generated = generate_request_with_context(&request, &hashmap!{ s!("id") => json!("10 & 20") });
+  expect!(generated.body).to(be_equal_to(OptionalBody::Present("id=10+%26+20&name=a+b".into())));
+}
+
+#[test]
+fn applies_body_generators_to_the_attributes_and_text_of_an_xml_body() {
+  let response = Response {
+    headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/xml")] }.into()),
+    body: OptionalBody::Present("<?xml version=\"1.0\"?><order id=\"1\"><note>old</note>\
+      <items><item sku=\"A\"/><item sku=\"B\"/></items></order>".into()),
+    generators: generators! {
+      "BODY" => {
+        "$.order['@id']" => Generator::ProviderStateGenerator(s!("id")),
+        "$.order[0].note['#text']" => Generator::ProviderStateGenerator(s!("note")),
+        "$.order[1].items[*].item['@sku']" => Generator::ProviderStateGenerator(s!("sku")),
+        "$.order[0].items['#text']" => Generator::ProviderStateGenerator(s!("note"))
+      }
+    }, .. Response::default_response()
+  };
+  let generated = generate_response_with_context(&response,
+    &hashmap!{ s!("id") => json!(42), s!("note") => json!("new"), s!("sku") => json!("C") });
+  expect!(generated.body.str_value()).to(be_equal_to("<?xml version='1.0'?><order id='42'><note>new</note>\
+    <items><item sku='C'/><item sku='C'/></items></order>"));
+}
+
+struct LinesHandler {
+  lines: Vec<String>
+}
+
+impl ContentTypeHandler<Vec<u8>> for LinesHandler {
+  fn process_body(&mut self, generators: &HashMap<String, Generator>, context: &HashMap<String, Value>) -> OptionalBody {
+    for (key, generator) in generators {
+      self.apply_key(key, generator, context);
+    }
+    OptionalBody::Present(self.lines.join("\n").into())
+  }
+
+  fn apply_key(&mut self, key: &String, generator: &Generator, context: &HashMap<String, Value>) {
+    if let Ok(index) = key.parse::<usize>() {
+      if let Some(line) = self.lines.get_mut(index) {
+        if let Some(value) = generator.generate_value(line, context) {
+          *line = value;
+        }
+      }
+    }
+  }
+}
+
+#[test]
+fn applies_body_generators_with_a_registered_content_type_handler() {
+  register_content_type_handler("application/x-test-lines", Arc::new(|body: &OptionalBody| {
+    let handler: Box<ContentTypeHandler<Vec<u8>>> = Box::new(LinesHandler {
+      lines: body.str_value().lines().map(|line| s!(line)).collect()
+    });
+    Ok(handler)
+  })).unwrap();
+  let request = Request {
+    headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/x-test-lines")] }.into()),
+    body: OptionalBody::Present("a\nb\nc".into()),
+    generators: generators! {
+      "BODY" => {
+        "1" => Generator::ProviderStateGenerator(s!("id"))
+      }
+    }, .. Request::default_request()
+  };
+  let generated = generate_request_with_context(&request, &hashmap!{ s!("id") => json!("100") });
+  expect!(generated.body).to(be_equal_to(OptionalBody::Present("a\n100\nc".into())));
+}
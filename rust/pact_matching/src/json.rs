@@ -9,6 +9,8 @@ use ansi_term::Colour::*;
 use std::str::FromStr;
 use models::matchingrules::*;
 use matchers::*;
+use time_utils::validate_datetime;
+use path_exp::{DocPath, PathToken};
 use regex::Regex;
 
 fn type_of(json: &Value) -> String {
@@ -140,6 +142,12 @@ impl Matches<Value> for Value {
           } else {
             Err(format!("Expected '{}' to be a number", value_of(actual)))
           },
+          MatchingRule::Timestamp(ref format) |
+          MatchingRule::Date(ref format) |
+          MatchingRule::Time(ref format) => match actual {
+            Value::String(s) => validate_datetime(s, format),
+            _ => Err(format!("Expected '{}' to be a date/time string", value_of(actual)))
+          },
           _ => Err(format!("Unable to match '{}' using {:?}", self, matcher))
        };
        debug!("Comparing '{}' to '{}' using {:?} -> {:?}", self, actual, matcher, result);
@@ -203,6 +211,11 @@ impl Matches<Vec<Value>> for Vec<Value> {
 
 /// Matches the expected JSON to the actual, and populates the mismatches vector with any differences
 pub fn match_json(expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
+    mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
+    match_json_bytes(expected, actual, config, mismatches, matchers)
+}
+
+pub(crate) fn match_json_bytes(expected: &[u8], actual: &[u8], config: DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
     let expected_json = serde_json::from_slice(expected);
     let actual_json = serde_json::from_slice(actual);
@@ -210,50 +223,47 @@ pub fn match_json(expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
     if expected_json.is_err() || actual_json.is_err() {
         match expected_json {
             Err(e) => {
-                mismatches.push(Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.clone().into()),
-                    actual: Some(actual.clone().into()),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.to_vec()),
+                    actual: Some(actual.to_vec()),
                     mismatch: format!("Failed to parse the expected body: '{}'", e)});
             },
             _ => ()
         }
         match actual_json {
             Err(e) => {
-                mismatches.push(Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.clone().into()),
-                    actual: Some(actual.clone().into()),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.to_vec()),
+                    actual: Some(actual.to_vec()),
                     mismatch: format!("Failed to parse the actual body: '{}'", e)});
             },
             _ => ()
         }
     } else {
-        compare(&vec![s!("$")], &expected_json.unwrap(), &actual_json.unwrap(), &config,
+        compare(&vec![s!("$")], &DocPath::root(), &expected_json.unwrap(), &actual_json.unwrap(), &config,
             mismatches, matchers);
     }
 }
 
-fn walk_json(json: &Value, path: &mut Iterator<Item=&str>) -> Option<Value> {
-    match path.next() {
-        Some(p) => match json {
-            &Value::Object(_) => json.get(p).map(|json| json.clone()),
-            &Value::Array(ref array) => match usize::from_str(p) {
-                Ok(index) => array.get(index).map(|json| json.clone()),
-                Err(_) => None
-            },
+fn walk_json(json: &Value, path: &[PathToken]) -> Option<Value> {
+    match path.split_first() {
+        Some((token, rest)) => match (token, json) {
+            (PathToken::Root, _) => walk_json(json, rest),
+            (PathToken::Field(name), Value::Object(map)) => map.get(name).and_then(|json| walk_json(json, rest)),
+            (PathToken::Index(index), Value::Array(array)) => array.get(*index).and_then(|json| walk_json(json, rest)),
             _ => None
         },
-        None => None
+        None => Some(json.clone())
     }
 }
 
 /// Returns a diff of the expected versus the actual JSON bodies, focusing on a particular path
-pub fn display_diff(expected: &String, actual: &String, path: &String) -> String {
+pub fn display_diff(expected: &String, actual: &String, path: &DocPath) -> String {
     let expected_body = Value::from_str(expected).unwrap();
     let actual_body = Value::from_str(actual).unwrap();
-    let path = path.split('.').skip(2);
-    let expected_fragment = match walk_json(&expected_body, &mut path.clone()) {
+    let expected_fragment = match walk_json(&expected_body, path.tokens()) {
         Some(json) => format!("{:?}", serde_json::to_string_pretty(&json)),
         None => s!("")
     };
-    let actual_fragment = match walk_json(&actual_body, &mut path.clone()) {
+    let actual_fragment = match walk_json(&actual_body, path.tokens()) {
         Some(json) => format!("{:?}", serde_json::to_string_pretty(&json)),
         None => s!("")
     };
@@ -269,48 +279,48 @@ pub fn display_diff(expected: &String, actual: &String, path: &String) -> String
     output
 }
 
-fn compare(path: &Vec<String>, expected: &Value, actual: &Value, config: &DiffConfig,
+fn compare(path: &Vec<String>, doc_path: &DocPath, expected: &Value, actual: &Value, config: &DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
     debug!("Comparing path {}", path.join("."));
     match (expected, actual) {
-        (&Value::Object(ref emap), &Value::Object(ref amap)) => compare_maps(path, emap, amap, config, mismatches, matchers),
+        (&Value::Object(ref emap), &Value::Object(ref amap)) => compare_maps(path, doc_path, emap, amap, config, mismatches, matchers),
         (&Value::Object(_), _) => {
-            mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
+            mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
                 expected: Some(value_of(expected).into()),
                 actual: Some(value_of(actual).into()),
                 mismatch: format!("Type mismatch: Expected {} {} but received {} {}",
                     type_of(expected), expected, type_of(actual), actual)});
         },
-        (&Value::Array(ref elist), &Value::Array(ref alist)) => compare_lists(path, elist, alist, config, mismatches, matchers),
+        (&Value::Array(ref elist), &Value::Array(ref alist)) => compare_lists(path, doc_path, elist, alist, config, mismatches, matchers),
         (&Value::Array(_), _) => {
-            mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
+            mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
                 expected: Some(value_of(expected).into()),
                 actual: Some(value_of(actual).into()),
                 mismatch: format!("Type mismatch: Expected {} {} but received {} {}",
                     type_of(expected), value_of(expected), type_of(actual), value_of(actual))});
         },
-        (_, _) => compare_values(path, expected, actual, mismatches, matchers)
+        (_, _) => compare_values(path, doc_path, expected, actual, mismatches, matchers)
     }
 }
 
-fn compare_maps(path: &Vec<String>, expected: &serde_json::Map<String, Value>, actual: &serde_json::Map<String, Value>,
+fn compare_maps(path: &Vec<String>, doc_path: &DocPath, expected: &serde_json::Map<String, Value>, actual: &serde_json::Map<String, Value>,
     config: &DiffConfig, mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
     if expected.is_empty() && !actual.is_empty() {
-      mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
+      mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
           expected: Some(value_of(&json!(expected)).into()),
           actual: Some(value_of(&json!(actual)).into()),
           mismatch: format!("Expected an empty Map but received {}", value_of(&json!(actual)))});
     } else {
         match config {
             &DiffConfig::AllowUnexpectedKeys if expected.len() > actual.len() => {
-                mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
+                mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
                     expected: Some(value_of(&json!(expected)).into()),
                     actual: Some(value_of(&json!(&actual)).into()),
                     mismatch: format!("Expected a Map with at least {} elements but received {} elements",
                     expected.len(), actual.len())});
             },
             &DiffConfig::NoUnexpectedKeys if expected.len() != actual.len() => {
-                mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
+                mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
                     expected: Some(value_of(&json!(expected)).into()),
                     actual: Some(value_of(&json!(&actual)).into()),
                     mismatch: format!("Expected a Map with {} elements but received {} elements",
@@ -326,9 +336,9 @@ fn compare_maps(path: &Vec<String>, expected: &serde_json::Map<String, Value>, a
                 let mut p = path.to_vec();
                 p.push(key.clone());
                 if expected.contains_key(key) {
-                    compare(&p, &expected[key], value, config, mismatches, matchers);
+                    compare(&p, &doc_path.join(key.clone()), &expected[key], value, config, mismatches, matchers);
                 } else if !expected.is_empty() {
-                    compare(&p, &expected.values().next().unwrap(), value, config, mismatches, matchers);
+                    compare(&p, &doc_path.join(key.clone()), &expected.values().next().unwrap(), value, config, mismatches, matchers);
                 }
             }
         } else {
@@ -336,9 +346,9 @@ fn compare_maps(path: &Vec<String>, expected: &serde_json::Map<String, Value>, a
                 if actual.contains_key(key) {
                     let mut p = path.to_vec();
                     p.push(key.clone());
-                    compare(&p, value, &actual[key], config, mismatches, matchers);
+                    compare(&p, &doc_path.join(key.clone()), value, &actual[key], config, mismatches, matchers);
                 } else {
-                    mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
+                    mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
                         expected: Some(value_of(&json!(expected)).into()),
                         actual: Some(value_of(&json!(&actual)).into()),
                         mismatch: format!("Expected entry {}={} but was missing", key, value_of(value))});
@@ -348,18 +358,17 @@ fn compare_maps(path: &Vec<String>, expected: &serde_json::Map<String, Value>, a
     }
 }
 
-fn compare_lists(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>, config: &DiffConfig,
+fn compare_lists(path: &Vec<String>, doc_path: &DocPath, expected: &Vec<Value>, actual: &Vec<Value>, config: &DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
-    let spath = path.join(".");
-    if matchers.matcher_is_defined("body", &path) {
-        debug!("compare_lists: matcher defined for path '{}'", spath);
+        if matchers.matcher_is_defined("body", &path) {
+        debug!("compare_lists: matcher defined for path '{}'", doc_path);
         let expected_json = Value::Array(expected.clone());
         let actual_json = Value::Array(actual.clone());
         match match_values("body", path, matchers.clone(), &expected_json, &actual_json) {
             Err(messages) => {
               for message in messages {
                 mismatches.push(Mismatch::BodyMismatch {
-                  path: path.join("."),
+                  path: doc_path.clone(),
                   expected: Some(expected_json.to_string().into()),
                   actual: Some(actual_json.to_string().into()),
                   mismatch: message.clone()
@@ -371,17 +380,17 @@ fn compare_lists(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>,
         let expected_example = expected.first().unwrap().clone();
         let mut expected_list = Vec::new();
         expected_list.resize(actual.len(), expected_example);
-        compare_list_content(path, &expected_list, actual, config, mismatches, matchers);
+        compare_list_content(path, doc_path, &expected_list, actual, config, mismatches, matchers);
     } else {
         if expected.is_empty() && !actual.is_empty() {
-            mismatches.push(Mismatch::BodyMismatch { path: spath,
+            mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
                 expected: Some(value_of(&json!(expected)).into()),
                 actual: Some(value_of(&json!(actual)).into()),
                 mismatch: format!("Expected an empty List but received {}", value_of(&json!(actual)))});
         } else {
-            compare_list_content(path, expected, actual, config, mismatches, matchers);
+            compare_list_content(path, doc_path, expected, actual, config, mismatches, matchers);
             if expected.len() != actual.len() {
-                mismatches.push(Mismatch::BodyMismatch { path: spath,
+                mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
                     expected: Some(value_of(&json!(expected)).into()),
                     actual: Some(value_of(&json!(actual)).into()),
                     mismatch: format!("Expected a List with {} elements but received {} elements",
@@ -391,7 +400,7 @@ fn compare_lists(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>,
     }
 }
 
-fn compare_list_content(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<Value>, config: &DiffConfig,
+fn compare_list_content(path: &Vec<String>, doc_path: &DocPath, expected: &Vec<Value>, actual: &Vec<Value>, config: &DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
     for (index, value) in expected.iter().enumerate() {
       let ps = index.to_string();
@@ -399,9 +408,9 @@ fn compare_list_content(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<
       let mut p = path.to_vec();
       p.push(ps);
       if index < actual.len() {
-          compare(&p, value, &actual[index], config, mismatches, matchers);
+          compare(&p, &doc_path.join_index(index), value, &actual[index], config, mismatches, matchers);
       } else if !matchers.matcher_is_defined("body", &p) {
-          mismatches.push(Mismatch::BodyMismatch { path: path.join("."),
+          mismatches.push(Mismatch::BodyMismatch { path: doc_path.clone(),
               expected: Some(value_of(&json!(expected)).into()),
               actual: Some(value_of(&json!(actual)).into()),
               mismatch: format!("Expected {} but was missing", value_of(value))});
@@ -409,7 +418,7 @@ fn compare_list_content(path: &Vec<String>, expected: &Vec<Value>, actual: &Vec<
     }
 }
 
-fn compare_values(path: &Vec<String>, expected: &Value, actual: &Value, mismatches: &mut Vec<super::Mismatch>,
+fn compare_values(path: &Vec<String>, doc_path: &DocPath, expected: &Value, actual: &Value, mismatches: &mut Vec<super::Mismatch>,
     matchers: &MatchingRules) {
     let matcher_result = if matchers.matcher_is_defined("body", &path) {
         match_values("body", path, matchers.clone(), expected, actual)
@@ -421,7 +430,7 @@ fn compare_values(path: &Vec<String>, expected: &Value, actual: &Value, mismatch
         Err(messages) => {
           for message in messages {
             mismatches.push(Mismatch::BodyMismatch {
-              path: path.join("."),
+              path: doc_path.clone(),
               expected: Some(format!("{}", expected).into()),
               actual: Some(format!("{}", actual).into()),
               mismatch: message.clone()
@@ -447,7 +456,7 @@ mod tests {
         match_json(&expected.clone().into(), &actual.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.into()),
             actual: Some(actual.into()), mismatch: s!("")}));
     }
 
@@ -459,7 +468,7 @@ mod tests {
         match_json(&expected.clone().into(), &actual.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.into()),
             actual: Some(actual.into()), mismatch: s!("Type mismatch: Expected List [{}] but received Map {}")}));
     }
 
@@ -478,7 +487,7 @@ mod tests {
         match_json(&expected.clone().into(), &actual.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.into()),
             actual: Some(actual.into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Type mismatch: Expected Map {} but received List []")));
     }
@@ -491,7 +500,7 @@ mod tests {
         match_json(&expected.clone().into(), &actual.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.into()),
             actual: Some(actual.into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Type mismatch: Expected List [{}] but received Map {}")));
     }
@@ -506,7 +515,7 @@ mod tests {
         match_json(&val1.clone().into(), &val2.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(val1.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(val1.into()),
             actual: Some(val2.into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'string value' to be equal to 'other value'")));
     }
@@ -521,7 +530,7 @@ mod tests {
         match_json(&val1.clone().into(), &val2.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(val1.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(val1.into()),
             actual: Some(val2.into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected '100' to be equal to '200'")));
     }
@@ -536,7 +545,7 @@ mod tests {
         match_json(&val1.clone().into(), &val2.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(val1.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(val1.into()),
             actual: Some(val2.into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected '100.01' to be equal to '100.02'")));
     }
@@ -551,7 +560,7 @@ mod tests {
         match_json(&val1.clone().into(), &val2.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(val1.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(val1.into()),
             actual: Some(val2.into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'true' to be equal to 'false'")));
     }
@@ -566,7 +575,7 @@ mod tests {
         match_json(&val1.clone().into(), &val2.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(val1.clone().into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(val1.clone().into()),
             actual: Some(val2.clone().into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'null' to be equal to '33'")));
     }
@@ -600,7 +609,7 @@ mod tests {
         match_json(&val2.clone().into(), &val3.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.1"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$[1]").unwrap(),
             expected: Some("22".into()), actual: Some("44".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected '22' to be equal to '44'")));
         mismatches.clear();
@@ -608,7 +617,7 @@ mod tests {
         match_json(&val3.clone().into(), &val4.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(),
             expected: Some("[11,44,33]".into()),
             actual: Some("[11,44,33,66]".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected a List with 3 elements but received 4 elements")));
@@ -617,12 +626,12 @@ mod tests {
         match_json(&val2.clone().into(), &val4.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(2));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.1"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$[1]").unwrap(),
             expected: Some("22".into()),
             actual: Some("44".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected '22' to be equal to '44'")));
         let mismatch = mismatches[1].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(),
             expected: Some("[11,22,33]".into()),
             actual: Some("[11,44,33,66]".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected a List with 3 elements but received 4 elements")));
@@ -671,7 +680,7 @@ mod tests {
         match_json(&val2.clone().into(), &val3.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.b"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.b").unwrap(),
             expected: Some("2".into()), actual: Some("3".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected '2' to be equal to '3'")));
         mismatches.clear();
@@ -681,7 +690,7 @@ mod tests {
         match_json(&val2.clone().into(), &val4.clone().into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(),
             expected: Some("{\"a\":1,\"b\":2}".into()),
             actual: Some("{\"a\":1,\"b\":2,\"c\":3}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected a Map with 2 elements but received 3 elements")));
@@ -690,7 +699,7 @@ mod tests {
         match_json(&val3.clone().into(), &val4.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.b"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.b").unwrap(),
             expected: Some("3".into()),
             actual: Some("2".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected '3' to be equal to '2'")));
@@ -699,12 +708,12 @@ mod tests {
         match_json(&val3.clone().into(), &val4.clone().into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(2));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(),
             expected: Some("{\"a\":1,\"b\":3}".into()),
             actual: Some("{\"a\":1,\"b\":2,\"c\":3}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected a Map with 2 elements but received 3 elements")));
         let mismatch = mismatches[1].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.b"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.b").unwrap(),
             expected: Some("3".into()),
             actual: Some("2".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected '3' to be equal to '2'")));
@@ -713,12 +722,12 @@ mod tests {
         match_json(&val4.clone().into(), &val2.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(2));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(),
             expected: Some("{\"a\":1,\"b\":2,\"c\":3}".into()),
             actual: Some("{\"a\":1,\"b\":2}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected a Map with at least 3 elements but received 2 elements")));
         let mismatch = mismatches[1].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(),
             expected: Some("{\"a\":1,\"b\":2,\"c\":3}".into()),
             actual: Some("{\"a\":1,\"b\":2}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected entry c=3 but was missing")));
@@ -820,6 +829,16 @@ mod tests {
     expect!(Value::String(s!("100")).matches(&json!(100.01), &matcher)).to(be_ok());
   }
 
+  #[test]
+  fn timestamp_matcher_test() {
+    let matcher = MatchingRule::Timestamp(s!("yyyy-MM-dd HH:mm"));
+    expect!(json!("2018-06-01 10:15").matches(&json!("2000-01-31 23:59"), &matcher)).to(be_ok());
+    expect!(json!("2018-06-01 10:15").matches(&json!("2000-01-31"), &matcher)).to(be_err());
+    expect!(json!("2018-06-01 10:15").matches(&json!(100), &matcher)).to(be_err());
+    expect!(json!("10:15").matches(&json!("23:59"), &MatchingRule::Time(s!("HH:mm")))).to(be_ok());
+    expect!(json!("2018-06-01").matches(&json!("01/06/2018"), &MatchingRule::Date(s!("yyyy-MM-dd")))).to(be_err());
+  }
+
   #[test]
   fn null_matcher_test() {
     let matcher = MatchingRule::Null;
@@ -887,4 +906,22 @@ mod tests {
         mismatches.clear();
     }
 
+    #[test]
+    fn match_json_reports_numeric_object_keys_as_fields() {
+        let mut mismatches = vec![];
+        let expected = s!(r#"{"1": {"2": "a"}, "list": [1, 2]}"#);
+        let actual = s!(r#"{"1": {"2": "b"}, "list": [1, 3]}"#);
+        match_json(&expected.clone().into(), &actual.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches,
+            &MatchingRules::default());
+        let paths: Vec<String> = mismatches.iter().map(|mismatch| match *mismatch {
+            Mismatch::BodyMismatch { ref path, .. } => path.to_string(),
+            _ => s!("")
+        }).collect();
+        expect!(paths).to(be_equal_to(vec![s!("$.1.2"), s!("$.list[1]")]));
+        let diff = display_diff(&expected, &actual, &DocPath::new("$.1.2").unwrap());
+        expect!(diff.contains(r#"\"a\""#) && diff.contains(r#"\"b\""#)).to(be_true());
+        expect!(walk_json(&Value::from_str(&expected).unwrap(), DocPath::new("$.1.2").unwrap().tokens()))
+            .to(be_some().value(json!("a")));
+    }
+
 }
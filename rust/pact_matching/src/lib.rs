@@ -166,6 +166,37 @@
 //! matcher
 //! 2. Otherwise compare the values using equality.
 //!
+//! #### Form post bodies
+//!
+//! Bodies with a content type of `application/x-www-form-urlencoded` are decoded into fields and compared in
+//! the same way as query parameters (see below). Matchers for the fields are defined in the body category
+//! with a path of `$.<field name>`. Unexpected fields are only a mismatch if unexpected keys are not allowed.
+//!
+//! #### Multipart bodies
+//!
+//! Bodies with a content type of `multipart/form-data` are split into parts using the `boundary` parameter of the
+//! content type header (bodies without one are compared as text), and the parts are matched by name. The headers
+//! of each expected part must be present in the actual part, and the part bodies are compared using the matcher for
+//! the part's content type (so JSON and XML parts are compared as JSON and XML). Matchers for a part are defined in the body category with paths that start
+//! with the part name, like `$.<part name>.id`, and are applied to the part body as if they started with `$.id`.
+//!
+//! #### Binary bodies
+//!
+//! Binary bodies (like PDFs or images) can be matched with a `contentType` matcher defined for the root of the body
+//! (`$`). The content type of the actual body is then detected from its magic bytes, and the body matches if the
+//! detected content type is the expected one. The bytes of the bodies are not otherwise compared. Bodies that are not
+//! text are stored in pact files base64 encoded, with a `contentType` and `encoded` marker.
+//!
+//! #### Other content types
+//!
+//! Bodies with other content types (like `text/plain` or `text/html`) are compared as text. If there is a matcher
+//! defined for the root of the body (`$`), like a regex or include matcher, it is applied to the whole text,
+//! otherwise the text is compared using equality. Matchers for other content types can be added with
+//! [`register_body_matcher`](fn.register_body_matcher.html), which takes a regular expression for the content
+//! type and a function to compare the bodies with. Registered matchers are used in preference to the built-in JSON and
+//! XML ones. Generators for the bodies can be added in the same way with
+//! [`register_content_type_handler`](models/generators/fn.register_content_type_handler.html).
+//!
 //! ### Matching Paths
 //!
 //! Paths are matched by the following:
@@ -186,6 +217,17 @@
 //! pairs can be in any order, but when the same key appears more than once the values
 //! are compared in the order they appear in the query string.
 //!
+//! Matchers defined for a parameter as a whole (i.e. `ids`, rather than `ids[0]`) change how the list of
+//! values is compared:
+//!
+//! * An `unordered` matcher compares the values in any order. This matcher is not part of the Pact specification,
+//!   so other Pact implementations will not understand it, and it is ignored outside of the query parameters.
+//! * A min/max type matcher allows any number of values within the bounds, and compares each value with the
+//!   expected value in the same position (or the last expected value).
+//!
+//! Array parameters with a bracketed name (i.e. `ids[]=1&ids[]=2`) are treated as the same parameter as
+//! the name without the brackets, and matchers for them can be defined with either name.
+//!
 //! ### Matching Headers
 //!
 //! 1. Do a case-insensitive sort of the headers by keys
@@ -193,10 +235,28 @@
 //!     1. If the actual headers contain that key, compare the header values
 //!     2. Otherwise the header does not match
 //!
+//! Headers can have multiple values. The values of headers that are comma separated lists (like `Accept`,
+//! `Cache-Control` or `Vary`) are split on the commas when they are loaded from a pact file, while other headers
+//! (like `Date`, `Link` or `Set-Cookie`, whose values can contain commas) are kept as a single value. Header values
+//! are written to V2 and V3 pact files joined with commas.
+//!
 //! For matching header values:
 //!
-//! 1. If there is a matcher defined for `header.<HEADER_KEY>`, default to that matcher
-//! 2. Otherwise strip all whitespace after commas and compare the resulting strings.
+//! 1. If there is a matcher defined for `header.<HEADER_KEY>`, default to that matcher and apply it to the
+//!    values of the header joined with commas
+//! 2. Otherwise the header must have the same number of values, and each value is compared to the expected value
+//!    in the same position.
+//!
+//! Some headers have structured values, and are compared by parsing them:
+//!
+//! * `Accept` and `Content-Type` values are compared by media type and parameters.
+//! * `Cache-Control` directives and `Link` values are compared ignoring their order.
+//! * `Content-Disposition` values are compared with the parameters in any order.
+//! * `Authorization` values are compared ignoring the case of the scheme, and with any auth parameters
+//!   (like those for `Digest`) in any order.
+//! * HTTP-date headers (`Date`, `Expires`, `Last-Modified`, `If-Modified-Since` and `If-Unmodified-Since`) must
+//!   be valid dates, and are compared as dates. They can be matched with a timestamp matcher to only check
+//!   the format of the date.
 //!
 //! #### Matching Request Headers
 //!
@@ -324,6 +384,7 @@
 //!
 
 #![warn(missing_docs)]
+extern crate serde;
 #[macro_use] extern crate serde_json;
 #[macro_use] extern crate serde_derive;
 #[macro_use] extern crate log;
@@ -342,6 +403,7 @@ extern crate difference;
 extern crate base64;
 extern crate uuid;
 extern crate indextree;
+extern crate libc;
 
 /// Simple macro to convert a string slice to a `String` struct.
 #[macro_export]
@@ -350,18 +412,26 @@ macro_rules! s {
 }
 
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use serde_json::Value;
 use std::iter::FromIterator;
 use regex::Regex;
 use ansi_term::*;
 use ansi_term::Colour::*;
 
 #[macro_use] pub mod models;
-mod path_exp;
+pub mod path_exp;
 mod matchers;
 pub mod json;
 mod xml;
+mod form_urlencoded;
+mod multipart;
+mod binary_utils;
+mod headers;
+mod time_utils;
 
 use models::HttpPart;
+use path_exp::DocPath;
 use models::matchingrules::*;
 use models::generators::*;
 use matchers::*;
@@ -370,19 +440,42 @@ fn strip_whitespace<'a, T: FromIterator<&'a str>>(val: &'a String, split_by: &'a
     val.split(split_by).map(|v| v.trim().clone() ).collect()
 }
 
+/// Function that matches the bodies for a content type. It is called with the expected and actual
+/// bodies, and must add any mismatches that are found to the list of mismatches.
+pub type BodyMatcher = Arc<Fn(&[u8], &[u8], DiffConfig, &mut Vec<Mismatch>, &MatchingRules) + Send + Sync>;
+
 lazy_static! {
-    static ref BODY_MATCHERS: [(Regex, fn(expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
-            mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules)); 3] = [
-        (Regex::new("application/.*json").unwrap(), json::match_json),
-        (Regex::new("application/json.*").unwrap(), json::match_json),
-        (Regex::new("application/.*xml").unwrap(), xml::match_xml)
-    ];
+    static ref BODY_MATCHERS: RwLock<Vec<(Regex, BodyMatcher)>> = RwLock::new(vec![
+        (Regex::new("application/.*json").unwrap(), Arc::new(json::match_json_bytes)),
+        (Regex::new("application/json.*").unwrap(), Arc::new(json::match_json_bytes)),
+        (Regex::new("application/.*xml").unwrap(), Arc::new(xml::match_xml)),
+        (Regex::new("application/x-www-form-urlencoded").unwrap(), Arc::new(form_urlencoded::match_form_urlencoded))
+    ]);
+}
+
+/// Registers a body matcher for the content types that match the regular expression. Matchers
+/// are checked in the reverse order that they were registered in, so a registered matcher takes
+/// precedence over the built-in JSON and XML ones. Returns an error if the regular expression is
+/// not valid.
+pub fn register_body_matcher(content_type: &str, matcher: BodyMatcher) -> Result<(), String> {
+    let regex = Regex::new(content_type)
+        .map_err(|err| format!("'{}' is not a valid content type regular expression - {}", content_type, err))?;
+    BODY_MATCHERS.write().unwrap().insert(0, (regex, matcher));
+    Ok(())
+}
+
+fn body_matcher_for(content_type: &str) -> Option<BodyMatcher> {
+    BODY_MATCHERS.read().unwrap().iter()
+        .find(|matcher| matcher.0.is_match(content_type))
+        .map(|matcher| matcher.1.clone())
 }
 
 static PARAMETERISED_HEADER_TYPES: [&'static str; 2] = ["accept", "content-type"];
 
-/// Enum that defines the different types of mismatches that can occur.
-#[derive(Debug, Clone)]
+/// Enum that defines the different types of mismatches that can occur. It is serialised with a
+/// `type` attribute naming the variant, so mismatches can be passed between processes as JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum Mismatch {
     /// Request Method mismatch
     MethodMismatch {
@@ -438,8 +531,8 @@ pub enum Mismatch {
     },
     /// Body element mismatch
     BodyMismatch {
-        /// path expression to where the mismatch occured
-        path: String,
+        /// path to where the mismatch occured
+        path: DocPath,
         /// expected value
         expected: Option<Vec<u8>>,
         /// actual value
@@ -452,70 +545,12 @@ pub enum Mismatch {
 impl Mismatch {
     /// Converts the mismatch to a `Value` struct.
     pub fn to_json(&self) -> serde_json::Value {
-        match self {
-            &Mismatch::MethodMismatch { expected: ref e, actual: ref a } => {
-                json!({
-                    s!("type") : json!("MethodMismatch"),
-                    s!("expected") : json!(e),
-                    s!("actual") : json!(a)
-                })
-            },
-            &Mismatch::PathMismatch { expected: ref e, actual: ref a, mismatch: ref m } => {
-                json!({
-                    s!("type") : json!("PathMismatch"),
-                    s!("expected") : json!(e),
-                    s!("actual") : json!(a),
-                    s!("mismatch") : json!(m)
-                })
-            },
-            &Mismatch::StatusMismatch { expected: ref e, actual: ref a } => {
-                json!({
-                    s!("type") : json!("StatusMismatch"),
-                    s!("expected") : json!(e),
-                    s!("actual") : json!(a)
-                })
-            },
-            &Mismatch::QueryMismatch { parameter: ref p, expected: ref e, actual: ref a, mismatch: ref m } => {
-                json!({
-                    s!("type") : json!("QueryMismatch"),
-                    s!("parameter") : json!(p),
-                    s!("expected") : json!(e),
-                    s!("actual") : json!(a),
-                    s!("mismatch") : json!(m)
-                })
-            },
-            &Mismatch::HeaderMismatch { key: ref k, expected: ref e, actual: ref a, mismatch: ref m } => {
-                json!({
-                    s!("type") : json!("HeaderMismatch"),
-                    s!("key") : json!(k),
-                    s!("expected") : json!(e),
-                    s!("actual") : json!(a),
-                    s!("mismatch") : json!(m)
-                })
-            },
-            &Mismatch::BodyTypeMismatch { expected: ref e, actual: ref a } => {
-                json!({
-                    s!("type") : json!("BodyTypeMismatch"),
-                    s!("expected") : json!(e),
-                    s!("actual") : json!(a)
-                })
-            },
-            &Mismatch::BodyMismatch { path: ref p, expected: ref e, actual: ref a, mismatch: ref m } => {
-                 json!({
-                    s!("type") : json!("BodyMismatch"),
-                    s!("path") : json!(p),
-                    s!("expected") : match e {
-                        &Some(ref v) => json!(v),
-                        &None => serde_json::Value::Null
-                    },
-                    s!("actual") : match a {
-                        &Some(ref v) => json!(v),
-                        &None => serde_json::Value::Null
-                    },
-                    s!("mismatch") : json!(m)
-                })
-            }
-        }
+        serde_json::to_value(self).unwrap()
+    }
+
+    /// Converts a `Value` struct (as returned by `to_json`) back into a mismatch
+    pub fn from_json(json: &serde_json::Value) -> Result<Mismatch, String> {
+        serde_json::from_value(json.clone()).map_err(|err| format!("Failed to parse mismatch - {}", err))
     }
 
     /// Returns the type of the mismatch as a string
@@ -568,7 +603,7 @@ impl Mismatch {
             Mismatch::HeaderMismatch { expected: ref e, actual: ref a, key: ref k, .. } => format!("Expected header '{}' to have value '{}' but was '{}'",
                 Style::new().bold().paint(k.clone()), Red.paint(e.to_string()), Green.paint(a.to_string())),
             Mismatch::BodyTypeMismatch {  expected: ref e, actual: ref a } => format!("expected '{}' body but was '{}'", Red.paint(e.clone()), Green.paint(a.clone())),
-            Mismatch::BodyMismatch { ref path, ref mismatch, .. } => format!("{} -> {}", Style::new().bold().paint(path.clone()), mismatch)
+            Mismatch::BodyMismatch { ref path, ref mismatch, .. } => format!("{} -> {}", Style::new().bold().paint(path.to_string()), mismatch)
         }
     }
 }
@@ -610,7 +645,7 @@ impl PartialEq for Mismatch {
 }
 
 /// Enum that defines the configuration options for performing a match.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DiffConfig {
     /// If unexpected keys are allowed and ignored during matching.
     AllowUnexpectedKeys,
@@ -620,10 +655,28 @@ pub enum DiffConfig {
 
 /// Matches the actual text body to the expected one.
 pub fn match_text(expected: &Vec<u8>, actual: &Vec<u8>, mismatches: &mut Vec<Mismatch>) {
-    if expected != actual {
-        mismatches.push(Mismatch::BodyMismatch { path: s!("/"), expected: Some(expected.clone()),
-            actual: Some(actual.clone()),
-            mismatch: format!("Expected text '{:?}' but received '{:?}'", expected, actual) });
+    match_text_with_matchers(expected, actual, mismatches, &MatchingRules::default())
+}
+
+/// Matches the actual text body to the expected one. If there is a matching rule defined for the
+/// root of the body (`$`), it is used to match the text, otherwise the text must be equal.
+pub fn match_text_with_matchers(expected: &Vec<u8>, actual: &Vec<u8>, mismatches: &mut Vec<Mismatch>,
+    matchers: &MatchingRules) {
+    let path = vec![s!("$")];
+    let expected_text = String::from_utf8_lossy(expected).into_owned();
+    let actual_text = String::from_utf8_lossy(actual).into_owned();
+    let result = if matchers.matcher_is_defined("body", &path) {
+        matchers::match_values("body", &path, matchers.clone(), &expected_text, &actual_text)
+    } else if expected != actual {
+        Err(vec![format!("Expected text '{}' but received '{}'", expected_text, actual_text)])
+    } else {
+        Ok(())
+    };
+    if let Err(messages) = result {
+        for message in messages {
+            mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.clone()),
+                actual: Some(actual.clone()), mismatch: message });
+        }
     }
 }
 
@@ -656,68 +709,142 @@ pub fn match_path(expected: String, actual: String, mismatches: &mut Vec<Mismatc
     }
 }
 
-fn compare_query_parameter_value(key: &String, expected: &String, actual: &String, index: usize,
+/// How the values of a parameter are matched and reported. Query parameters and the fields of
+/// form post bodies are both lists of values per key, and are matched the same way.
+struct ParameterKind {
+    /// Category the matching rules for the parameter values are defined in
+    category: &'static str,
+    /// What the parameter is called in the mismatch descriptions
+    name: &'static str,
+    /// Creates a mismatch for the parameter key from the expected value, actual value and description
+    mismatch: fn(&str, String, String, String) -> Mismatch
+}
+
+const QUERY_PARAMETER: ParameterKind = ParameterKind {
+    category: "query",
+    name: "query parameter",
+    mismatch: |key, expected, actual, mismatch| Mismatch::QueryMismatch { parameter: key.to_string(),
+        expected, actual, mismatch }
+};
+
+fn compare_parameter_value(kind: &ParameterKind, key: &str, expected: &String, actual: &String, index: usize,
     mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
-    let path = vec![s!("$"), key.clone(), format!("{}", index)];
-    let matcher_result = if matchers.matcher_is_defined("query", &path) {
-      matchers::match_values("query", &path, matchers.clone(), expected, actual)
+    let path = vec![s!("$"), s!(models::query_parameter_name(key)), format!("{}", index)];
+    let matcher_result = if matchers.matcher_is_defined(kind.category, &path) {
+      matchers::match_values(kind.category, &path, matchers.clone(), expected, actual)
     } else {
       expected.matches(actual, &MatchingRule::Equality).map_err(|err| vec![err])
     };
-    match matcher_result {
-        Err(messages) => {
-          for message in messages {
-            mismatches.push(Mismatch::QueryMismatch {
-              parameter: key.clone(),
-              expected: expected.clone(),
-              actual: actual.clone(),
-              mismatch: message
-            })
-          }
-        },
-        Ok(_) => ()
+    if let Err(messages) = matcher_result {
+        for message in messages {
+            mismatches.push((kind.mismatch)(key, expected.clone(), actual.clone(), message));
+        }
     }
 }
 
-fn compare_query_parameter_values(key: &String, expected: &Vec<String>, actual: &Vec<String>,
+fn compare_parameter_values(kind: &ParameterKind, key: &str, expected: &[String], actual: &[String],
     mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
     for (index, val) in expected.iter().enumerate() {
         if index < actual.len() {
-            compare_query_parameter_value(key, val, &actual[index], index, mismatches, matchers);
+            compare_parameter_value(kind, key, val, &actual[index], index, mismatches, matchers);
         } else {
-            mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
-                expected: format!("{:?}", expected),
-                actual: format!("{:?}", actual),
-                mismatch: format!("Expected query parameter '{}' value '{}' but was missing", key, val) });
+            mismatches.push((kind.mismatch)(key, format!("{:?}", expected), format!("{:?}", actual),
+                format!("Expected {} '{}' value '{}' but was missing", kind.name, key, val)));
+        }
+    }
+}
+
+/// Compares the values in any order. Each expected value must match a different actual value.
+fn compare_unordered_parameter_values(kind: &ParameterKind, key: &str, expected: &[String], actual: &[String],
+    mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
+    let mut unmatched: Vec<(usize, &String)> = actual.iter().enumerate().collect();
+    for val in expected {
+        let found = unmatched.iter().position(|&(index, actual_value)| {
+            let mut value_mismatches = vec![];
+            compare_parameter_value(kind, key, val, actual_value, index, &mut value_mismatches, matchers);
+            value_mismatches.is_empty()
+        });
+        match found {
+            Some(position) => { unmatched.remove(position); },
+            None => mismatches.push((kind.mismatch)(key, format!("{:?}", expected), format!("{:?}", actual),
+                format!("Expected {} '{}' value '{}' but was missing", kind.name, key, val)))
         }
     }
 }
 
-fn match_query_values(key: &String, expected: &Vec<String>, actual: &Vec<String>,
+/// Returns the rules defined for a parameter as a whole (i.e. `ids`), rather than for one of its values
+fn parameter_rules(kind: &ParameterKind, key: &str, matchers: &MatchingRules) -> Vec<MatchingRule> {
+    let path = vec![s!("$"), s!(models::query_parameter_name(key))];
+    matchers.resolve_matchers(kind.category, &path)
+        .map(|category| category.rules.values().flat_map(|rule_list| rule_list.rules.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Checks the number of values against the minimum and maximum from a min/max type matcher
+fn match_parameter_values_length(kind: &ParameterKind, key: &str, expected: &[String], actual: &[String],
+    bounds: (Option<usize>, Option<usize>), mismatches: &mut Vec<Mismatch>) {
+    let mismatch = match bounds {
+        (Some(min), _) if actual.len() < min => Some(format!(
+            "Expected {} '{}' with at least {} value(s) but received {} value(s)", kind.name, key, min, actual.len())),
+        (_, Some(max)) if actual.len() > max => Some(format!(
+            "Expected {} '{}' with at most {} value(s) but received {} value(s)", kind.name, key, max, actual.len())),
+        _ => None
+    };
+    if let Some(mismatch) = mismatch {
+        mismatches.push((kind.mismatch)(key, format!("{:?}", expected), format!("{:?}", actual), mismatch));
+    }
+}
+
+/// Matches the values of a query parameter or form field against the expected ones. By default the
+/// values are compared in order, but the rules for the parameter can allow them to be in any order
+/// (`Unordered`) or to vary in number (`MinType`, `MaxType` and `MinMaxType`).
+fn match_parameter_values(kind: &ParameterKind, key: &str, expected: &[String], actual: &[String],
     mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
     if expected.is_empty() && !actual.is_empty() {
-        mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
-            expected: format!("{:?}", expected),
-            actual: format!("{:?}", actual),
-            mismatch: format!("Expected an empty parameter list for '{}' but received {:?}", key, actual) });
+        mismatches.push((kind.mismatch)(key, format!("{:?}", expected), format!("{:?}", actual),
+            format!("Expected an empty parameter list for '{}' but received {:?}", key, actual)));
     } else {
-        if expected.len() != actual.len() {
-            mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
-                expected: format!("{:?}", expected),
-                actual: format!("{:?}", actual),
-                mismatch: format!(
-                    "Expected query parameter '{}' with {} value(s) but received {} value(s)",
-                    key, expected.len(), actual.len()) });
+        let rules = parameter_rules(kind, key, matchers);
+        let length_bounds = rules.iter().filter_map(|rule| match *rule {
+            MatchingRule::MinType(min) => Some((Some(min), None)),
+            MatchingRule::MaxType(max) => Some((None, Some(max))),
+            MatchingRule::MinMaxType(min, max) => Some((Some(min), Some(max))),
+            _ => None
+        }).next();
+        match length_bounds {
+            Some(bounds) => match_parameter_values_length(kind, key, expected, actual, bounds, mismatches),
+            None => if expected.len() != actual.len() {
+                mismatches.push((kind.mismatch)(key, format!("{:?}", expected), format!("{:?}", actual),
+                    format!("Expected {} '{}' with {} value(s) but received {} value(s)",
+                        kind.name, key, expected.len(), actual.len())));
+            }
+        }
+        if rules.contains(&MatchingRule::Unordered) {
+            compare_unordered_parameter_values(kind, key, expected, actual, mismatches, matchers);
+        } else if length_bounds.is_some() {
+            for (index, val) in actual.iter().enumerate() {
+                let expected_value = expected.get(index).or_else(|| expected.last()).cloned().unwrap_or_default();
+                compare_parameter_value(kind, key, &expected_value, val, index, mismatches, matchers);
+            }
+        } else {
+            compare_parameter_values(kind, key, expected, actual, mismatches, matchers);
         }
-        compare_query_parameter_values(key, expected, actual, mismatches, matchers);
     }
 }
 
+/// Finds the values for a parameter, treating `ids` and `ids[]` as the same parameter
+fn find_parameter<'a>(parameters: &'a HashMap<String, Vec<String>>, key: &str) -> Option<&'a Vec<String>> {
+    parameters.get(key).or_else(|| {
+        let name = models::query_parameter_name(key);
+        parameters.iter().find(|&(k, _)| models::query_parameter_name(k) == name).map(|(_, v)| v)
+    })
+}
+
 fn match_query_maps(expected: HashMap<String, Vec<String>>, actual: HashMap<String, Vec<String>>,
     mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
     for (key, value) in &expected {
-        match actual.get(key) {
-            Some(actual_value) => match_query_values(key, value, actual_value, mismatches, matchers),
+        match find_parameter(&actual, key) {
+            Some(actual_value) => match_parameter_values(&QUERY_PARAMETER, key, value, actual_value, mismatches, matchers),
             None => mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
                 expected: format!("{:?}", value),
                 actual: "".to_string(),
@@ -725,7 +852,7 @@ fn match_query_maps(expected: HashMap<String, Vec<String>>, actual: HashMap<Stri
         }
     }
     for (key, value) in &actual {
-        match expected.get(key) {
+        match find_parameter(&expected, key) {
             Some(_) => (),
             None => mismatches.push(Mismatch::QueryMismatch { parameter: key.clone(),
                 expected: "".to_string(),
@@ -796,16 +923,26 @@ fn match_parameter_header(expected: &String, actual: &String, mismatches: &mut V
 fn match_header_value(key: &String, expected: &String, actual: &String, mismatches: &mut Vec<Mismatch>,
     matchers: &MatchingRules) {
     let path = vec![s!("$"), key.clone()];
-    let expected = strip_whitespace::<String>(expected, ",");
-    let actual = strip_whitespace::<String>(actual, ",");
+    let date_result = if headers::is_date_header(key) {
+        Some(headers::match_date(&path, expected, actual, matchers))
+    } else {
+        None
+    };
+    let parameterised_result = headers::match_parameterised_value(key, expected, actual);
+    let stripped_expected = strip_whitespace::<String>(expected, ",");
+    let stripped_actual = strip_whitespace::<String>(actual, ",");
 
-    let matcher_result = if matchers.matcher_is_defined("header", &path) {
-        matchers::match_values("header",&path, matchers.clone(), &expected, &actual)
+    let matcher_result = if let Some(result) = date_result {
+        result
+    } else if matchers.matcher_is_defined("header", &path) {
+        matchers::match_values("header",&path, matchers.clone(), &headers::HeaderValue(expected), actual)
     } else if PARAMETERISED_HEADER_TYPES.contains(&key.to_lowercase().as_str()) {
-        match_parameter_header(&expected, &actual, mismatches, &key);
+        match_parameter_header(&stripped_expected, &stripped_actual, mismatches, &key);
         Ok(())
+    } else if let Some(result) = parameterised_result {
+        result
     } else {
-      expected.matches(&actual, &MatchingRule::Equality).map_err(|err| vec![err])
+      stripped_expected.matches(&stripped_actual, &MatchingRule::Equality).map_err(|err| vec![err])
     };
     match matcher_result {
         Err(messages) => {
@@ -822,36 +959,60 @@ fn match_header_value(key: &String, expected: &String, actual: &String, mismatch
     }
 }
 
-fn find_entry<T>(map: &HashMap<String, T>, key: &String) -> Option<(String, T)> where T: Clone {
-    match map.keys().find(|k| k.to_lowercase() == key.to_lowercase() ) {
-        Some(k) => map.get(k).map(|v| (key.clone(), v.clone()) ),
-        None => None
+/// Matches the values of a header. If there is a matcher defined for the header, it is applied to the
+/// values joined with commas, otherwise the values must match the expected ones in order.
+fn match_header_values(key: &String, expected: &[String], actual: &[String], mismatches: &mut Vec<Mismatch>,
+    matchers: &MatchingRules) {
+    let path = vec![s!("$"), key.clone()];
+    if matchers.matcher_is_defined("header", &path) {
+        match_header_value(key, &expected.join(", "), &actual.join(", "), mismatches, matchers);
+    } else if let Some(result) = headers::match_header_list(key, expected, actual) {
+        if let Err(messages) = result {
+            for message in messages {
+                mismatches.push(Mismatch::HeaderMismatch { key: key.clone(),
+                    expected: expected.join(", "),
+                    actual: actual.join(", "),
+                    mismatch: format!("Mismatch with header '{}': {}", key, message) });
+            }
+        }
+    } else if expected.len() != actual.len() {
+        mismatches.push(Mismatch::HeaderMismatch { key: key.clone(),
+            expected: expected.join(", "),
+            actual: actual.join(", "),
+            mismatch: format!("Expected header '{}' to have {} value(s) but received {} value(s)",
+                key, expected.len(), actual.len()) });
+    } else {
+        for (expected_value, actual_value) in expected.iter().zip(actual.iter()) {
+            match_header_value(key, expected_value, actual_value, mismatches, matchers);
+        }
     }
 }
 
-fn match_header_maps(expected: HashMap<String, String>, actual: HashMap<String, String>,
+fn match_header_maps(expected: models::HeaderMap, actual: models::HeaderMap,
     mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
     for (key, value) in &expected {
-        match find_entry(&actual, key) {
-            Some((_, actual_value)) => match_header_value(key, value, &actual_value, mismatches, matchers),
+        match actual.get(key) {
+            Some(actual_value) => match_header_values(key, value, actual_value, mismatches, matchers),
             None => mismatches.push(Mismatch::HeaderMismatch { key: key.clone(),
-                expected: format!("{:?}", value),
+                expected: value.join(", "),
                 actual: "".to_string(),
                 mismatch: format!("Expected header '{}' but was missing", key) })
         }
     }
 }
 
-/// Matches the actual headers to the expected ones.
-pub fn match_headers(expected: Option<HashMap<String, String>>,
-    actual: Option<HashMap<String, String>>, mismatches: &mut Vec<Mismatch>,
+/// Matches the actual headers to the expected ones. Header names are compared ignoring case, and each
+/// value of a header with multiple values is matched separately, unless there is a matcher defined for
+/// the header.
+pub fn match_headers(expected: Option<models::HeaderMap>,
+    actual: Option<models::HeaderMap>, mismatches: &mut Vec<Mismatch>,
     matchers: &MatchingRules) {
     match (actual, expected) {
         (Some(aqm), Some(eqm)) => match_header_maps(eqm, aqm, mismatches, matchers),
         (Some(_), None) => (),
         (None, Some(eqm)) => for (key, value) in &eqm {
             mismatches.push(Mismatch::HeaderMismatch { key: key.clone(),
-                expected: format!("{:?}", value),
+                expected: value.join(", "),
                 actual: "".to_string(),
                 mismatch: format!("Expected header '{}' but was missing", key) });
         },
@@ -859,63 +1020,102 @@ pub fn match_headers(expected: Option<HashMap<String, String>>,
     };
 }
 
-fn compare_bodies(mimetype: String, expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
-    mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
-    match BODY_MATCHERS.iter().find(|mt| mt.0.is_match(&mimetype)) {
-        Some(ref match_fn) => match_fn.1(expected, actual, config, mismatches, matchers),
-        None => match_text(expected, actual, mismatches)
+/// Returns the content type from a `ContentType` matching rule defined for the root of the body
+fn root_content_type_rule(matchers: &MatchingRules) -> Option<String> {
+    matchers.resolve_body_matchers_by_path(&vec![s!("$")])
+        .and_then(|rules| rules.rules.iter().filter_map(|rule| match *rule {
+            MatchingRule::ContentType(ref content_type) => Some(content_type.clone()),
+            _ => None
+        }).next())
+}
+
+fn compare_bodies(mimetype: String, boundaries: Option<(String, String)>, expected: &Vec<u8>, actual: &Vec<u8>,
+    config: DiffConfig, mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
+    if let Some(content_type) = root_content_type_rule(matchers) {
+        if let Err(message) = binary_utils::match_content_type(actual, &content_type) {
+            mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.clone()),
+                actual: Some(actual.clone()), mismatch: message });
+        }
+        return;
+    }
+    match (body_matcher_for(&mimetype), boundaries) {
+        (Some(ref match_fn), _) => match_fn(expected, actual, config, mismatches, matchers),
+        (None, Some((ref expected_boundary, ref actual_boundary))) => multipart::match_multipart(expected, actual,
+            expected_boundary, actual_boundary, config, mismatches, matchers),
+        (None, None) => match_text_with_matchers(expected, actual, mismatches, matchers)
     }
 }
 
-fn match_body_content(content_type: String, expected: &models::OptionalBody, actual: &models::OptionalBody,
+fn match_body_content(content_type: String, boundaries: Option<(String, String)>, expected: &models::OptionalBody, actual: &models::OptionalBody,
     config: DiffConfig, mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
     match (expected, actual) {
         (&models::OptionalBody::Missing, _) => (),
         (&models::OptionalBody::Null, &models::OptionalBody::Present(ref b)) => {
             mismatches.push(Mismatch::BodyMismatch { expected: None, actual: Some(b.clone()),
-                mismatch: format!("Expected empty body but received '{:?}'", b.clone()),
-                path: s!("/")});
+                mismatch: format!("Expected empty body but received '{}'", String::from_utf8_lossy(b)),
+                path: DocPath::root()});
         },
         (&models::OptionalBody::Empty, &models::OptionalBody::Present(ref b)) => {
             mismatches.push(Mismatch::BodyMismatch { expected: None, actual: Some(b.clone()),
-                mismatch: format!("Expected empty body but received '{:?}'", b.clone()),
-                path: s!("/")});
+                mismatch: format!("Expected empty body but received '{}'", String::from_utf8_lossy(b)),
+                path: DocPath::root()});
         },
         (&models::OptionalBody::Null, _) => (),
         (&models::OptionalBody::Empty, _) => (),
         (e, &models::OptionalBody::Missing) => {
             mismatches.push(Mismatch::BodyMismatch { expected: Some(e.value()), actual: None,
-                mismatch: format!("Expected body '{:?}' but was missing", e.value()),
-                path: s!("/")});
+                mismatch: format!("Expected body '{}' but was missing", String::from_utf8_lossy(&e.value())),
+                path: DocPath::root()});
         },
         (_, _) => {
-            compare_bodies(content_type, &expected.value(), &actual.value(),
+            compare_bodies(content_type, boundaries, &expected.value(), &actual.value(),
                 config, mismatches, matchers);
         }
     }
 }
 
+/// Returns the boundaries from the content type headers if the parts have multipart bodies
+fn multipart_boundaries(expected: &models::HttpPart, actual: &models::HttpPart) -> Option<(String, String)> {
+    if expected.content_type() != "multipart/form-data" {
+        return None;
+    }
+    let boundary = |part: &models::HttpPart| part.lookup_header_value(&s!("content-type"))
+        .and_then(|content_type| multipart::boundary(&content_type));
+    match (boundary(expected), boundary(actual)) {
+        (Some(expected_boundary), Some(actual_boundary)) => Some((expected_boundary, actual_boundary)),
+        _ => None
+    }
+}
+
 /// Matches the actual body to the expected one. This takes into account the content type of each.
 pub fn match_body(expected: &models::HttpPart, actual: &models::HttpPart, config: DiffConfig,
     mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
     debug!("expected content type = '{}', actual content type = '{}'", expected.content_type(),
            actual.content_type());
     if expected.content_type() == actual.content_type() {
-        match_body_content(expected.content_type(), expected.body(), actual.body(), config, mismatches, matchers)
+        match_body_content(expected.content_type(), multipart_boundaries(expected, actual), expected.body(),
+            actual.body(), config, mismatches, matchers)
     } else if expected.body().is_present() {
         mismatches.push(Mismatch::BodyTypeMismatch { expected: expected.content_type(),
             actual: actual.content_type() });
     }
 }
 
-/// Matches the expected and actual requests.
+/// Matches the expected and actual requests. Any unexpected keys in the actual body are mismatches.
 pub fn match_request(expected: models::Request, actual: models::Request) -> Vec<Mismatch> {
+    match_request_with_config(expected, actual, DiffConfig::NoUnexpectedKeys)
+}
+
+/// Matches the expected and actual requests, using the config to decide how unexpected keys in
+/// the actual body are handled.
+pub fn match_request_with_config(expected: models::Request, actual: models::Request,
+    config: DiffConfig) -> Vec<Mismatch> {
     let mut mismatches = vec![];
 
     info!("comparing to expected request: {:?}", expected);
     match_method(expected.method.clone(), actual.method.clone(), &mut mismatches);
     match_path(expected.path.clone(), actual.path.clone(), &mut mismatches, &expected.matching_rules);
-    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &expected.matching_rules);
+    match_body(&expected, &actual, config, &mut mismatches, &expected.matching_rules);
     match_query(expected.query, actual.query, &mut mismatches, &expected.matching_rules);
     match_headers(expected.headers, actual.headers, &mut mismatches, &expected.matching_rules);
 
@@ -930,13 +1130,44 @@ pub fn match_status(expected: u16, actual: u16, mismatches: &mut Vec<Mismatch>)
     }
 }
 
-/// Matches the actual and expected responses.
+/// Matches the expected and actual status codes, using any matching rule defined for the status
+/// code (like a status class) instead of equality.
+pub fn match_status_with_matchers(expected: u16, actual: u16, mismatches: &mut Vec<Mismatch>,
+    matchers: &MatchingRules) {
+    let rules = matchers.rules_for_category(&s!("status"))
+        .and_then(|category| category.rules.get("").cloned());
+    match rules {
+        Some(ref rulelist) if !rulelist.rules.is_empty() => {
+            let matched = rulelist.rules.iter().map(|rule| match *rule {
+                MatchingRule::StatusCode(ref status) => status.matches(actual),
+                _ => expected == actual
+            });
+            let matched = match rulelist.rule_logic {
+                RuleLogic::And => matched.fold(true, |acc, m| acc && m),
+                RuleLogic::Or => matched.fold(false, |acc, m| acc || m)
+            };
+            if !matched {
+                mismatches.push(Mismatch::StatusMismatch { expected, actual });
+            }
+        },
+        _ => match_status(expected, actual, mismatches)
+    }
+}
+
+/// Matches the actual and expected responses. Any unexpected keys in the actual body are ignored.
 pub fn match_response(expected: models::Response, actual: models::Response) -> Vec<Mismatch> {
+    match_response_with_config(expected, actual, DiffConfig::AllowUnexpectedKeys)
+}
+
+/// Matches the actual and expected responses, using the config to decide how unexpected keys in
+/// the actual body are handled.
+pub fn match_response_with_config(expected: models::Response, actual: models::Response,
+    config: DiffConfig) -> Vec<Mismatch> {
     let mut mismatches = vec![];
 
     info!("comparing to expected response: {:?}", expected);
-    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &expected.matching_rules);
-    match_status(expected.status, actual.status, &mut mismatches);
+    match_body(&expected, &actual, config, &mut mismatches, &expected.matching_rules);
+    match_status_with_matchers(expected.status, actual.status, &mut mismatches, &expected.matching_rules);
     match_headers(expected.headers, actual.headers, &mut mismatches, &expected.matching_rules);
 
     mismatches
@@ -946,7 +1177,7 @@ pub fn match_response(expected: models::Response, actual: models::Response) -> V
 pub fn match_message_contents(expected: &models::message::Message, actual: &models::message::Message, config: DiffConfig,
     mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
     if expected.mimetype() == actual.mimetype() {
-        match_body_content(expected.mimetype(), &expected.contents, &actual.contents, config, mismatches, matchers)
+        match_body_content(expected.mimetype(), None, &expected.contents, &actual.contents, config, mismatches, matchers)
     } else if expected.contents.is_present() {
         mismatches.push(Mismatch::BodyTypeMismatch { expected: expected.mimetype(),
             actual: actual.mimetype() });
@@ -963,24 +1194,37 @@ pub fn match_message(expected: models::message::Message, actual: models::message
     mismatches
 }
 
+/// Replaces each value of the header (ignoring the case of the header name) with a generated one
+fn generate_header_values(headers: &mut models::HeaderMap, key: &str, generator: &Generator,
+    context: &HashMap<String, Value>) {
+    if let Some(values) = headers.get_mut(key) {
+        for value in values.iter_mut() {
+            if let Some(v) = generator.generate_value(value, context) {
+                *value = v;
+            }
+        }
+    }
+}
+
 /// Generates the request by applying any defined generators
 pub fn generate_request(request: &models::Request) -> models::Request {
+    generate_request_with_context(request, &HashMap::new())
+}
+
+/// Generates the request by applying any defined generators. The context contains the values
+/// returned from the provider state callbacks, which are used by provider state generators.
+pub fn generate_request_with_context(request: &models::Request, context: &HashMap<String, Value>) -> models::Request {
     let generators = request.generators.clone();
     let mut request = request.clone();
     generators.apply_generator(&GeneratorCategory::PATH, |_, generator| {
-        match generator.generate_value(&request.path) {
+        match generator.generate_value(&request.path, context) {
             Some(v) => request.path = v,
             None => ()
         }
     });
     generators.apply_generator(&GeneratorCategory::HEADER, |key, generator| {
         match request.headers {
-            Some(ref mut headers) => if headers.contains_key(key) {
-                match generator.generate_value(&headers.get(key).unwrap().clone()) {
-                    Some(v) => headers.insert(key.clone(), v),
-                    None => None
-                };
-            },
+            Some(ref mut headers) => generate_header_values(headers, key, generator, context),
             None => ()
         }
     });
@@ -990,7 +1234,7 @@ pub fn generate_request(request: &models::Request) -> models::Request {
           Some(parameter) => {
             let mut generated = parameter.clone();
             for (index, val) in parameter.iter().enumerate() {
-              match generator.generate_value(val) {
+              match generator.generate_value(val, context) {
                 Some(v) => generated[index] = v,
                 None => ()
               };
@@ -1002,32 +1246,33 @@ pub fn generate_request(request: &models::Request) -> models::Request {
         None => ()
       }
     });
-    request.body = generators.apply_body_generators(&request.body, request.content_type_enum());
+    request.body = generators.generate_body(&request, context);
     request
 }
 
 /// Generates the response by applying any defined generators
 pub fn generate_response(response: &models::Response) -> models::Response {
+    generate_response_with_context(response, &HashMap::new())
+}
+
+/// Generates the response by applying any defined generators. The context contains the values
+/// that are used by provider state generators.
+pub fn generate_response_with_context(response: &models::Response, context: &HashMap<String, Value>) -> models::Response {
   let generators = response.generators.clone();
   let mut response = response.clone();
   generators.apply_generator(&GeneratorCategory::STATUS, |_, generator| {
-    match generator.generate_value(&response.status) {
+    match generator.generate_value(&response.status, context) {
       Some(v) => response.status = v,
       None => ()
     }
   });
   generators.apply_generator(&GeneratorCategory::HEADER, |key, generator| {
     match response.headers {
-      Some(ref mut headers) => if headers.contains_key(key) {
-        match generator.generate_value(&headers.get(key).unwrap().clone()) {
-          Some(v) => headers.insert(key.clone(), v),
-          None => None
-        };
-      },
+      Some(ref mut headers) => generate_header_values(headers, key, generator, context),
       None => ()
     }
   });
-  response.body = generators.apply_body_generators(&response.body, response.content_type_enum());
+  response.body = generators.generate_body(&response, context);
   response
 }
 
@@ -22,7 +22,7 @@ impl Matches<String> for String {
               Err(err) => Err(format!("'{}' is not a valid regular expression - {}", regex, err))
             }
           },
-          MatchingRule::Equality => {
+          MatchingRule::Equality | MatchingRule::Unordered => {
             if self == actual {
               Ok(())
             } else {
@@ -4,19 +4,22 @@ use std::{
   collections::HashMap,
   hash::{Hash, Hasher},
   str::FromStr,
-  ops::Index
+  ops::Index,
+  sync::{Arc, RwLock}
 };
 use serde_json::{self, Value};
 use super::PactSpecification;
 use rand::{self, Rng};
 use uuid::Uuid;
-use models::{OptionalBody, DetectedContentType};
+use models::{OptionalBody, DetectedContentType, HttpPart, parse_query_string, build_query_string};
 use models::json_utils::{JsonToNum, json_to_string};
 use models::xml_utils::parse_bytes;
-use sxd_document::dom::Document;
+use sxd_document::dom::{Document, Element, Text};
+use sxd_document::writer::format_document;
 use path_exp::*;
 use itertools::Itertools;
 use indextree::{Arena, NodeId};
+use regex::Regex;
 
 /// Trait to represent a generator
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Eq, Hash)]
@@ -40,7 +43,10 @@ pub enum Generator {
   /// Generates a random timestamp that matches either the provided format or the ISO format
   Timestamp(Option<String>),
   /// Generates a random boolean value
-  RandomBoolean
+  RandomBoolean,
+  /// Generates a value from the values returned by the provider state callbacks. The expression is
+  /// either the name of a value (`id`), or a string with `${id}` placeholders for the values.
+  ProviderStateGenerator(String)
 }
 
 impl Generator {
@@ -65,7 +71,8 @@ impl Generator {
         &Some(ref format) => json!({ "type": "Timestamp", "format": format }),
         &None => json!({ "type": "Timestamp" })
       },
-      &Generator::RandomBoolean => json!({ "type": "RandomBoolean" })
+      &Generator::RandomBoolean => json!({ "type": "RandomBoolean" }),
+      &Generator::ProviderStateGenerator(ref expression) => json!({ "type": "ProviderState", "expression": expression })
     }
   }
 
@@ -86,6 +93,7 @@ impl Generator {
       "Time" => Some(Generator::Time(map.get("format").map(|f| json_to_string(f)))),
       "Timestamp" => Some(Generator::Timestamp(map.get("format").map(|f| json_to_string(f)))),
       "RandomBoolean" => Some(Generator::RandomBoolean),
+      "ProviderState" => map.get("expression").map(|val| Generator::ProviderStateGenerator(json_to_string(val))),
       _ => {
         warn!("'{}' is not a valid generator type", gen_type);
         None
@@ -96,15 +104,55 @@ impl Generator {
 
 /// Trait that represents generation of a value based on a source value.
 pub trait GenerateValue<T> {
-  /// Generates a new value based on the source value. `None` will be returned if the value can not
-  /// be generated.
-  fn generate_value(&self, value: &T) -> Option<T>;
+  /// Generates a new value based on the source value. The context contains any values returned
+  /// from the provider state callbacks. `None` will be returned if the value can not be generated.
+  fn generate_value(&self, value: &T, context: &HashMap<String, Value>) -> Option<T>;
+}
+
+/// Evaluates a provider state expression against the values in the context. If the expression is
+/// a single `${name}` placeholder (or just the name), the value is returned as is, otherwise all
+/// the placeholders are replaced with the string form of the values. Returns `None` if any of the
+/// values are missing from the context.
+pub fn evaluate_expression(expression: &String, context: &HashMap<String, Value>) -> Option<Value> {
+  if !expression.contains("${") {
+    return context.get(expression).cloned();
+  }
+  if expression.starts_with("${") && expression.ends_with('}') && expression.matches("${").count() == 1 {
+    return context.get(&expression[2..expression.len() - 1]).cloned();
+  }
+
+  let mut result = String::new();
+  let mut remaining = expression.as_str();
+  while let Some(start) = remaining.find("${") {
+    result.push_str(&remaining[..start]);
+    match remaining[start..].find('}') {
+      Some(end) => {
+        let name = &remaining[start + 2..start + end];
+        match context.get(name) {
+          Some(value) => result.push_str(&json_to_string(value)),
+          None => {
+            warn!("Provider state expression '{}' refers to '{}', which is not available", expression, name);
+            return None
+          }
+        }
+        remaining = &remaining[start + end + 1..];
+      },
+      None => break
+    }
+  }
+  result.push_str(remaining);
+  Some(Value::String(result))
 }
 
 impl GenerateValue<u16> for Generator {
-  fn generate_value(&self, _: &u16) -> Option<u16> {
+  fn generate_value(&self, _: &u16, context: &HashMap<String, Value>) -> Option<u16> {
     match self {
       &Generator::RandomInt(min, max) => Some(rand::thread_rng().gen_range(min as u16, max as u16 + 1)),
+      &Generator::ProviderStateGenerator(ref expression) => match evaluate_expression(expression, context) {
+        Some(Value::Number(ref n)) => n.as_u64().map(|n| n as u16),
+        Some(Value::String(ref s)) => s.parse().ok(),
+        _ => None
+      },
       _ => None
     }
   }
@@ -127,7 +175,7 @@ fn generate_ascii_string(size: usize) -> String {
 }
 
 impl GenerateValue<String> for Generator {
-  fn generate_value(&self, _: &String) -> Option<String> {
+  fn generate_value(&self, _: &String, context: &HashMap<String, Value>) -> Option<String> {
     let mut rnd = rand::thread_rng();
     match self {
       &Generator::RandomInt(min, max) => Some(format!("{}", rnd.gen_range(min, max + 1))),
@@ -151,13 +199,15 @@ impl GenerateValue<String> for Generator {
         warn!("Timestamp generator is not implemented");
         None
       },
-      &Generator::RandomBoolean => Some(format!("{}", rnd.gen::<bool>()))
+      &Generator::RandomBoolean => Some(format!("{}", rnd.gen::<bool>())),
+      &Generator::ProviderStateGenerator(ref expression) => evaluate_expression(expression, context)
+        .map(|value| json_to_string(&value))
     }
   }
 }
 
 impl GenerateValue<Value> for Generator {
-  fn generate_value(&self, value: &Value) -> Option<Value> {
+  fn generate_value(&self, value: &Value, context: &HashMap<String, Value>) -> Option<Value> {
     match self {
       &Generator::RandomInt(min, max) => {
         let rand_int = rand::thread_rng().gen_range(min, max + 1);
@@ -203,7 +253,12 @@ impl GenerateValue<Value> for Generator {
         warn!("Timestamp generator is not implemented");
         None
       },
-      &Generator::RandomBoolean => Some(json!(rand::thread_rng().gen::<bool>()))
+      &Generator::RandomBoolean => Some(json!(rand::thread_rng().gen::<bool>())),
+      &Generator::ProviderStateGenerator(ref expression) => match evaluate_expression(expression, context) {
+        Some(Value::String(ref s)) if !value.is_string() => Some(serde_json::from_str(s).unwrap_or(json!(s))),
+        Some(ref generated) if value.is_string() => Some(json!(json_to_string(generated))),
+        generated => generated
+      }
     }
   }
 }
@@ -264,9 +319,35 @@ impl Into<String> for GeneratorCategory {
 /// Trait to define a handler for applying generators to data of a particular content type.
 pub trait ContentTypeHandler<T> {
   /// Processes the body using the map of generators, returning a (possibly) updated body.
-  fn process_body(&mut self, generators: &HashMap<String, Generator>) -> OptionalBody;
+  fn process_body(&mut self, generators: &HashMap<String, Generator>, context: &HashMap<String, Value>) -> OptionalBody;
   /// Applies the generator to the key in the body.
-  fn apply_key(&mut self, key: &String, generator: &Generator);
+  fn apply_key(&mut self, key: &String, generator: &Generator, context: &HashMap<String, Value>);
+}
+
+/// Function that creates a content type handler for a body. Handlers for other content types work
+/// with the raw bytes of the body, so implement `ContentTypeHandler<Vec<u8>>`. An error must be
+/// returned if the body can not be parsed.
+pub type ContentTypeHandlerFactory = Arc<Fn(&OptionalBody) -> Result<Box<ContentTypeHandler<Vec<u8>>>, String> + Send + Sync>;
+
+lazy_static! {
+  static ref CONTENT_TYPE_HANDLERS: RwLock<Vec<(Regex, ContentTypeHandlerFactory)>> = RwLock::new(vec![]);
+}
+
+/// Registers a content type handler for applying generators to bodies with content types that
+/// match the regular expression. Handlers are checked in the reverse order that they were registered
+/// in, and are used in preference to the built-in JSON and XML ones. Returns an error if the regular
+/// expression is not valid.
+pub fn register_content_type_handler(content_type: &str, factory: ContentTypeHandlerFactory) -> Result<(), String> {
+  let regex = Regex::new(content_type)
+    .map_err(|err| format!("'{}' is not a valid content type regular expression - {}", content_type, err))?;
+  CONTENT_TYPE_HANDLERS.write().unwrap().insert(0, (regex, factory));
+  Ok(())
+}
+
+fn content_type_handler_for(content_type: &str) -> Option<ContentTypeHandlerFactory> {
+  CONTENT_TYPE_HANDLERS.read().unwrap().iter()
+    .find(|handler| handler.0.is_match(content_type))
+    .map(|handler| handler.1.clone())
 }
 
 /// Implementation of a content type handler for JSON
@@ -352,14 +433,14 @@ impl JsonHandler {
 }
 
 impl ContentTypeHandler<Value> for JsonHandler {
-  fn process_body(&mut self, generators: &HashMap<String, Generator>) -> OptionalBody {
+  fn process_body(&mut self, generators: &HashMap<String, Generator>, context: &HashMap<String, Value>) -> OptionalBody {
     for (key, generator) in generators {
-      self.apply_key(key, generator);
+      self.apply_key(key, generator, context);
     };
     OptionalBody::Present(self.value.to_string().into())
   }
 
-  fn apply_key(&mut self, key: &String, generator: &Generator) {
+  fn apply_key(&mut self, key: &String, generator: &Generator, context: &HashMap<String, Value>) {
     match parse_path_exp(key.clone()) {
       Ok(path_exp) => {
         let mut tree = Arena::new();
@@ -379,7 +460,7 @@ impl ContentTypeHandler<Value> for JsonHandler {
         if !expanded_paths.is_empty() {
           for pointer_str in expanded_paths {
             match self.value.pointer_mut(&pointer_str) {
-              Some(json_value) => match generator.generate_value(&json_value.clone()) {
+              Some(json_value) => match generator.generate_value(&json_value.clone(), context) {
                 Some(new_value) => *json_value = new_value,
                 None => ()
               },
@@ -387,7 +468,7 @@ impl ContentTypeHandler<Value> for JsonHandler {
             }
           }
         } else if path_exp.len() == 1 {
-          match generator.generate_value(&self.value.clone()) {
+          match generator.generate_value(&self.value.clone(), context) {
             Some(new_value) => self.value = new_value,
             None => ()
           }
@@ -398,19 +479,132 @@ impl ContentTypeHandler<Value> for JsonHandler {
   }
 }
 
-/// Implementation of a content type handler for XML (currently unimplemented).
+/// Implementation of a content type handler for XML. Generator paths use the same form as the XML
+/// matching rules, so `$.order['@id']` refers to the `id` attribute of the root `order` element,
+/// `$.order[0].note['#text']` to the text of its first child element (which must be a `note`), and
+/// `$.order[*].item['@sku']` to the `sku` attribute of all its child `item` elements.
 pub struct XmlHandler<'a> {
   /// XML document to apply the generators to.
   pub value: Document<'a>
 }
 
+impl <'a> XmlHandler<'a> {
+  fn apply_to_element(element: Element<'a>, path_exp: &[PathToken], generator: &Generator,
+                      context: &HashMap<String, Value>) {
+    match path_exp.first() {
+      Some(PathToken::Field(name)) if name == element.name().local_part() => (),
+      Some(&PathToken::Star) => (),
+      _ => return
+    }
+    match path_exp[1..] {
+      [PathToken::Field(ref name)] if name == "#text" => {
+        let texts: Vec<Text> = element.children().iter().filter_map(|child| child.text()).collect();
+        let text: String = texts.iter().map(|text| text.text()).collect();
+        if let Some(new_value) = generator.generate_value(&s!(text.trim()), context) {
+          match texts.split_first() {
+            Some((first, rest)) => {
+              first.set_text(&new_value);
+              for text in rest {
+                text.set_text("");
+              }
+            },
+            None => element.append_child(element.document().create_text(&new_value))
+          }
+        }
+      },
+      [PathToken::Field(ref name)] if name.starts_with('@') => {
+        let attribute = element.attributes().into_iter()
+          .find(|attribute| attribute.name().local_part() == &name[1..]);
+        if let Some(attribute) = attribute {
+          if let Some(new_value) = generator.generate_value(&s!(attribute.value()), context) {
+            element.set_attribute_value(attribute.name(), &new_value);
+          }
+        }
+      },
+      [PathToken::Index(_), ..] | [PathToken::StarIndex, ..] => {
+        let children = element.children().into_iter().filter_map(|child| child.element()).enumerate();
+        for (index, child) in children {
+          if path_exp[1] == PathToken::StarIndex || path_exp[1] == PathToken::Index(index) {
+            XmlHandler::apply_to_element(child, &path_exp[2..], generator, context);
+          }
+        }
+      },
+      _ => ()
+    }
+  }
+}
+
 impl <'a> ContentTypeHandler<Document<'a>> for XmlHandler<'a> {
-  fn process_body(&mut self, _generators: &HashMap<String, Generator>) -> OptionalBody {
-    unimplemented!()
+  fn process_body(&mut self, generators: &HashMap<String, Generator>, context: &HashMap<String, Value>) -> OptionalBody {
+    for (key, generator) in generators {
+      self.apply_key(key, generator, context);
+    };
+    let mut buffer = vec![];
+    match format_document(&self.value, &mut buffer) {
+      Ok(()) => OptionalBody::Present(buffer),
+      Err(err) => {
+        error!("Failed to write the XML body with the generated values: {}", err);
+        OptionalBody::Missing
+      }
+    }
   }
 
-  fn apply_key(&mut self, _key: &String, _generator: &Generator) {
-    unimplemented!()
+  fn apply_key(&mut self, key: &String, generator: &Generator, context: &HashMap<String, Value>) {
+    match parse_path_exp(key.clone()) {
+      Ok(path_exp) => match path_exp.split_first() {
+        Some((&PathToken::Root, rest)) => {
+          let root = self.value.root().children().into_iter().filter_map(|child| child.element()).next();
+          if let Some(root) = root {
+            XmlHandler::apply_to_element(root, rest, generator, context);
+          }
+        },
+        _ => warn!("Generator path '{}' does not start at the root of the document, ignoring", key)
+      },
+      Err(err) => warn!("Generator path '{}' is invalid, ignoring: {}", key, err)
+    }
+  }
+}
+
+/// Implementation of a content type handler for form post (application/x-www-form-urlencoded)
+/// bodies. Generator paths refer to the fields of the form, like `$.name`, and are applied to all
+/// the values of the field unless an index is given.
+pub struct FormUrlEncodedHandler {
+  /// Fields of the form to apply the generators to.
+  pub params: HashMap<String, Vec<String>>
+}
+
+impl ContentTypeHandler<HashMap<String, Vec<String>>> for FormUrlEncodedHandler {
+  fn process_body(&mut self, generators: &HashMap<String, Generator>, context: &HashMap<String, Value>) -> OptionalBody {
+    for (key, generator) in generators {
+      self.apply_key(key, generator, context);
+    };
+    OptionalBody::Present(build_query_string(self.params.clone()).into())
+  }
+
+  fn apply_key(&mut self, key: &String, generator: &Generator, context: &HashMap<String, Value>) {
+    match parse_path_exp(key.clone()) {
+      Ok(path_exp) => {
+        let (name, index) = match *path_exp.as_slice() {
+          [PathToken::Root, PathToken::Field(ref name)] => (name.clone(), None),
+          [PathToken::Root, PathToken::Field(ref name), PathToken::StarIndex] => (name.clone(), None),
+          [PathToken::Root, PathToken::Field(ref name), PathToken::Index(index)] => (name.clone(), Some(index)),
+          _ => {
+            warn!("Generator path '{}' does not refer to a form field, ignoring", key);
+            return
+          }
+        };
+        if let Some(values) = self.params.get_mut(&name) {
+          for (i, value) in values.iter_mut().enumerate() {
+            if index.is_none() || index == Some(i) {
+              if let Some(new_value) = generator.generate_value(&value.clone(), context) {
+                *value = new_value;
+              }
+            }
+          }
+        }
+      },
+      Err(err) => warn!("Generator path '{}' is invalid, ignoring: {}", key, err)
+    }
   }
 }
 
@@ -492,9 +686,11 @@ impl Generators {
           }
         },
         _ => {
-          for (key, val) in category {
-            map.insert(cat.clone(), json!({ key.as_str(): val.to_json() }));
-          }
+          let generators = category.iter().fold(serde_json::Map::new(), |mut map, (key, val)| {
+            map.insert(key.clone(), val.to_json());
+            map
+          });
+          map.insert(cat.clone(), Value::Object(generators));
         }
       }
       map
@@ -524,8 +720,31 @@ impl Generators {
     }
   }
 
+  /// Applies all the body generators to the body of the HTTP part and returns a new body (if
+  /// anything was applied). Any content type handler registered for the content type of the part is
+  /// used in preference to the built-in ones.
+  pub fn generate_body(&self, part: &HttpPart, context: &HashMap<String, Value>) -> OptionalBody {
+    let body = part.body();
+    match content_type_handler_for(&part.content_type()) {
+      Some(factory) => if body.is_present() && self.categories.contains_key(&GeneratorCategory::BODY) &&
+        !self.categories[&GeneratorCategory::BODY].is_empty() {
+        match factory(body) {
+          Ok(mut handler) => handler.process_body(&self.categories[&GeneratorCategory::BODY], context),
+          Err(err) => {
+            error!("Failed to parse the body, so not applying any generators: {}", err);
+            body.clone()
+          }
+        }
+      } else {
+        body.clone()
+      },
+      None => self.apply_body_generators(body, part.content_type_enum(), context)
+    }
+  }
+
   /// Applies all the body generators to the body and returns a new body (if anything was applied).
-  pub fn apply_body_generators(&self, body: &OptionalBody, content_type: DetectedContentType) -> OptionalBody {
+  pub fn apply_body_generators(&self, body: &OptionalBody, content_type: DetectedContentType,
+                               context: &HashMap<String, Value>) -> OptionalBody {
     if body.is_present() && self.categories.contains_key(&GeneratorCategory::BODY) &&
       !self.categories[&GeneratorCategory::BODY].is_empty() {
       let generators = &self.categories[&GeneratorCategory::BODY];
@@ -535,7 +754,7 @@ impl Generators {
           match result {
             Ok(val) => {
               let mut handler = JsonHandler { value: val };
-              handler.process_body(&generators)
+              handler.process_body(&generators, context)
             },
             Err(err) => {
               error!("Failed to parse the body, so not applying any generators: {}", err);
@@ -546,13 +765,19 @@ impl Generators {
         DetectedContentType::Xml => match parse_bytes(&body.value()) {
           Ok(val) => {
             let mut handler = XmlHandler { value: val.as_document() };
-            handler.process_body(&generators)
+            handler.process_body(&generators, context)
           },
           Err(err) => {
             error!("Failed to parse the body, so not applying any generators: {}", err);
             body.clone()
           }
         },
+        DetectedContentType::FormUrlEncoded => {
+          let mut handler = FormUrlEncodedHandler {
+            params: parse_query_string(&body.str_value().to_string()).unwrap_or_default()
+          };
+          handler.process_body(generators, context)
+        },
         _ => body.clone()
       }
     } else {
@@ -698,6 +923,13 @@ mod tests {
     expect!(Generator::from_map(&s!("RandomBoolean"), &serde_json::Map::new())).to(be_some().value(Generator::RandomBoolean));
   }
 
+  #[test]
+  fn provider_state_generator_from_json_test() {
+    expect!(Generator::from_map(&s!("ProviderState"), &serde_json::Map::new())).to(be_none());
+    expect!(Generator::from_map(&s!("ProviderState"), &json!({ "expression": "${id}" }).as_object().unwrap()))
+      .to(be_some().value(Generator::ProviderStateGenerator(s!("${id}"))));
+  }
+
   #[test]
   fn randomint_generator_from_json_test() {
     expect!(Generator::from_map(&s!("RandomInt"), &serde_json::Map::new())).to(be_some().value(Generator::RandomInt(0, 10)));
@@ -810,5 +1042,33 @@ mod tests {
     expect!(Generator::Timestamp(None).to_json()).to(be_equal_to(json!({
       "type": "Timestamp"
     })));
+    expect!(Generator::ProviderStateGenerator(s!("/orders/${id}")).to_json()).to(be_equal_to(json!({
+      "type": "ProviderState",
+      "expression": "/orders/${id}"
+    })));
+  }
+
+  #[test]
+  fn evaluate_expression_test() {
+    let context = hashmap!{
+      s!("id") => json!(100),
+      s!("name") => json!("Fred")
+    };
+    expect!(evaluate_expression(&s!("id"), &context)).to(be_some().value(json!(100)));
+    expect!(evaluate_expression(&s!("${id}"), &context)).to(be_some().value(json!(100)));
+    expect!(evaluate_expression(&s!("/users/${id}/${name}"), &context)).to(be_some().value(json!("/users/100/Fred")));
+    expect!(evaluate_expression(&s!("other"), &context)).to(be_none());
+    expect!(evaluate_expression(&s!("/users/${other}"), &context)).to(be_none());
+  }
+
+  #[test]
+  fn provider_state_generator_test() {
+    let context = hashmap!{ s!("id") => json!(100) };
+    let generator = Generator::ProviderStateGenerator(s!("${id}"));
+    expect!(generator.generate_value(&json!(1), &context)).to(be_some().value(json!(100)));
+    expect!(generator.generate_value(&json!("1"), &context)).to(be_some().value(json!("100")));
+    expect!(generator.generate_value(&s!("1"), &context)).to(be_some().value(s!("100")));
+    expect!(generator.generate_value(&200u16, &context)).to(be_some().value(100u16));
+    expect!(generator.generate_value(&json!(1), &hashmap!{})).to(be_none());
   }
 }
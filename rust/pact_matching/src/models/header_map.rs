@@ -0,0 +1,247 @@
+//! `header_map` module contains the map used for the headers of requests and responses. Header
+//! names are not case sensitive, and a header can have multiple values.
+
+use std::collections::{BTreeMap, HashMap};
+use std::collections::btree_map;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::Index;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+/// Map of header names to their values. Looking up, comparing and hashing headers ignores the case
+/// of the header names, while the name a header was first added with is kept, so it can be
+/// displayed and written out to pact files as it was given.
+#[derive(Clone, Default)]
+pub struct HeaderMap {
+    // Keyed by the lowercased header name, with the name as it was given
+    headers: BTreeMap<String, (String, Vec<String>)>
+}
+
+impl HeaderMap {
+
+    /// Creates an empty header map
+    pub fn new() -> HeaderMap {
+        HeaderMap { headers: BTreeMap::new() }
+    }
+
+    /// Number of headers in the map
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// If the map has no headers
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Returns the values of the header, ignoring the case of the name
+    pub fn get(&self, name: &str) -> Option<&Vec<String>> {
+        self.headers.get(&name.to_lowercase()).map(|(_, values)| values)
+    }
+
+    /// Returns the values of the header to modify, ignoring the case of the name
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Vec<String>> {
+        self.headers.get_mut(&name.to_lowercase()).map(|(_, values)| values)
+    }
+
+    /// Returns the name the header was added with and its values, ignoring the case of the name
+    pub fn get_key_value(&self, name: &str) -> Option<(&String, &Vec<String>)> {
+        self.headers.get(&name.to_lowercase()).map(|(key, values)| (key, values))
+    }
+
+    /// If the map has the header, ignoring the case of the name
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.headers.contains_key(&name.to_lowercase())
+    }
+
+    /// Sets the values of a header, replacing the header if it is already in the map with any case.
+    /// Returns the values that were replaced.
+    pub fn insert<N: Into<String>>(&mut self, name: N, values: Vec<String>) -> Option<Vec<String>> {
+        let name = name.into();
+        self.headers.insert(name.to_lowercase(), (name, values)).map(|(_, values)| values)
+    }
+
+    /// Adds values to a header. If the header is already in the map with any case, the values are
+    /// added after its existing values.
+    pub fn add<N: Into<String>>(&mut self, name: N, values: Vec<String>) {
+        let name = name.into();
+        self.headers.entry(name.to_lowercase()).or_insert_with(|| (name, vec![])).1.extend(values);
+    }
+
+    /// Removes a header, ignoring the case of the name. Returns the values of the removed header.
+    pub fn remove(&mut self, name: &str) -> Option<Vec<String>> {
+        self.headers.remove(&name.to_lowercase()).map(|(_, values)| values)
+    }
+
+    /// Only keeps the headers that the predicate returns true for
+    pub fn retain<F>(&mut self, mut predicate: F) where F: FnMut(&String, &mut Vec<String>) -> bool {
+        let keys: Vec<String> = self.headers.iter_mut()
+            .filter_map(|(key, (name, values))| if predicate(name, values) {
+                None
+            } else {
+                Some(key.clone())
+            })
+            .collect();
+        for key in keys {
+            self.headers.remove(&key);
+        }
+    }
+
+    /// Iterates over the header names, as they were added, and their values
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { inner: self.headers.values() }
+    }
+}
+
+impl PartialEq for HeaderMap {
+    fn eq(&self, other: &HeaderMap) -> bool {
+        self.headers.len() == other.headers.len() &&
+            self.headers.iter().all(|(key, (_, values))| match other.headers.get(key) {
+                Some((_, other_values)) => values == other_values,
+                None => false
+            })
+    }
+}
+
+impl Eq for HeaderMap {}
+
+impl Hash for HeaderMap {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for (key, (_, values)) in &self.headers {
+            key.hash(state);
+            values.hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for HeaderMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Index<&str> for HeaderMap {
+    type Output = Vec<String>;
+
+    fn index(&self, name: &str) -> &Vec<String> {
+        self.get(name).expect("no entry found for header")
+    }
+}
+
+impl FromIterator<(String, Vec<String>)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item=(String, Vec<String>)>>(iter: I) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, values) in iter {
+            headers.add(name, values);
+        }
+        headers
+    }
+}
+
+impl From<HashMap<String, Vec<String>>> for HeaderMap {
+    fn from(headers: HashMap<String, Vec<String>>) -> HeaderMap {
+        headers.into_iter().collect()
+    }
+}
+
+/// Iterator over the headers in a `HeaderMap`
+pub struct Iter<'a> {
+    inner: btree_map::Values<'a, String, (String, Vec<String>)>
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a Vec<String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(name, values)| (name, values))
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a String, &'a Vec<String>);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator that takes the headers out of a `HeaderMap`
+pub struct IntoIter {
+    inner: btree_map::IntoIter<String, (String, Vec<String>)>
+}
+
+impl Iterator for IntoIter {
+    type Item = (String, Vec<String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(_, header)| header)
+    }
+}
+
+impl IntoIterator for HeaderMap {
+    type Item = (String, Vec<String>);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { inner: self.headers.into_iter() }
+    }
+}
+
+impl Serialize for HeaderMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for HeaderMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<HeaderMap, D::Error> {
+        HashMap::<String, Vec<String>>::deserialize(deserializer).map(HeaderMap::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash(headers: &HeaderMap) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        headers.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn header_names_are_not_case_sensitive() {
+        let mut headers = HeaderMap::new();
+        headers.add("Content-Type", vec![s!("application/json")]);
+        expect!(headers.get("content-type")).to(be_some().value(&vec![s!("application/json")]));
+        expect!(headers.contains_key("CONTENT-TYPE")).to(be_true());
+        expect!(headers.get_key_value("content-TYPE")).to(
+            be_some().value((&s!("Content-Type"), &vec![s!("application/json")])));
+        expect!(&headers["content-type"]).to(be_equal_to(&vec![s!("application/json")]));
+
+        headers.add("VARY", vec![s!("Accept")]);
+        headers.add("vary", vec![s!("Origin")]);
+        expect!(headers.iter().map(|(name, values)| (name.clone(), values.clone())).collect::<Vec<_>>()).to(
+            be_equal_to(vec![(s!("Content-Type"), vec![s!("application/json")]),
+                (s!("VARY"), vec![s!("Accept"), s!("Origin")])]));
+
+        expect!(headers.insert("vary", vec![s!("*")])).to(be_some().value(vec![s!("Accept"), s!("Origin")]));
+        expect!(headers.get_key_value("Vary")).to(be_some().value((&s!("vary"), &vec![s!("*")])));
+        expect!(headers.remove("VaRy")).to(be_some().value(vec![s!("*")]));
+        expect!(headers.len()).to(be_equal_to(1));
+    }
+
+    #[test]
+    fn header_maps_are_compared_and_hashed_ignoring_the_case_of_the_names() {
+        let headers: HeaderMap = hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into();
+        let lowercase: HeaderMap = hashmap!{ s!("content-type") => vec![s!("text/plain")] }.into();
+        let other: HeaderMap = hashmap!{ s!("Content-Type") => vec![s!("text/html")] }.into();
+        expect!(headers.clone()).to(be_equal_to(lowercase.clone()));
+        expect!(hash(&headers)).to(be_equal_to(hash(&lowercase)));
+        expect!(headers).to_not(be_equal_to(other));
+    }
+}
@@ -9,7 +9,7 @@ use std::{
 use std::str::FromStr;
 use models::json_utils::{json_to_string, json_to_num};
 use path_exp::*;
-use super::PactSpecification;
+use super::{PactSpecification, query_parameter_name};
 
 fn matches_token(path_fragment: &String, path_token: &PathToken) -> usize {
   match *path_token {
@@ -88,7 +88,87 @@ pub enum MatchingRule {
   /// Match if the value is a decimal number
   Decimal,
   /// Match if the value is a null value (this is content specific, for JSON will match a JSON null)
-  Null
+  Null,
+  /// Match binary data by its content type, which is detected from the magic bytes of the data
+  ContentType(String),
+  /// Match the values of a list in any order, comparing the values using equality. This is not
+  /// part of the Pact specification, and is only read from and written to the query category of a pact
+  Unordered,
+  /// Match a response status code by its class, or against a list of status codes
+  StatusCode(HttpStatus)
+}
+
+/// Classes of HTTP status codes, used to match response statuses
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Eq, Hash)]
+pub enum HttpStatus {
+  /// Informational responses (100-199)
+  Information,
+  /// Successful responses (200-299)
+  Success,
+  /// Redirects (300-399)
+  Redirect,
+  /// Client errors (400-499)
+  ClientError,
+  /// Server errors (500-599)
+  ServerError,
+  /// Any of the listed status codes
+  StatusCodes(Vec<u16>),
+  /// Any status code which is not an error (100-399)
+  NonError,
+  /// Any error status code (400-599)
+  Error
+}
+
+impl HttpStatus {
+
+  /// Builds a `HttpStatus` from a `Value` struct, which is either the name of the class or an
+  /// array of status codes
+  pub fn from_json(value: &Value) -> Option<HttpStatus> {
+    match *value {
+      Value::Array(ref codes) => Some(HttpStatus::StatusCodes(codes.iter()
+        .filter_map(|code| code.as_u64().map(|code| code as u16)).collect())),
+      Value::String(ref name) => match name.as_str() {
+        "info" => Some(HttpStatus::Information),
+        "success" => Some(HttpStatus::Success),
+        "redirect" => Some(HttpStatus::Redirect),
+        "clientError" => Some(HttpStatus::ClientError),
+        "serverError" => Some(HttpStatus::ServerError),
+        "nonError" => Some(HttpStatus::NonError),
+        "error" => Some(HttpStatus::Error),
+        _ => None
+      },
+      _ => None
+    }
+  }
+
+  /// Converts this `HttpStatus` to a `Value` struct
+  pub fn to_json(&self) -> Value {
+    match *self {
+      HttpStatus::StatusCodes(ref codes) => json!(codes),
+      HttpStatus::Information => json!("info"),
+      HttpStatus::Success => json!("success"),
+      HttpStatus::Redirect => json!("redirect"),
+      HttpStatus::ClientError => json!("clientError"),
+      HttpStatus::ServerError => json!("serverError"),
+      HttpStatus::NonError => json!("nonError"),
+      HttpStatus::Error => json!("error")
+    }
+  }
+
+  /// If the status code belongs to this class
+  pub fn matches(&self, status: u16) -> bool {
+    match *self {
+      HttpStatus::Information => (100..200).contains(&status),
+      HttpStatus::Success => (200..300).contains(&status),
+      HttpStatus::Redirect => (300..400).contains(&status),
+      HttpStatus::ClientError => (400..500).contains(&status),
+      HttpStatus::ServerError => (500..600).contains(&status),
+      HttpStatus::StatusCodes(ref codes) => codes.contains(&status),
+      HttpStatus::NonError => status < 400,
+      HttpStatus::Error => status >= 400
+    }
+  }
+
 }
 
 impl MatchingRule {
@@ -140,11 +220,19 @@ impl MatchingRule {
               None => None
             },
             "null" => Some(MatchingRule::Null),
+            "contentType" => match m.get("value") {
+              Some(s) => Some(MatchingRule::ContentType(json_to_string(s))),
+              None => None
+            },
+            "unordered" => Some(MatchingRule::Unordered),
+            "statusCode" => m.get("status").and_then(HttpStatus::from_json).map(MatchingRule::StatusCode),
             _ => None
           }
         },
         None => if let Some(val) = m.get("regex") {
             Some(MatchingRule::Regex(json_to_string(val)))
+          } else if let (Some(min), Some(max)) = (json_to_num(m.get("min").cloned()), json_to_num(m.get("max").cloned())) {
+            Some(MatchingRule::MinMaxType(min, max))
           } else if let Some(val) = json_to_num(m.get("min").cloned()) {
             Some(MatchingRule::MinType(val))
           } else if let Some(val) = json_to_num(m.get("max").cloned()) {
@@ -187,7 +275,12 @@ impl MatchingRule {
       &MatchingRule::Number => json!({ "match": Value::String(s!("number")) }),
       &MatchingRule::Integer => json!({ "match": Value::String(s!("integer")) }),
       &MatchingRule::Decimal => json!({ "match": Value::String(s!("decimal")) }),
-      &MatchingRule::Null => json!({ "match": Value::String(s!("null")) })
+      &MatchingRule::Null => json!({ "match": Value::String(s!("null")) }),
+      &MatchingRule::ContentType(ref ct) => json!({ "match": Value::String(s!("contentType")),
+        "value": Value::String(ct.clone()) }),
+      &MatchingRule::Unordered => json!({ "match": Value::String(s!("unordered")) }),
+      &MatchingRule::StatusCode(ref status) => json!({ "match": Value::String(s!("statusCode")),
+        "status": status.to_json() })
     }
   }
 
@@ -290,6 +383,8 @@ impl Category {
   /// Adds a rule from the Value representation
   pub fn rule_from_json(&mut self, key: &String, matcher_json: &Value, rule_logic: &RuleLogic) {
     match MatchingRule::from_json(matcher_json) {
+      Some(MatchingRule::Unordered) if self.name != "query" =>
+        warn!("Ignoring unordered matcher for {} '{}', it is only supported for query parameters", self.name, key),
       Some(matching_rule) => {
         let rules = self.rules.entry(key.clone()).or_insert(RuleList::default(rule_logic));
         rules.rules.push(matching_rule);
@@ -315,15 +410,37 @@ impl Category {
   }
 
   fn max_by_path(&self, path: &Vec<String>) -> Option<RuleList> {
+    // Where two paths have the same weight, the longer one is more specific
     self.rules.iter().map(|(k, v)| (k, v, calc_path_weight(k.clone(), path)))
       .filter(|&(_, _, w)| w > 0)
-      .max_by_key(|&(_, _, w)| w)
+      .max_by_key(|&(k, _, w)| (w, path_length(k.clone())))
       .map(|(_, v, _)| v.clone())
   }
 
+  /// The rules to write out to a pact file. Unordered matchers are only written out for query
+  /// parameters, as they are not supported anywhere else
+  fn rules_to_write(&self) -> HashMap<String, RuleList> {
+    if self.name == "query" {
+      return self.rules.clone();
+    }
+    self.rules.iter()
+      .map(|(k, v)| (k.clone(), RuleList {
+        rules: v.rules.iter().filter(|rule| **rule != MatchingRule::Unordered).cloned().collect(),
+        rule_logic: v.rule_logic.clone()
+      }))
+      .filter(|&(_, ref v)| !v.rules.is_empty())
+      .collect()
+  }
+
   /// Returns a JSON Value representation in V3 format
   pub fn to_v3_json(&self) -> Value {
-    Value::Object(self.rules.iter().fold(serde_json::Map::new(), |mut map, (category, rulelist)| {
+    // The status code has no sub-categories, so its rules are written out directly
+    if self.name == "status" {
+      if let Some(rulelist) = self.rules.get("") {
+        return rulelist.to_v3_json();
+      }
+    }
+    Value::Object(self.rules_to_write().iter().fold(serde_json::Map::new(), |mut map, (category, rulelist)| {
       map.insert(category.clone(), rulelist.to_v3_json());
       map
     }))
@@ -333,12 +450,14 @@ impl Category {
   pub fn to_v2_json(&self) -> HashMap<String, Value> {
     let mut map = hashmap!{};
 
-    if self.name == "body" {
-      for (k, v) in self.rules.clone() {
+    if self.name == "status" {
+      // Status code matching is not supported by the V2 format
+    } else if self.name == "body" {
+      for (k, v) in self.rules_to_write() {
         map.insert(k.replace("$", "$.body"), v.to_v2_json());
       }
     } else {
-      for (k, v) in self.rules.clone() {
+      for (k, v) in self.rules_to_write() {
         map.insert(format!("$.{}.{}", self.name, k), v.to_v2_json());
       }
     }
@@ -423,10 +542,14 @@ impl MatchingRules {
 
     /// Returns a `Category` filtered with all rules that match the given path.
     pub fn resolve_matchers(&self, category: &str, path: &Vec<String>) -> Option<Category> {
-      if category == "body" || category == "header" || category == "query" {
+      if category == "body" || category == "header" {
         self.rules_for_category(&s!(category)).map(|category| category.filter(|&(val, _)| {
           calc_path_weight(val.clone(), path) > 0
         }))
+      } else if category == "query" {
+        self.rules_for_category(&s!(category)).map(|category| category.filter(|&(val, _)| {
+          calc_path_weight(s!(query_parameter_name(val)), path) > 0
+        }))
       } else {
         self.rules_for_category(&s!(category))
       }
@@ -479,7 +602,7 @@ impl MatchingRules {
 
     fn add_rules(&mut self, category_name: &String, rules: &Value) {
       let category = self.add_category(category_name.clone());
-      if category_name == "path" && rules.get("matchers").is_some() {
+      if (category_name == "path" || category_name == "status") && rules.get("matchers").is_some() {
         let rule_logic = match rules.get("combine") {
           Some(val) => if json_to_string(val).to_uppercase() == "OR" {
               RuleLogic::Or
@@ -836,6 +959,8 @@ mod tests {
       be_some().value(MatchingRule::MinType(1)));
     expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"max\", \"max\": \"1\"}").unwrap())).to(
       be_some().value(MatchingRule::MaxType(1)));
+    expect!(MatchingRule::from_json(&Value::from_str("{\"min\": 1, \"max\": 3}").unwrap())).to(
+      be_some().value(MatchingRule::MinMaxType(1, 3)));
     expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"min\"}").unwrap())).to(be_none());
     expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"max\"}").unwrap())).to(be_none());
 
@@ -860,6 +985,74 @@ mod tests {
 
     expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"null\"}").unwrap())).to(
       be_some().value(MatchingRule::Null));
+
+    expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"contentType\", \"value\": \"image/png\"}").unwrap())).to(
+      be_some().value(MatchingRule::ContentType(s!("image/png"))));
+    expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"contentType\"}").unwrap())).to(be_none());
+    expect!(MatchingRule::ContentType(s!("image/png")).to_json()).to(
+      be_equal_to(json!({ "match": "contentType", "value": "image/png" })));
+
+    expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"unordered\"}").unwrap())).to(
+      be_some().value(MatchingRule::Unordered));
+    expect!(MatchingRule::Unordered.to_json()).to(be_equal_to(json!({ "match": "unordered" })));
+
+    expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"statusCode\", \"status\": \"clientError\"}").unwrap())).to(
+      be_some().value(MatchingRule::StatusCode(HttpStatus::ClientError)));
+    expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"statusCode\", \"status\": [200, 201]}").unwrap())).to(
+      be_some().value(MatchingRule::StatusCode(HttpStatus::StatusCodes(vec![200, 201]))));
+    expect!(MatchingRule::from_json(&Value::from_str("{\"match\": \"statusCode\"}").unwrap())).to(be_none());
+    expect!(MatchingRule::StatusCode(HttpStatus::Success).to_json()).to(
+      be_equal_to(json!({ "match": "statusCode", "status": "success" })));
+  }
+
+  #[test]
+  fn status_rules_are_written_without_a_sub_category_and_can_be_read_back() {
+    let matching_rules = matchingrules!{
+      "status" => { "" => [ MatchingRule::StatusCode(HttpStatus::Success) ] }
+    };
+    let json = matchers_to_json(&matching_rules, &PactSpecification::V3);
+    expect!(json.clone()).to(be_equal_to(json!({
+      "status": { "combine": "AND", "matchers": [ { "match": "statusCode", "status": "success" } ] }
+    })));
+    expect!(matchers_from_json(&json!({ "matchingRules": json }), &None)).to(be_equal_to(matching_rules.clone()));
+    expect!(matchers_to_json(&matching_rules, &PactSpecification::V2)).to(be_equal_to(json!({})));
+  }
+
+  #[test]
+  fn unordered_rules_are_only_read_and_written_for_query_parameters() {
+    let matching_rules = matchingrules!{
+      "query" => { "ids" => [ MatchingRule::Unordered ] },
+      "header" => { "Accept" => [ MatchingRule::Unordered ] },
+      "body" => { "$.ids" => [ MatchingRule::Unordered, MatchingRule::Type ] }
+    };
+    let json = matchers_to_json(&matching_rules, &PactSpecification::V3);
+    expect!(json.clone()).to(be_equal_to(json!({
+      "query": { "ids": { "combine": "AND", "matchers": [ { "match": "unordered" } ] } },
+      "header": {},
+      "body": { "$.ids": { "combine": "AND", "matchers": [ { "match": "type" } ] } }
+    })));
+
+    let json = json!({
+      "query": { "ids": { "matchers": [ { "match": "unordered" } ] } },
+      "body": { "$.ids": { "matchers": [ { "match": "unordered" }, { "match": "type" } ] } }
+    });
+    expect!(matchers_from_json(&json!({ "matchingRules": json }), &None)).to(be_equal_to(matchingrules!{
+      "query" => { "ids" => [ MatchingRule::Unordered ] },
+      "body" => { "$.ids" => [ MatchingRule::Type ] }
+    }));
+  }
+
+  #[test]
+  fn http_status_classes_match_status_codes() {
+    expect!(HttpStatus::Information.matches(101)).to(be_true());
+    expect!(HttpStatus::Success.matches(204)).to(be_true());
+    expect!(HttpStatus::Success.matches(302)).to(be_false());
+    expect!(HttpStatus::Redirect.matches(302)).to(be_true());
+    expect!(HttpStatus::ClientError.matches(404)).to(be_true());
+    expect!(HttpStatus::ServerError.matches(404)).to(be_false());
+    expect!(HttpStatus::NonError.matches(302)).to(be_true());
+    expect!(HttpStatus::Error.matches(503)).to(be_true());
+    expect!(HttpStatus::StatusCodes(vec![200, 201]).matches(202)).to(be_false());
   }
 
   #[test]
@@ -982,6 +1175,30 @@ mod tests {
     expect!(matches_token(&s!("1"), &PathToken::Star)).to(be_equal_to(1));
   }
 
+  #[test]
+  fn max_by_path_prefers_the_longer_path_when_the_weights_are_equal() {
+    let matchers = matchingrules!{
+      "body" => {
+        "$.foo" => [ MatchingRule::MinType(2) ],
+        "$.foo[*]" => [ MatchingRule::Type ]
+      }
+    };
+    expect!(matchers.resolve_body_matchers_by_path(&vec![s!("$"), s!("foo"), s!("0")])).to(
+      be_some().value(RuleList::new(MatchingRule::Type)));
+  }
+
+  #[test]
+  fn max_by_path_prefers_the_heavier_path_over_a_longer_one() {
+    let matchers = matchingrules!{
+      "body" => {
+        "$.foo" => [ MatchingRule::Regex(s!("\\d+")) ],
+        "$.*.*" => [ MatchingRule::Type ]
+      }
+    };
+    expect!(matchers.resolve_body_matchers_by_path(&vec![s!("$"), s!("foo"), s!("bar")])).to(
+      be_some().value(RuleList::new(MatchingRule::Regex(s!("\\d+")))));
+  }
+
   #[test]
   fn matches_path_matches_root_path_element() {
     expect!(calc_path_weight(s!("$"), &vec![s!("$")]) > 0).to(be_true());
@@ -6,19 +6,21 @@ use serde_json;
 use serde_json::Value;
 use hex::FromHex;
 use super::strip_whitespace;
+use headers::split_unquoted;
 use regex::Regex;
 use semver::Version;
 use itertools::Itertools;
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, Error, ErrorKind, SeekFrom};
 use std::io::prelude::*;
 use std::fs;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::path::Path;
 use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use hyper::client::Client;
 use std::str;
 use base64::{encode, decode};
+use self::json_utils::json_to_string;
 
 /// Version of the library
 pub const VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
@@ -67,6 +69,15 @@ impl PactSpecification {
     }
 }
 
+/// How `Pact::write_pact_with_mode` treats a pact file which already exists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PactWriteMode {
+    /// Merge the interactions into the existing pact file, failing if any of them conflict
+    Merge,
+    /// Replace the existing pact file
+    Overwrite
+}
+
 /// Struct that defines the consumer of the pact.
 #[derive(Debug, Clone)]
 pub struct Consumer {
@@ -189,6 +200,7 @@ lazy_static! {
 
     static ref JSON_CONTENT_TYPE: Regex = Regex::new("application/.*json.*").unwrap();
     static ref XML_CONTENT_TYPE: Regex = Regex::new("application/.*xml").unwrap();
+    static ref FORM_URLENCODED_CONTENT_TYPE: Regex = Regex::new("application/x-www-form-urlencoded").unwrap();
 }
 
 /// Enumeration of general content types
@@ -198,6 +210,8 @@ pub enum DetectedContentType {
     Json,
     /// XML content types
     Xml,
+    /// Form post (application/x-www-form-urlencoded) content types
+    FormUrlEncoded,
     /// All other content types
     Text
 }
@@ -225,12 +239,15 @@ pub mod json_utils;
 pub mod xml_utils;
 #[macro_use] pub mod matchingrules;
 #[macro_use] pub mod generators;
+pub mod header_map;
+
+pub use self::header_map::HeaderMap;
 
 /// Trait to specify an HTTP part of a message. It encapsulates the shared parts of a request and
 /// response.
 pub trait HttpPart {
     /// Returns the headers of the HTTP part.
-    fn headers(&self) -> &Option<HashMap<String, String>>;
+    fn headers(&self) -> &Option<HeaderMap>;
 
     /// Returns the body of the HTTP part.
     fn body(&self) -> &OptionalBody;
@@ -286,6 +303,8 @@ pub trait HttpPart {
             DetectedContentType::Json
         } else if XML_CONTENT_TYPE.is_match(&content_type[..]) {
             DetectedContentType::Xml
+        } else if FORM_URLENCODED_CONTENT_TYPE.is_match(&content_type[..]) {
+            DetectedContentType::FormUrlEncoded
         } else {
             DetectedContentType::Text
         }
@@ -296,12 +315,16 @@ pub trait HttpPart {
         self.lookup_header_value(header_name).is_some()
     }
 
-    /// Checks if the HTTP Part has the given header
+    /// Returns the value of the given header, ignoring the case of the header name. If the header has
+    /// multiple values, they are returned joined with commas.
     fn lookup_header_value(&self, header_name: &String) -> Option<String> {
+        self.lookup_header_values(header_name).map(|values| values.join(", "))
+    }
+
+    /// Returns the values of the given header, ignoring the case of the header name.
+    fn lookup_header_values(&self, header_name: &String) -> Option<Vec<String>> {
         match *self.headers() {
-            Some(ref h) => h.iter()
-                .find(|kv| kv.0.to_lowercase() == header_name.to_lowercase())
-                .map(|kv| kv.1.clone()),
+            Some(ref h) => h.get(header_name).cloned(),
             None => None
         }
     }
@@ -316,8 +339,8 @@ pub struct Request {
     pub path: String,
     /// Request query string
     pub query: Option<HashMap<String, Vec<String>>>,
-    /// Request headers
-    pub headers: Option<HashMap<String, String>>,
+    /// Request headers. Header names are case-insensitive, and a header can have multiple values
+    pub headers: Option<HeaderMap>,
     /// Request body
     pub body: OptionalBody,
     /// Request matching rules
@@ -327,7 +350,7 @@ pub struct Request {
 }
 
 impl HttpPart for Request {
-    fn headers(&self) -> &Option<HashMap<String, String>> {
+    fn headers(&self) -> &Option<HeaderMap> {
         &self.headers
     }
 
@@ -354,36 +377,60 @@ impl Hash for Request {
                 v.hash(state);
             }
         }
-        if self.headers.is_some() {
-            for (k, v) in self.headers.clone().unwrap() {
-                k.hash(state);
-                v.hash(state);
-            }
-        }
+        self.headers.hash(state);
         self.body.hash(state);
         self.matching_rules.hash(state);
         self.generators.hash(state);
     }
 }
 
-fn headers_from_json(request: &Value) -> Option<HashMap<String, String>> {
+/// Headers whose values are comma separated lists, so are split into multiple values. The values of
+/// other headers can contain commas (like the dates in `Expires` or the links in `Link`), so are
+/// kept as they are.
+static LIST_HEADERS: [&str; 22] = ["accept", "accept-charset", "accept-encoding", "accept-language",
+    "accept-ranges", "access-control-allow-headers", "access-control-allow-methods",
+    "access-control-expose-headers", "access-control-request-headers", "allow", "cache-control",
+    "connection", "content-encoding", "content-language", "if-match", "if-none-match", "pragma", "te",
+    "trailer", "transfer-encoding", "vary", "via"];
+
+/// Parses the value of a header into its values. The values of headers that are comma separated lists
+/// (like `Accept` or `Vary`) are split on the commas, while other headers have a single value.
+pub fn parse_header(name: &str, value: &str) -> Vec<String> {
+    if LIST_HEADERS.contains(&name.to_lowercase().as_str()) {
+        split_unquoted(value, ',')
+    } else {
+        vec![s!(value.trim())]
+    }
+}
+
+fn headers_from_json(request: &Value) -> Option<HeaderMap> {
     match request.get("headers") {
         Some(v) => match *v {
-            Value::Object(ref m) => Some(m.iter().map(|(key, val)| {
-                match val {
-                    &Value::String(ref s) => (key.clone(), s.clone()),
-                    _ => (key.clone(), val.to_string())
-                }
-            }).collect()),
+            Value::Object(ref m) => Some(m.iter().fold(HeaderMap::new(), |mut headers, (key, val)| {
+                let values = match *val {
+                    Value::String(ref s) => parse_header(key, s),
+                    Value::Array(ref values) => values.iter().map(json_to_string).collect(),
+                    _ => vec![val.to_string()]
+                };
+                headers.add(key.clone(), values);
+                headers
+            })),
             _ => None
         },
         None => None
     }
 }
 
-fn headers_to_json(headers: &HashMap<String, String>) -> Value {
+/// Headers are stored in V2 and V3 pact files with their values joined with commas. Headers with
+/// multiple values that are not comma separated lists (like `Set-Cookie`) are stored as an array of
+/// the values, as they would be read back as a single value if joined.
+fn headers_to_json(headers: &HeaderMap) -> Value {
     json!(headers.iter().fold(BTreeMap::new(), |mut map, kv| {
-        map.insert(kv.0.clone(), Value::String(kv.1.clone()));
+        if kv.1.len() > 1 && !LIST_HEADERS.contains(&kv.0.to_lowercase().as_str()) {
+            map.insert(kv.0.clone(), json!(kv.1));
+        } else {
+            map.insert(kv.0.clone(), Value::String(kv.1.join(", ")));
+        }
         map
     }))
 }
@@ -395,11 +442,64 @@ enum JsonParsable {
     KeyValue(HashMap<String, Value>)
 }
 
-fn body_from_json(request: &Value, fieldname: &str, headers: &Option<HashMap<String, String>>) -> OptionalBody {
+/// Decodes a body stored base64 encoded by `body_to_json`. The content type is taken from the headers,
+/// or else from the stored body. JSON bodies are never stored encoded, so a JSON body which just looks
+/// like an encoded one is left as it is, and text bodies are only stored encoded when they are not UTF-8.
+fn decode_encoded_body(map: &serde_json::Map<String, Value>, content_type: &Option<String>) -> Option<Vec<u8>> {
+    let is_encoded = map.len() <= 3 && map.contains_key("content") &&
+        map.get("encoded").map(|encoded| json_to_string(encoded).to_lowercase() == "base64").unwrap_or(false);
+    let content_type = content_type.clone()
+        .or_else(|| map.get("contentType").map(|content_type| json_to_string(content_type).to_lowercase()))
+        .unwrap_or_default();
+    if !is_encoded || content_type.is_empty() || JSON_CONTENT_TYPE.is_match(&content_type) {
+        return None;
+    }
+    let content = map.get("content").map(json_to_string).unwrap_or_default();
+    match decode(&content) {
+        Ok(bytes) => if is_text_content_type(&content_type) && str::from_utf8(&bytes).is_ok() {
+            None
+        } else {
+            Some(bytes)
+        },
+        Err(err) => {
+            warn!("Failed to decode base64 encoded body, using it as is: {}", err);
+            Some(content.into())
+        }
+    }
+}
+
+/// Returns true if bodies with the content type can be stored in a pact file as plain text
+fn is_text_content_type(content_type: &str) -> bool {
+    content_type.starts_with("text/") || JSON_CONTENT_TYPE.is_match(content_type) ||
+        XML_CONTENT_TYPE.is_match(content_type) || FORM_URLENCODED_CONTENT_TYPE.is_match(content_type)
+}
+
+/// Converts a body to the form stored in a pact file. JSON bodies are stored as JSON, and text bodies as strings.
+/// All other bodies are base64 encoded and stored with their content type, like
+/// `{ "content": "...", "contentType": "application/pdf", "encoded": "base64" }`.
+fn body_to_json(body: &Vec<u8>, content_type: &str) -> Value {
+    if content_type == "application/json" {
+        match serde_json::from_slice(body) {
+            Ok(json_body) => return json_body,
+            Err(err) => warn!("Failed to parse json body: {}", err)
+        }
+    } else if is_text_content_type(content_type) {
+        if let Ok(s) = str::from_utf8(body) {
+            return Value::String(s.to_string());
+        }
+    }
+    json!({
+        s!("content"): Value::String(encode(body)),
+        s!("contentType"): Value::String(content_type.to_string()),
+        s!("encoded"): Value::String(s!("base64"))
+    })
+}
+
+fn body_from_json(request: &Value, fieldname: &str, headers: &Option<HeaderMap>) -> OptionalBody {
     let content_type = match headers {
-        &Some(ref h) => match h.iter().find(|kv| kv.0.to_lowercase() == s!("content-type")) {
-            Some(kv) => {
-                match strip_whitespace::<Vec<&str>>(&kv.1, ";").first() {
+        &Some(ref h) => match h.get("content-type").and_then(|values| values.first()) {
+            Some(value) => {
+                match strip_whitespace::<Vec<&str>>(value, ";").first() {
                     Some(v) => Some(v.to_lowercase()),
                     None => None
                 }
@@ -411,6 +511,10 @@ fn body_from_json(request: &Value, fieldname: &str, headers: &Option<HashMap<Str
 
     match request.get(fieldname) {
         Some(v) => match *v {
+            Value::Object(ref map) => match decode_encoded_body(map, &content_type) {
+                Some(bytes) => OptionalBody::Present(bytes),
+                None => OptionalBody::Present(v.to_string().into())
+            },
             Value::String(ref s) => {
                 if s.is_empty() {
                   OptionalBody::Empty
@@ -547,20 +651,7 @@ impl Request {
             }
             match self.body {
                 OptionalBody::Present(ref body) => {
-                    if self.content_type() == "application/json" {
-                        match serde_json::from_slice(body) {
-                            Ok(json_body) => { map.insert(s!("body"), json_body); },
-                            Err(err) => {
-                                warn!("Failed to parse json body: {}", err);
-                                map.insert(s!("body"), Value::String(encode(body)));
-                            }
-                        }
-                    } else {
-                        match str::from_utf8(body) {
-                        Ok(s) =>map.insert(s!("body"), Value::String(s.to_string())),
-                        Err(_) => map.insert(s!("body"), Value::String(encode(body)))
-                      };
-                    }
+                    map.insert(s!("body"), body_to_json(body, &self.content_type()));
                 },
                 OptionalBody::Empty => { map.insert(s!("body"), Value::String(s!(""))); },
                 OptionalBody::Missing => (),
@@ -621,8 +712,8 @@ impl Request {
 pub struct Response {
     /// Response status
     pub status: u16,
-    /// Response headers
-    pub headers: Option<HashMap<String, String>>,
+    /// Response headers. Header names are case-insensitive, and a header can have multiple values
+    pub headers: Option<HeaderMap>,
     /// Response body
     pub body: OptionalBody,
     /// Response matching rules
@@ -673,20 +764,7 @@ impl Response {
             }
             match self.body {
                 OptionalBody::Present(ref body) => {
-                    if self.content_type() == "application/json" {
-                        match serde_json::from_slice(body) {
-                            Ok(json_body) => { map.insert(s!("body"), json_body); },
-                            Err(err) => {
-                                warn!("Failed to parse json body: {}", err);
-                                map.insert(s!("body"), Value::String(encode(body)));
-                            }
-                        }
-                    } else {
-                      match str::from_utf8(body) {
-                        Ok(s) => map.insert(s!("body"), Value::String(s.to_string())),
-                        Err(_) => map.insert(s!("body"), Value::String(encode(body)))
-                      };
-                    }
+                    map.insert(s!("body"), body_to_json(body, &self.content_type()));
                 },
                 OptionalBody::Empty => { map.insert(s!("body"), Value::String(s!(""))); },
                 OptionalBody::Missing => (),
@@ -724,7 +802,7 @@ impl Response {
 }
 
 impl HttpPart for Response {
-    fn headers(&self) -> &Option<HashMap<String, String>> {
+    fn headers(&self) -> &Option<HeaderMap> {
         &self.headers
     }
 
@@ -744,12 +822,7 @@ impl HttpPart for Response {
 impl Hash for Response {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.status.hash(state);
-        if self.headers.is_some() {
-            for (k, v) in self.headers.clone().unwrap() {
-                k.hash(state);
-                v.hash(state);
-            }
-        }
+        self.headers.hash(state);
         self.body.hash(state);
         self.matching_rules.hash(state);
         self.generators.hash(state);
@@ -1102,22 +1175,39 @@ impl Pact {
     /// automatically created. If an existing pact is found at the path, this pact will be
     /// merged into the pact file.
     pub fn write_pact(&self, path: &Path, pact_spec: PactSpecification) -> io::Result<()> {
+        self.write_pact_with_mode(path, pact_spec, PactWriteMode::Merge)
+    }
+
+    /// Writes this pact out to the provided file path, either merging it into or replacing
+    /// any existing pact file. All directories in the path will automatically created. The
+    /// file is locked while it is read and written, so that separate processes (like test
+    /// binaries running in parallel) can safely write to the same pact file.
+    ///
+    /// **NOTE:** The file is only locked on Unix platforms. On other platforms (like Windows)
+    /// separate processes writing to the same pact file at the same time can overwrite each
+    /// others interactions.
+    pub fn write_pact_with_mode(&self, path: &Path, pact_spec: PactSpecification,
+                                mode: PactWriteMode) -> io::Result<()> {
         fs::create_dir_all(path.parent().unwrap())?;
-        if path.exists() {
-            let existing_pact = Pact::read_pact(path)?;
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        lock_file(&file)?;
+        let mut existing = String::new();
+        file.read_to_string(&mut existing)?;
+        let pact_json = if mode == PactWriteMode::Merge && !existing.trim().is_empty() {
+            let existing_pact = match serde_json::from_str(&existing) {
+                Ok(ref json) => Pact::from_json(&format!("{:?}", path), json),
+                Err(err) => return Err(Error::new(ErrorKind::Other, format!("Failed to parse Pact JSON - {}", err)))
+            };
             match existing_pact.merge(self) {
-                Ok(ref merged_pact) => {
-                    let mut file = File::create(path)?;
-                    file.write_all(format!("{}", serde_json::to_string_pretty(&merged_pact.to_json(pact_spec)).unwrap()).as_bytes())?;
-                    Ok(())
-                },
-                Err(ref message) => Err(Error::new(ErrorKind::Other, message.clone()))
+                Ok(ref merged_pact) => merged_pact.to_json(pact_spec),
+                Err(ref message) => return Err(Error::new(ErrorKind::Other, message.clone()))
             }
         } else {
-            let mut file = File::create(path)?;
-            file.write_all(format!("{}", serde_json::to_string_pretty(&self.to_json(pact_spec)).unwrap()).as_bytes())?;
-            Ok(())
-        }
+            self.to_json(pact_spec)
+        };
+        file.seek(SeekFrom::Start(0))?;
+        file.set_len(0)?;
+        file.write_all(serde_json::to_string_pretty(&pact_json).unwrap().as_bytes())
     }
 
     /// Returns a default Pact struct
@@ -1135,6 +1225,23 @@ impl Pact {
     }
 }
 
+/// Takes an exclusive lock on the file, which is released when the file is closed.
+#[cfg(unix)]
+fn lock_file(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+        Ok(())
+    } else {
+        Err(Error::last_os_error())
+    }
+}
+
+/// File locking is only supported on Unix platforms, so this does nothing.
+#[cfg(not(unix))]
+fn lock_file(_file: &File) -> io::Result<()> {
+    Ok(())
+}
+
 fn decode_query(query: &str) -> String {
     let mut chars = query.chars();
     let mut ch = chars.next();
@@ -1230,5 +1337,12 @@ pub fn parse_query_string(query: &String) -> Option<HashMap<String, Vec<String>>
     }
 }
 
+/// Returns the name of a query parameter without any `[]` suffix. Array parameters can be sent
+/// with a bracketed name (i.e. `ids[]=1&ids[]=2`), and are treated the same as parameters without
+/// the brackets when matching.
+pub fn query_parameter_name(name: &str) -> &str {
+    name.strip_suffix("[]").unwrap_or(name)
+}
+
 #[cfg(test)]
 mod tests;
@@ -6,12 +6,13 @@ use std::collections::hash_map::DefaultHasher;
 use std::fs::{self, File};
 use std::io;
 use std::env;
+use std::thread;
 use expectest::prelude::*;
 use rand;
 use std::hash::{Hash, Hasher};
 use super::provider_states::*;
 use super::matchingrules::*;
-use super::generators::{Generators, Generator, generators_from_json};
+use super::generators::{Generators, Generator, generators_from_json, generators_to_json};
 use std::str::FromStr;
 #[allow(unused_imports)] use env_logger;
 
@@ -145,16 +146,16 @@ fn request_mimetype_is_based_on_the_content_type_header() {
         body: OptionalBody::Missing, .. Request::default_request() };
     expect!(request.content_type()).to(be_equal_to("text/plain"));
     expect!(Request {
-        headers: Some(hashmap!{ s!("Content-Type") => s!("text/html") }), .. request.clone() }.content_type())
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/html")] }.into()), .. request.clone() }.content_type())
         .to(be_equal_to("text/html"));
     expect!(Request {
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json; charset=UTF-8") }), .. request.clone() }.content_type())
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json; charset=UTF-8")] }.into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }), .. request.clone() }.content_type())
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
-        headers: Some(hashmap!{ s!("CONTENT-TYPE") => s!("application/json ; charset=UTF-8") }), .. request.clone() }.content_type())
+        headers: Some(hashmap!{ s!("CONTENT-TYPE") => vec![s!("application/json ; charset=UTF-8")] }.into()), .. request.clone() }.content_type())
         .to(be_equal_to("application/json"));
     expect!(Request {
         body: OptionalBody::Present("{\"json\": true}".into()), .. request.clone() }.content_type())
@@ -191,34 +192,96 @@ fn content_type_enum_test() {
         body: OptionalBody::Missing, .. Request::default_request() };
     expect!(request.content_type_enum()).to(be_equal_to(DetectedContentType::Text));
     expect!(Request {
-        headers: Some(hashmap!{ s!("Content-Type") => s!("text/html") }), .. request.clone() }.content_type_enum())
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/html")] }.into()), .. request.clone() }.content_type_enum())
         .to(be_equal_to(DetectedContentType::Text));
     expect!(Request {
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }), .. request.clone() }.content_type_enum())
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()), .. request.clone() }.content_type_enum())
         .to(be_equal_to(DetectedContentType::Json));
     expect!(Request {
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/hal+json") }), .. request.clone() }.content_type_enum())
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/hal+json")] }.into()), .. request.clone() }.content_type_enum())
         .to(be_equal_to(DetectedContentType::Json));
     expect!(Request {
-        headers: Some(hashmap!{ s!("CONTENT-TYPE") => s!("application/json-rpc") }), .. request.clone() }.content_type_enum())
+        headers: Some(hashmap!{ s!("CONTENT-TYPE") => vec![s!("application/json-rpc")] }.into()), .. request.clone() }.content_type_enum())
         .to(be_equal_to(DetectedContentType::Json));
     expect!(Request {
-        headers: Some(hashmap!{ s!("CONTENT-TYPE") => s!("application/xml") }), .. request.clone() }.content_type_enum())
+        headers: Some(hashmap!{ s!("CONTENT-TYPE") => vec![s!("application/xml")] }.into()), .. request.clone() }.content_type_enum())
         .to(be_equal_to(DetectedContentType::Xml));
     expect!(Request {
-        headers: Some(hashmap!{ s!("CONTENT-TYPE") => s!("application/stuff+xml") }), .. request.clone() }.content_type_enum())
+        headers: Some(hashmap!{ s!("CONTENT-TYPE") => vec![s!("application/stuff+xml")] }.into()), .. request.clone() }.content_type_enum())
         .to(be_equal_to(DetectedContentType::Xml));
 }
 
 #[test]
 fn http_part_has_header_test() {
     let request = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json; charset=UTF-8") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json; charset=UTF-8")] }.into()),
         body: OptionalBody::Missing, .. Request::default_request() };
     expect!(request.has_header(&s!("Content-Type"))).to(be_true());
     expect!(request.lookup_header_value(&s!("Content-Type"))).to(be_some().value("application/json; charset=UTF-8"));
 }
 
+#[test]
+fn lookup_header_values_ignores_the_case_of_the_header_name() {
+    let request = Request { headers: Some(hashmap!{ s!("Vary") => vec![s!("Accept"), s!("Origin")] }.into()),
+        .. Request::default_request() };
+    expect!(request.lookup_header_values(&s!("vary"))).to(be_some().value(vec![s!("Accept"), s!("Origin")]));
+    expect!(request.lookup_header_value(&s!("VARY"))).to(be_some().value("Accept, Origin"));
+    expect!(request.lookup_header_values(&s!("Accept"))).to(be_none());
+}
+
+#[test]
+fn headers_from_json_splits_comma_separated_values() {
+    let json = json!({
+        "headers": {
+            "Vary": "Accept, Origin",
+            "Date": "Tue, 15 Nov 1994 08:12:31 GMT",
+            "Set-Cookie": "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+            "X-List": ["a", "b"]
+        }
+    });
+    expect!(headers_from_json(&json)).to(be_some().value(hashmap!{
+        s!("Vary") => vec![s!("Accept"), s!("Origin")],
+        s!("Date") => vec![s!("Tue, 15 Nov 1994 08:12:31 GMT")],
+        s!("Set-Cookie") => vec![s!("id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT")],
+        s!("X-List") => vec![s!("a"), s!("b")]
+    }.into()));
+}
+
+#[test]
+fn headers_from_json_merges_headers_that_differ_by_case() {
+    let json = json!({ "headers": { "Accept": "text/html", "accept": "application/json" } });
+    let headers = headers_from_json(&json).unwrap();
+    expect!(headers.len()).to(be_equal_to(1));
+    let mut values = headers.iter().next().unwrap().1.clone();
+    values.sort();
+    expect!(values).to(be_equal_to(vec![s!("application/json"), s!("text/html")]));
+}
+
+#[test]
+fn multi_valued_headers_survive_a_round_trip_through_json() {
+    let response = Response { headers: Some(hashmap!{
+        s!("Vary") => vec![s!("Accept"), s!("Origin")],
+        s!("Content-Type") => vec![s!("application/json")]
+    }.into()), .. Response::default_response() };
+    let json = response.to_json(&PactSpecification::V2);
+    expect!(json.get("headers").unwrap().get("Vary").unwrap().clone()).to(be_equal_to(json!("Accept, Origin")));
+    expect!(Response::from_json(&json, &PactSpecification::V2).headers).to(be_equal_to(response.headers));
+}
+
+#[test]
+fn multi_valued_headers_that_are_not_lists_survive_a_round_trip_through_json() {
+    let response = Response { headers: Some(hashmap!{
+        s!("Set-Cookie") => vec![s!("a=1"), s!("b=2; Expires=Wed, 21 Oct 2015 07:28:00 GMT")],
+        s!("Date") => vec![s!("Tue, 15 Nov 1994 08:12:31 GMT")]
+    }.into()), .. Response::default_response() };
+    let json = response.to_json(&PactSpecification::V3);
+    expect!(json["headers"]["Set-Cookie"].clone()).to(be_equal_to(
+        json!(["a=1", "b=2; Expires=Wed, 21 Oct 2015 07:28:00 GMT"])));
+    expect!(json["headers"]["Date"].clone()).to(be_equal_to(json!("Tue, 15 Nov 1994 08:12:31 GMT")));
+    let json = serde_json::from_str(&json.to_string()).unwrap();
+    expect!(Response::from_json(&json, &PactSpecification::V3).headers).to(be_equal_to(response.headers));
+}
+
 #[test]
 fn loading_interaction_from_json() {
     let interaction_json = r#"{
@@ -386,7 +449,7 @@ fn load_basic_pact() {
     }));
     expect!(interaction.response).to(be_equal_to(Response {
         status: 200,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("text/html") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/html")] }.into()),
         body: OptionalBody::Present("\"That is some good Mallory.\"".into()),
       .. Response::default_response()
     }));
@@ -453,13 +516,13 @@ fn load_pact() {
         method: s!("GET"),
         path: s!("/"),
         query: Some(hashmap!{ s!("q") => vec![s!("p"), s!("p2")], s!("r") => vec![s!("s")] }),
-        headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheadervalue") }),
+        headers: Some(hashmap!{ s!("testreqheader") => vec![s!("testreqheadervalue")] }.into()),
         body: "{\"test\":true}".into(),
       .. Request::default_request()
     }));
     expect!(interaction.response).to(be_equal_to(Response {
         status: 200,
-        headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheaderval") }),
+        headers: Some(hashmap!{ s!("testreqheader") => vec![s!("testreqheaderval")] }.into()),
         body: "{\"responsetest\":true}".into(),
         .. Response::default_response()
     }));
@@ -527,13 +590,13 @@ fn load_v3_pact() {
         method: s!("GET"),
         path: s!("/"),
         query: Some(hashmap!{ s!("q") => vec![s!("p"), s!("p2")], s!("r") => vec![s!("s")] }),
-        headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheadervalue") }),
+        headers: Some(hashmap!{ s!("testreqheader") => vec![s!("testreqheadervalue")] }.into()),
         body: OptionalBody::Present("{\"test\":true}".into()),
       .. Request::default_request()
     }));
     expect!(interaction.response).to(be_equal_to(Response {
         status: 200,
-        headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheaderval") }),
+        headers: Some(hashmap!{ s!("testreqheader") => vec![s!("testreqheaderval")] }.into()),
         body: OptionalBody::Present("{\"responsetest\":true}".into()),
         .. Response::default_response()
     }));
@@ -591,7 +654,7 @@ fn load_pact_encoded_query_string() {
         path: s!("/"),
         query: Some(hashmap!{ s!("datetime") => vec![s!("2011-12-03T10:15:30+01:00")],
             s!("description") => vec![s!("hello world!")] }),
-        headers: Some(hashmap!{ s!("testreqheader") => s!("testreqheadervalue") }),
+        headers: Some(hashmap!{ s!("testreqheader") => vec![s!("testreqheadervalue")] }.into()),
         body: OptionalBody::Present("{\"test\":true}".into()),
       .. Request::default_request()
     }));
@@ -704,9 +767,9 @@ fn request_to_json_with_a_query_v3_must_not_encode_the_query_with_utf8_chars() {
 #[test]
 fn request_to_json_with_headers() {
     let request = Request { headers: Some(hashmap!{
-        s!("HEADERA") => s!("VALUEA"),
-        s!("HEADERB") => s!("VALUEB1, VALUEB2")
-    }), .. Request::default_request() };
+        s!("HEADERA") => vec![s!("VALUEA")],
+        s!("HEADERB") => vec![s!("VALUEB1, VALUEB2")]
+    }.into()), .. Request::default_request() };
     expect!(request.to_json(&PactSpecification::V3).to_string()).to(
         be_equal_to(r#"{"headers":{"HEADERA":"VALUEA","HEADERB":"VALUEB1, VALUEB2"},"method":"GET","path":"/"}"#)
     );
@@ -715,8 +778,8 @@ fn request_to_json_with_headers() {
 #[test]
 fn request_to_json_with_json_body() {
     let request = Request { headers: Some(hashmap!{
-        s!("Content-Type") => s!("application/json")
-    }), body: OptionalBody::Present(r#"{"key": "value"}"#.into()), .. Request::default_request() };
+        s!("Content-Type") => vec![s!("application/json")]
+    }.into()), body: OptionalBody::Present(r#"{"key": "value"}"#.into()), .. Request::default_request() };
     expect!(request.to_json(&PactSpecification::V3).to_string()).to(
         be_equal_to(r#"{"body":{"key":"value"},"headers":{"Content-Type":"application/json"},"method":"GET","path":"/"}"#)
     );
@@ -725,7 +788,7 @@ fn request_to_json_with_json_body() {
 
 #[test]
 fn request_to_json_with_non_json_body() {
-    let request = Request { headers: Some(hashmap!{ s!("Content-Type") => s!("text/plain") }),
+    let request = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into()),
         body: OptionalBody::Present("This is some text".into()), .. Request::default_request() };
     expect!(request.to_json(&PactSpecification::V3).to_string()).to(
         be_equal_to(r#"{"body":"This is some text","headers":{"Content-Type":"text/plain"},"method":"GET","path":"/"}"#)
@@ -757,9 +820,9 @@ fn response_to_json_with_defaults() {
 #[test]
 fn response_to_json_with_headers() {
     let response = Response { headers: Some(hashmap!{
-        s!("HEADERA") => s!("VALUEA"),
-        s!("HEADERB") => s!("VALUEB1, VALUEB2")
-    }), .. Response::default_response() };
+        s!("HEADERA") => vec![s!("VALUEA")],
+        s!("HEADERB") => vec![s!("VALUEB1, VALUEB2")]
+    }.into()), .. Response::default_response() };
     expect!(response.to_json(&PactSpecification::V3).to_string()).to(
         be_equal_to(r#"{"headers":{"HEADERA":"VALUEA","HEADERB":"VALUEB1, VALUEB2"},"status":200}"#)
     );
@@ -768,8 +831,8 @@ fn response_to_json_with_headers() {
 #[test]
 fn response_to_json_with_json_body() {
     let response = Response { headers: Some(hashmap!{
-        s!("Content-Type") => s!("application/json")
-    }), body: OptionalBody::Present(r#"{"key": "value"}"#.into()), .. Response::default_response() };
+        s!("Content-Type") => vec![s!("application/json")]
+    }.into()), body: OptionalBody::Present(r#"{"key": "value"}"#.into()), .. Response::default_response() };
     expect!(response.to_json(&PactSpecification::V3).to_string()).to(
         be_equal_to(r#"{"body":{"key":"value"},"headers":{"Content-Type":"application/json"},"status":200}"#)
     );
@@ -777,7 +840,7 @@ fn response_to_json_with_json_body() {
 
 #[test]
 fn response_to_json_with_non_json_body() {
-    let response = Response { headers: Some(hashmap!{ s!("Content-Type") => s!("text/plain") }),
+    let response = Response { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into()),
         body: OptionalBody::Present("This is some text".into()), .. Response::default_response() };
     expect!(response.to_json(&PactSpecification::V3).to_string()).to(
         be_equal_to(r#"{"body":"This is some text","headers":{"Content-Type":"text/plain"},"status":200}"#)
@@ -800,6 +863,28 @@ fn response_to_json_with_null_body() {
     );
 }
 
+#[test]
+fn response_to_json_with_binary_body() {
+    let response = Response { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/pdf")] }.into()),
+        body: OptionalBody::Present(vec![0x25, 0x50, 0x44, 0x46, 0x2D, 0xE2, 0xE3]), .. Response::default_response() };
+    expect!(response.to_json(&PactSpecification::V3)["body"].clone()).to(be_equal_to(json!({
+        "content": "JVBERi3i4w==",
+        "contentType": "application/pdf",
+        "encoded": "base64"
+    })));
+}
+
+#[test]
+fn request_to_json_with_a_text_body_that_is_not_utf8() {
+    let request = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into()),
+        body: OptionalBody::Present(vec![0xFF, 0xFE, 0x41]), .. Request::default_request() };
+    expect!(request.to_json(&PactSpecification::V3)["body"].clone()).to(be_equal_to(json!({
+        "content": "//5B",
+        "contentType": "text/plain",
+        "encoded": "base64"
+    })));
+}
+
 #[test]
 fn default_file_name_is_based_in_the_consumer_and_provider() {
     let pact = Pact { consumer: Consumer { name: s!("consumer") },
@@ -1028,6 +1113,77 @@ fn write_pact_test_should_not_merge_pacts_with_conflicts() {
 }}"#, super::VERSION.unwrap())));
 }
 
+#[test]
+fn write_pact_test_should_overwrite_pacts_in_overwrite_mode() {
+    let pact = Pact { consumer: Consumer { name: s!("overwrite_consumer") },
+        provider: Provider { name: s!("overwrite_provider") },
+        interactions: vec![
+            Interaction {
+                description: s!("Test Interaction"),
+                provider_states: vec![],
+                request: Request::default_request(),
+                response: Response::default_response()
+            }
+        ],
+        .. Pact::default() };
+    let pact2 = Pact { interactions: vec![
+            Interaction {
+                description: s!("Test Interaction"),
+                provider_states: vec![],
+                request: Request::default_request(),
+                response: Response { status: 400, .. Response::default_response() }
+            }
+        ],
+        .. pact.clone() };
+    let mut dir = env::temp_dir();
+    let x = rand::random::<u16>();
+    dir.push(format!("pact_test_{}", x));
+    dir.push(pact.default_file_name());
+
+    let result = pact.write_pact(dir.as_path(), PactSpecification::V3);
+    let result2 = pact2.write_pact_with_mode(dir.as_path(), PactSpecification::V3, PactWriteMode::Overwrite);
+
+    let written = Pact::read_pact(dir.as_path());
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap_or(());
+
+    expect!(result).to(be_ok());
+    expect!(result2).to(be_ok());
+    expect!(written.unwrap().interactions).to(be_equal_to(pact2.interactions));
+}
+
+#[test]
+fn write_pact_test_should_merge_pacts_written_concurrently() {
+    let mut dir = env::temp_dir();
+    let x = rand::random::<u16>();
+    dir.push(format!("pact_test_{}", x));
+    dir.push("concurrent_consumer-concurrent_provider.json");
+
+    let threads: Vec<_> = (0..8).map(|i| {
+        let path = dir.clone();
+        thread::spawn(move || {
+            let pact = Pact { consumer: Consumer { name: s!("concurrent_consumer") },
+                provider: Provider { name: s!("concurrent_provider") },
+                interactions: vec![
+                    Interaction {
+                        description: format!("Test Interaction {}", i),
+                        provider_states: vec![],
+                        request: Request::default_request(),
+                        response: Response::default_response()
+                    }
+                ],
+                .. Pact::default() };
+            pact.write_pact(path.as_path(), PactSpecification::V3)
+        })
+    }).collect();
+    let results: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+
+    let written = Pact::read_pact(dir.as_path());
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap_or(());
+
+    expect!(results.iter().all(|result| result.is_ok())).to(be_true());
+    expect!(written.unwrap().interactions.len()).to(be_equal_to(8));
+}
+
 #[test]
 fn pact_merge_does_not_merge_different_consumers() {
     let pact = Pact { consumer: Consumer { name: s!("test_consumer") },
@@ -1226,11 +1382,11 @@ fn hash_for_request() {
     let request1 = Request::default_request();
     let request2 = Request { method: s!("POST"), .. Request::default_request() };
     let request3 = Request { headers: Some(hashmap!{
-        s!("H1") => s!("A")
-    }), .. Request::default_request() };
+        s!("H1") => vec![s!("A")]
+    }.into()), .. Request::default_request() };
     let request4 = Request { headers: Some(hashmap!{
-        s!("H1") => s!("B")
-    }), .. Request::default_request() };
+        s!("H1") => vec![s!("B")]
+    }.into()), .. Request::default_request() };
     expect!(hash(&request1)).to(be_equal_to(hash(&request1)));
     expect!(hash(&request3)).to(be_equal_to(hash(&request3)));
     expect!(hash(&request1)).to_not(be_equal_to(hash(&request2)));
@@ -1242,11 +1398,11 @@ fn hash_for_response() {
     let response1 = Response::default_response();
     let response2 = Response { status: 400, .. Response::default_response() };
     let response3 = Response { headers: Some(hashmap!{
-        s!("H1") => s!("A")
-    }), .. Response::default_response() };
+        s!("H1") => vec![s!("A")]
+    }.into()), .. Response::default_response() };
     let response4 = Response { headers: Some(hashmap!{
-        s!("H1") => s!("B")
-    }), .. Response::default_response() };
+        s!("H1") => vec![s!("B")]
+    }.into()), .. Response::default_response() };
     expect!(hash(&response1)).to(be_equal_to(hash(&response1)));
     expect!(hash(&response3)).to(be_equal_to(hash(&response3)));
     expect!(hash(&response1)).to_not(be_equal_to(hash(&response2)));
@@ -1668,6 +1824,59 @@ fn body_from_json_returns_the_body_if_the_content_type_is_json() {
     expect!(body).to(be_equal_to(OptionalBody::Present("{\"test\":true}".into())));
 }
 
+#[test]
+fn body_from_json_decodes_base64_encoded_bodies() {
+    let json = json!({
+        "path": "/",
+        "body": { "content": "JVBERi3i4w==", "contentType": "application/pdf", "encoded": "base64" }
+    });
+    let body = body_from_json(&json, "body", &Some(hashmap!{ s!("Content-Type") => vec![s!("application/pdf")] }.into()));
+    expect!(body).to(be_equal_to(OptionalBody::Present(vec![0x25, 0x50, 0x44, 0x46, 0x2D, 0xE2, 0xE3])));
+}
+
+#[test]
+fn body_from_json_does_not_decode_json_bodies_that_look_like_encoded_bodies() {
+    let json = json!({
+        "path": "/",
+        "body": { "content": "abc", "encoded": "base64", "other": true, "more": false }
+    });
+    let body = body_from_json(&json, "body", &Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()));
+    expect!(body.str_value().contains("\"other\":true")).to(be_true());
+}
+
+#[test]
+fn body_from_json_only_decodes_encoded_bodies_with_a_binary_content_type() {
+    let json = json!({
+        "path": "/",
+        "body": { "content": "YWJj", "contentType": "application/pdf", "encoded": "base64" }
+    });
+    let body = body_from_json(&json, "body", &Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()));
+    expect!(body.str_value().contains("\"content\":\"YWJj\"")).to(be_true());
+    let body = body_from_json(&json, "body", &None);
+    expect!(body).to(be_equal_to(OptionalBody::Present("abc".into())));
+
+    let json = json!({ "path": "/", "body": { "content": "YWJj", "encoded": "base64" } });
+    let body = body_from_json(&json, "body", &None);
+    expect!(body.str_value().contains("\"encoded\":\"base64\"")).to(be_true());
+}
+
+#[test]
+fn text_bodies_that_are_not_utf8_survive_a_round_trip_through_json() {
+    let request = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into()),
+        body: OptionalBody::Present(vec![0xFF, 0xFE, 0x41]), .. Request::default_request() };
+    let json = request.to_json(&PactSpecification::V3);
+    expect!(Request::from_json(&json, &PactSpecification::V3).body).to(be_equal_to(request.body));
+}
+
+#[test]
+fn binary_bodies_survive_a_round_trip_through_json() {
+    let response = Response { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("image/png")] }.into()),
+        body: OptionalBody::Present(vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0xFF]),
+        .. Response::default_response() };
+    let json = response.to_json(&PactSpecification::V3);
+    expect!(Response::from_json(&json, &PactSpecification::V3).body).to(be_equal_to(response.body));
+}
+
 #[test]
 fn write_v3_pact_test() {
     let pact = Pact { consumer: Consumer { name: s!("write_pact_test_consumer") },
@@ -1847,6 +2056,26 @@ fn generators_from_json_loads_generators_correctly() {
     }));
 }
 
+#[test]
+fn generators_to_json_writes_all_the_generators_in_a_category() {
+    let generators = generators!{
+        "BODY" => {
+            "$.id" => Generator::Uuid,
+            "$.count" => Generator::RandomInt(1, 10)
+        },
+        "PATH" => { "" => Generator::RandomString(10) }
+    };
+    let json = generators_to_json(&generators, &PactSpecification::V3);
+    expect!(json.clone()).to(be_equal_to(json!({
+        "body": {
+            "$.id": { "type": "Uuid" },
+            "$.count": { "type": "RandomInt", "min": 1, "max": 10 }
+        },
+        "path": { "type": "RandomString", "size": 10 }
+    })));
+    expect!(generators_from_json(&json!({ "generators": json }))).to(be_equal_to(generators));
+}
+
 #[test]
 fn write_pact_test_with_generators() {
     let pact = Pact { consumer: Consumer { name: s!("write_pact_test_consumer") },
@@ -0,0 +1,420 @@
+//! The `multipart` module provides functions to compare `multipart/form-data` bodies. The boundary
+//! is taken from the `boundary` parameter of the content type header.
+
+use super::Mismatch;
+use super::DiffConfig;
+use std::collections::HashMap;
+use std::str;
+use models::matchingrules::*;
+use matchers::*;
+use path_exp::DocPath;
+
+/// A single part of a multipart body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    /// Name of the part, from the Content-Disposition header
+    pub name: String,
+    /// Headers of the part
+    pub headers: Vec<(String, String)>,
+    /// Body of the part
+    pub body: Vec<u8>
+}
+
+impl Part {
+
+    /// Returns the value of the header, ignoring the case of the header name
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.headers.iter()
+            .find(|(key, _)| key.to_lowercase() == name.to_lowercase())
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Returns the content type of the part without any parameters, defaulting to `text/plain`
+    pub fn content_type(&self) -> String {
+        match self.header("content-type") {
+            Some(ref value) => s!(value.split(';').next().unwrap_or("").trim()),
+            None => s!("text/plain")
+        }
+    }
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+    (from..(haystack.len() - needle.len() + 1)).find(|&i| &haystack[i..i + needle.len()] == needle)
+}
+
+fn part_name(content_disposition: &str) -> Option<String> {
+    content_disposition.split(';')
+        .map(|param| param.trim())
+        .filter_map(|param| {
+            let mut name_value = param.splitn(2, '=');
+            match (name_value.next(), name_value.next()) {
+                (Some(name), Some(value)) if name.trim() == "name" => Some(s!(value.trim().trim_matches('"'))),
+                _ => None
+            }
+        })
+        .next()
+}
+
+fn parse_part(bytes: &[u8]) -> Result<Part, String> {
+    let (header_bytes, body) = match find_bytes(bytes, b"\r\n\r\n", 0) {
+        Some(index) => (&bytes[..index], &bytes[index + 4..]),
+        None => match find_bytes(bytes, b"\n\n", 0) {
+            Some(index) => (&bytes[..index], &bytes[index + 2..]),
+            None => (bytes, &bytes[bytes.len()..])
+        }
+    };
+    let headers = str::from_utf8(header_bytes)
+        .map_err(|err| format!("Part headers are not valid UTF-8 - {}", err))?
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut name_value = line.splitn(2, ':');
+            (s!(name_value.next().unwrap_or("").trim()), s!(name_value.next().unwrap_or("").trim()))
+        })
+        .collect::<Vec<(String, String)>>();
+    let mut part = Part { name: s!(""), headers, body: body.to_vec() };
+    part.name = part.header("content-disposition").and_then(|cd| part_name(&cd))
+        .ok_or_else(|| s!("Part does not have a Content-Disposition header with a name"))?;
+    Ok(part)
+}
+
+/// Returns the boundary parameter of a `multipart` content type header value, like
+/// `multipart/form-data; boundary=XYZ`.
+pub fn boundary(content_type: &str) -> Option<String> {
+    content_type.split(';')
+        .skip(1)
+        .filter_map(|param| {
+            let mut name_value = param.splitn(2, '=');
+            match (name_value.next(), name_value.next()) {
+                (Some(name), Some(value)) if name.trim().to_lowercase() == "boundary" =>
+                    Some(s!(value.trim().trim_matches('"'))),
+                _ => None
+            }
+        })
+        .find(|boundary| !boundary.is_empty())
+}
+
+/// Returns the index after the line ending of a delimiter that ends at `index`, or `None` if the
+/// delimiter is followed by something other than whitespace and a line ending.
+fn end_of_delimiter_line(body: &[u8], index: usize) -> Option<usize> {
+    let padding = body[index..].iter().take_while(|b| **b == b' ' || **b == b'\t').count();
+    if body[index + padding..].starts_with(b"\r\n") {
+        Some(index + padding + 2)
+    } else {
+        None
+    }
+}
+
+/// Parses a multipart body into its parts, using the boundary from the content type. Delimiters
+/// are only recognised at the start of a line, and any preamble before the first delimiter and
+/// epilogue after the closing one are ignored.
+pub fn parse_multipart(body: &[u8], boundary: &str) -> Result<Vec<Part>, String> {
+    let delimiter = format!("\r\n--{}", boundary).into_bytes();
+    // The first delimiter may be at the start of the body, without a preceding line ending
+    let mut delimiter_end = if body.starts_with(&delimiter[2..]) {
+        delimiter.len() - 2
+    } else {
+        find_delimiter(body, &delimiter, 0)
+            .map(|index| index + delimiter.len())
+            .ok_or_else(|| format!("Could not find the multipart boundary '{}'", boundary))?
+    };
+    let mut parts = vec![];
+    loop {
+        if body[delimiter_end..].starts_with(b"--") {
+            break;
+        }
+        let start = end_of_delimiter_line(body, delimiter_end)
+            .ok_or_else(|| s!("Multipart delimiter is not followed by a line ending"))?;
+        match find_delimiter(body, &delimiter, start) {
+            Some(end) => {
+                parts.push(parse_part(&body[start..end])?);
+                delimiter_end = end + delimiter.len();
+            },
+            None => return Err(s!("Multipart body is missing the closing boundary"))
+        }
+    }
+    Ok(parts)
+}
+
+/// Finds the next delimiter that is followed by either the closing `--` or a line ending, so
+/// that text in a part that starts with the boundary is not taken to be a delimiter.
+fn find_delimiter(body: &[u8], delimiter: &[u8], from: usize) -> Option<usize> {
+    let mut position = from;
+    while let Some(index) = find_bytes(body, delimiter, position) {
+        let end = index + delimiter.len();
+        if body[end..].starts_with(b"--") || end_of_delimiter_line(body, end).is_some() {
+            return Some(index);
+        }
+        position = index + 1;
+    }
+    None
+}
+
+/// Returns the body matching rules for a part, with the paths made relative to the part
+fn part_matchers(name: &str, matchers: &MatchingRules) -> MatchingRules {
+    let mut part_rules = MatchingRules::default();
+    if let Some(category) = matchers.rules_for_category(&s!("body")) {
+        let prefixes = [format!("$.{}", name), format!("$['{}']", name)];
+        for (path, rules) in category.rules {
+            let scoped = prefixes.iter()
+                .filter(|prefix| path.starts_with(prefix.as_str()))
+                .map(|prefix| format!("${}", &path[prefix.len()..]))
+                .find(|scoped| scoped == "$" || scoped.starts_with("$.") || scoped.starts_with("$["));
+            if let Some(scoped) = scoped {
+                part_rules.add_category("body").rules.insert(scoped, rules);
+            }
+        }
+    }
+    part_rules
+}
+
+fn part_path(name: &str, path: &DocPath) -> DocPath {
+    DocPath::root().join(name).join_path(path)
+}
+
+fn match_part_headers(expected: &Part, actual: &Part, mismatches: &mut Vec<Mismatch>) {
+    for (key, value) in &expected.headers {
+        let matches = match actual.header(key) {
+            Some(_) if key.to_lowercase() == "content-type" =>
+                expected.content_type() == actual.content_type(),
+            Some(actual_value) => {
+                let mut header_mismatches = vec![];
+                super::match_header_value(key, value, &actual_value, &mut header_mismatches,
+                    &MatchingRules::default());
+                header_mismatches.is_empty()
+            },
+            None => false
+        };
+        if !matches {
+            mismatches.push(Mismatch::BodyMismatch { path: DocPath::root().join(expected.name.as_str()),
+                expected: Some(value.clone().into()),
+                actual: actual.header(key).map(|value| value.into()),
+                mismatch: format!("Expected part '{}' to have header '{}' with value '{}' but was '{}'",
+                    expected.name, key, value, actual.header(key).unwrap_or_default()) });
+        }
+    }
+}
+
+fn match_part_body(expected: &Part, actual: &Part, config: DiffConfig, mismatches: &mut Vec<Mismatch>,
+    matchers: &MatchingRules) {
+    let matchers = part_matchers(&expected.name, matchers);
+    let content_type = expected.content_type();
+    match (super::root_content_type_rule(&matchers), super::body_matcher_for(&content_type)) {
+        (None, None) => {
+            let expected_body = String::from_utf8_lossy(&expected.body).into_owned();
+            let actual_body = String::from_utf8_lossy(&actual.body).into_owned();
+            let path = vec![s!("$")];
+            let matcher_result = if matchers.matcher_is_defined("body", &path) {
+                match_values("body", &path, matchers.clone(), &expected_body, &actual_body)
+            } else if expected.body == actual.body {
+                Ok(())
+            } else {
+                Err(vec![format!("Expected part '{}' to have body '{}' but was '{}'", expected.name,
+                    expected_body, actual_body)])
+            };
+            if let Err(messages) = matcher_result {
+                for message in messages {
+                    mismatches.push(Mismatch::BodyMismatch { path: DocPath::root().join(expected.name.as_str()),
+                        expected: Some(expected.body.clone()),
+                        actual: Some(actual.body.clone()),
+                        mismatch: message });
+                }
+            }
+        },
+        _ => {
+            let mut part_mismatches = vec![];
+            super::compare_bodies(content_type, None, &expected.body, &actual.body, config, &mut part_mismatches, &matchers);
+            for mismatch in part_mismatches {
+                match mismatch {
+                    Mismatch::BodyMismatch { path, expected: e, actual: a, mismatch: m } => mismatches.push(
+                        Mismatch::BodyMismatch { path: part_path(&expected.name, &path), expected: e, actual: a,
+                            mismatch: m }),
+                    _ => mismatches.push(mismatch)
+                }
+            }
+        }
+    }
+}
+
+/// Matches the expected and actual multipart bodies. Parts are matched by name, and the headers
+/// and bodies of each part are compared. Matching rules for a part are defined with a path starting
+/// with the part name, like `$.name`. The bodies are split into parts with the boundaries from
+/// their content types.
+pub fn match_multipart(expected: &[u8], actual: &[u8], expected_boundary: &str, actual_boundary: &str,
+    config: DiffConfig, mismatches: &mut Vec<Mismatch>, matchers: &MatchingRules) {
+    let expected_parts = parse_multipart(expected, expected_boundary);
+    let actual_parts = parse_multipart(actual, actual_boundary);
+    match (expected_parts, actual_parts) {
+        (Ok(expected_parts), Ok(actual_parts)) => {
+            let mut actual_by_name: HashMap<String, Vec<&Part>> = HashMap::new();
+            for part in &actual_parts {
+                actual_by_name.entry(part.name.clone()).or_default().push(part);
+            }
+            let mut expected_counts: HashMap<String, usize> = HashMap::new();
+            for part in &expected_parts {
+                let index = {
+                    let count = expected_counts.entry(part.name.clone()).or_insert(0);
+                    *count += 1;
+                    *count - 1
+                };
+                match actual_by_name.get(&part.name).and_then(|parts| parts.get(index)) {
+                    Some(actual_part) => {
+                        match_part_headers(part, actual_part, mismatches);
+                        match_part_body(part, actual_part, config, mismatches, matchers);
+                    },
+                    None => mismatches.push(Mismatch::BodyMismatch { path: DocPath::root().join(part.name.as_str()),
+                        expected: Some(part.body.clone()),
+                        actual: None,
+                        mismatch: format!("Expected a part with name '{}' but was missing", part.name) })
+                }
+            }
+            if config == DiffConfig::NoUnexpectedKeys {
+                for (name, parts) in &actual_by_name {
+                    let expected_count = expected_counts.get(name).cloned().unwrap_or(0);
+                    for part in parts.iter().skip(expected_count) {
+                        mismatches.push(Mismatch::BodyMismatch { path: DocPath::root().join(name.as_str()),
+                            expected: None,
+                            actual: Some(part.body.clone()),
+                            mismatch: format!("Unexpected part with name '{}' received", name) });
+                    }
+                }
+            }
+        },
+        (expected_parts, actual_parts) => {
+            if let Err(err) = expected_parts {
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.to_vec()),
+                    actual: Some(actual.to_vec()),
+                    mismatch: format!("Failed to parse the expected body: '{}'", err)});
+            }
+            if let Err(err) = actual_parts {
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.to_vec()),
+                    actual: Some(actual.to_vec()),
+                    mismatch: format!("Failed to parse the actual body: '{}'", err)});
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    const BODY: &str = "--XYZ\r\n\
+        Content-Disposition: form-data; name=\"description\"\r\n\
+        \r\n\
+        A picture\r\n\
+        --XYZ\r\n\
+        Content-Disposition: form-data; name=\"metadata\"\r\n\
+        Content-Type: application/json\r\n\
+        \r\n\
+        {\"id\": 100, \"tags\": [\"a\"]}\r\n\
+        --XYZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line 1\r\nline 2\r\n\
+        --XYZ--\r\n";
+
+    fn match_bodies(expected: &str, actual: &str, matchers: &MatchingRules) -> Vec<String> {
+        let mut mismatches = vec![];
+        match_multipart(expected.as_bytes(), actual.as_bytes(), "XYZ", "XYZ", DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, matchers);
+        let mut descriptions: Vec<String> = mismatches.iter().map(|m| m.description()).collect();
+        descriptions.sort();
+        descriptions
+    }
+
+    #[test]
+    fn parse_multipart_returns_the_parts() {
+        let parts = parse_multipart(BODY.as_bytes(), "XYZ").unwrap();
+        expect!(parts.iter().map(|p| p.name.clone()).collect::<Vec<String>>()).to(be_equal_to(vec![
+            s!("description"), s!("metadata"), s!("file")
+        ]));
+        expect!(parts[1].content_type()).to(be_equal_to(s!("application/json")));
+        expect!(parts[2].header("content-disposition")).to(be_some().value(s!("form-data; name=\"file\"; filename=\"a.txt\"")));
+        expect!(parts[2].body.clone()).to(be_equal_to(b"line 1\r\nline 2".to_vec()));
+    }
+
+    #[test]
+    fn parse_multipart_fails_if_there_is_no_boundary() {
+        expect!(parse_multipart(b"not multipart", "XYZ")).to(be_err());
+        expect!(parse_multipart(b"--XYZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nA", "XYZ")).to(be_err());
+        expect!(parse_multipart(BODY.as_bytes(), "ABC")).to(be_err());
+    }
+
+    #[test]
+    fn parse_multipart_only_recognises_delimiters_at_the_start_of_a_line() {
+        let body = format!("--not the boundary\r\n{}", BODY.replace("line 1", "line --XYZ 1")
+            .replace("line 2", "--XYZ line 2"));
+        let parts = parse_multipart(body.as_bytes(), "XYZ").unwrap();
+        expect!(parts.len()).to(be_equal_to(3));
+        expect!(parts[2].body.clone()).to(be_equal_to(b"line --XYZ 1\r\n--XYZ line 2".to_vec()));
+    }
+
+    #[test]
+    fn boundary_is_taken_from_the_content_type() {
+        expect!(boundary("multipart/form-data; boundary=XYZ")).to(be_some().value(s!("XYZ")));
+        expect!(boundary("multipart/form-data; charset=UTF-8; Boundary=\"a b\"")).to(be_some().value(s!("a b")));
+        expect!(boundary("multipart/form-data")).to(be_none());
+    }
+
+    #[test]
+    fn matches_bodies_with_different_boundaries() {
+        let actual = BODY.replace("XYZ", "1234567890");
+        let mut mismatches = vec![];
+        match_multipart(BODY.as_bytes(), actual.as_bytes(), "XYZ", "1234567890", DiffConfig::NoUnexpectedKeys,
+            &mut mismatches, &MatchingRules::default());
+        expect!(mismatches.iter()).to(be_empty());
+    }
+
+    #[test]
+    fn reports_missing_and_unexpected_parts() {
+        let actual = BODY.replace("name=\"description\"", "name=\"title\"");
+        expect!(match_bodies(BODY, &actual, &MatchingRules::default())).to(be_equal_to(vec![
+            s!("$.description -> Expected a part with name 'description' but was missing"),
+            s!("$.title -> Unexpected part with name 'title' received")
+        ]));
+    }
+
+    #[test]
+    fn compares_the_part_headers_and_bodies() {
+        let actual = BODY.replace("Content-Type: text/plain", "Content-Type: text/csv")
+            .replace("A picture", "A photo");
+        expect!(match_bodies(BODY, &actual, &MatchingRules::default())).to(be_equal_to(vec![
+            s!("$.description -> Expected part 'description' to have body 'A picture' but was 'A photo'"),
+            s!("$.file -> Expected part 'file' to have header 'Content-Type' with value 'text/plain' but was 'text/csv'")
+        ]));
+    }
+
+    #[test]
+    fn compares_the_content_disposition_parameters_in_any_order() {
+        let actual = BODY.replace("form-data; name=\"file\"; filename=\"a.txt\"",
+            "form-data; filename=\"a.txt\"; name=\"file\"");
+        expect!(match_bodies(BODY, &actual, &MatchingRules::default()).iter()).to(be_empty());
+        let actual = BODY.replace("filename=\"a.txt\"", "filename=\"b.txt\"");
+        expect!(match_bodies(BODY, &actual, &MatchingRules::default())).to(be_equal_to(vec![
+            s!("$.file -> Expected part 'file' to have header 'Content-Disposition' with value 'form-data; name=\"file\"; filename=\"a.txt\"' but was 'form-data; name=\"file\"; filename=\"b.txt\"'")
+        ]));
+    }
+
+    #[test]
+    fn applies_the_matching_rules_scoped_to_each_part() {
+        let actual = BODY.replace("\"id\": 100", "\"id\": 200").replace("A picture", "A photo");
+        expect!(match_bodies(BODY, &actual, &MatchingRules::default())).to(be_equal_to(vec![
+            s!("$.description -> Expected part 'description' to have body 'A picture' but was 'A photo'"),
+            s!("$.metadata.id -> Expected '100' to be equal to '200'")
+        ]));
+        let matchers = matchingrules!{
+            "body" => {
+                "$.description" => [ MatchingRule::Regex(s!("^A ")) ],
+                "$.metadata.id" => [ MatchingRule::Type ]
+            }
+        };
+        expect!(match_bodies(BODY, &actual, &matchers).iter()).to(be_empty());
+    }
+}
@@ -1,11 +1,23 @@
+//! Parsing of path expressions (like `$.a.b[1]`), and a structured path type built from them
+//! which is used to report where in a document a mismatch occurred.
+
+use std::fmt::{self, Display, Formatter};
 use std::iter::Peekable;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// A token in a parsed path expression
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PathToken {
+    /// The root of the document (`$`)
     Root,
+    /// A named field (`.name` or `['name']`)
     Field(String),
+    /// An index into a list (`[1]`)
     Index(usize),
+    /// Any field (`.*`)
     Star,
+    /// Any index (`[*]`)
     StarIndex
 }
 
@@ -172,6 +184,7 @@ fn path_exp<I>(chars: &mut Peekable<I>, tokens: &mut Vec<PathToken>, path: &Stri
     Ok(())
 }
 
+/// Parses the path expression into a list of tokens
 pub fn parse_path_exp(path: String) -> Result<Vec<PathToken>, String> {
     let mut tokens = vec![];
 
@@ -198,6 +211,110 @@ pub fn parse_path_exp(path: String) -> Result<Vec<PathToken>, String> {
     }
 }
 
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(ch) if ch.is_alphabetic() || ch.is_numeric() =>
+            chars.all(|ch| ch.is_alphabetic() || ch.is_numeric() || ch == '_' || ch == '-'),
+        _ => false
+    }
+}
+
+/// Structured path to a value in a document. It is displayed and serialised as a path expression
+/// (i.e. `$.a.b[1]`), which can be parsed back with `DocPath::new`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DocPath {
+    tokens: Vec<PathToken>
+}
+
+impl DocPath {
+
+    /// Path to the root of the document
+    pub fn root() -> DocPath {
+        DocPath { tokens: vec![PathToken::Root] }
+    }
+
+    /// Parses the path expression into a path
+    pub fn new<S: Into<String>>(expression: S) -> Result<DocPath, String> {
+        parse_path_exp(expression.into()).map(|tokens| DocPath { tokens })
+    }
+
+    /// Builds a path from the list of path segments used while matching XML bodies (i.e.
+    /// `["$", "a", "1"]`). As these segments don't record whether a number refers to a field or a
+    /// list item, numeric segments are taken as indexes, which is how matching rule paths treat them.
+    /// JSON bodies can have numeric keys, so the JSON matcher builds its paths as it walks the body.
+    pub fn from_segments<S: AsRef<str>>(segments: &[S]) -> DocPath {
+        DocPath {
+            tokens: segments.iter().map(|segment| {
+                let segment = segment.as_ref();
+                match segment {
+                    "$" => PathToken::Root,
+                    "*" => PathToken::Star,
+                    _ => match segment.parse() {
+                        Ok(index) => PathToken::Index(index),
+                        Err(_) => PathToken::Field(segment.to_string())
+                    }
+                }
+            }).collect()
+        }
+    }
+
+    /// The tokens of this path
+    pub fn tokens(&self) -> &Vec<PathToken> {
+        &self.tokens
+    }
+
+    /// Returns a new path with the field appended to this one
+    pub fn join<S: Into<String>>(&self, field: S) -> DocPath {
+        let mut tokens = self.tokens.clone();
+        tokens.push(PathToken::Field(field.into()));
+        DocPath { tokens }
+    }
+
+    /// Returns a new path with the list index appended to this one
+    pub fn join_index(&self, index: usize) -> DocPath {
+        let mut tokens = self.tokens.clone();
+        tokens.push(PathToken::Index(index));
+        DocPath { tokens }
+    }
+
+    /// Returns a new path with the other path (without its root) appended to this one
+    pub fn join_path(&self, other: &DocPath) -> DocPath {
+        let mut tokens = self.tokens.clone();
+        tokens.extend(other.tokens.iter().filter(|token| **token != PathToken::Root).cloned());
+        DocPath { tokens }
+    }
+}
+
+impl Display for DocPath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for token in &self.tokens {
+            match *token {
+                PathToken::Root => write!(f, "$")?,
+                PathToken::Field(ref name) if is_identifier(name) => write!(f, ".{}", name)?,
+                PathToken::Field(ref name) => write!(f, "['{}']", name)?,
+                PathToken::Index(index) => write!(f, "[{}]", index)?,
+                PathToken::Star => write!(f, ".*")?,
+                PathToken::StarIndex => write!(f, "[*]")?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for DocPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DocPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<DocPath, D::Error> {
+        let expression = String::deserialize(deserializer)?;
+        DocPath::new(expression).map_err(D::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -314,4 +431,22 @@ mod tests {
         expect!(parse_path_exp(s!("$[-1]"))).to(
             be_err().value(s!("Indexes can only consist of numbers or a \"*\", found \"-\" instead in path expression \"$[-1]\" at index 2")));
     }
+
+    #[test]
+    fn doc_path_is_displayed_as_a_path_expression() {
+        expect!(DocPath::root().to_string()).to(be_equal_to("$"));
+        expect!(DocPath::from_segments(&["$", "a", "1", "b c", "@id"]).to_string())
+            .to(be_equal_to("$.a[1]['b c']['@id']"));
+        expect!(DocPath::root().join("a").join_path(&DocPath::new("$.b[2]").unwrap()).to_string())
+            .to(be_equal_to("$.a.b[2]"));
+        expect!(DocPath::root().join("1").join_index(1).to_string()).to(be_equal_to("$.1[1]"));
+    }
+
+    #[test]
+    fn doc_path_round_trips_through_its_path_expression() {
+        let path = DocPath::from_segments(&["$", "a", "1", "b c", "@id", "*"]);
+        expect!(DocPath::new(path.to_string())).to(be_ok().value(path.clone()));
+        expect!(path.tokens().clone()).to(be_equal_to(vec![PathToken::Root, PathToken::Field(s!("a")),
+            PathToken::Index(1), PathToken::Field(s!("b c")), PathToken::Field(s!("@id")), PathToken::Star]));
+    }
 }
@@ -2,7 +2,7 @@ use super::*;
 use super::{match_header_value, strip_whitespace};
 use std::collections::HashMap;
 use expectest::prelude::*;
-use models::{Request, OptionalBody};
+use models::{Request, Response, OptionalBody, HeaderMap};
 
 #[test]
 fn match_method_returns_nothing_if_the_method_matches() {
@@ -41,6 +41,23 @@ fn match_status_returns_a_mismatch_if_the_status_does_not_match() {
     assert_eq!(mismatches[0], Mismatch::StatusMismatch { expected: 200, actual: 300 });
 }
 
+#[test]
+fn match_status_with_matchers_uses_the_status_class_rule() {
+    let matchers = matchingrules!{ "status" => { "" => [ MatchingRule::StatusCode(HttpStatus::Success) ] } };
+    let mut mismatches = vec![];
+    match_status_with_matchers(200, 204, &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+    match_status_with_matchers(200, 404, &mut mismatches, &matchers);
+    assert_eq!(mismatches, vec![Mismatch::StatusMismatch { expected: 200, actual: 404 }]);
+}
+
+#[test]
+fn match_status_with_matchers_falls_back_to_equality() {
+    let mut mismatches = vec![];
+    match_status_with_matchers(200, 204, &mut mismatches, &MatchingRules::default());
+    assert_eq!(mismatches, vec![Mismatch::StatusMismatch { expected: 200, actual: 204 }]);
+}
+
 #[test]
 fn match_query_returns_nothing_if_there_are_no_query_strings() {
     let mut mismatches = vec![];
@@ -344,10 +361,10 @@ fn mismatched_header_description_reports_accept_header_mismatches_correctly() {
 fn body_does_not_match_if_different_content_types() {
     let mut mismatches = vec![];
     let expected = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()),
         body: OptionalBody::Present(vec![]), .. Request::default_request() };
     let actual = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("text/plain") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into()),
         body: OptionalBody::Missing, .. Request::default_request() };
     match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
     expect!(mismatches.iter()).to_not(be_empty());
@@ -359,10 +376,10 @@ fn body_does_not_match_if_different_content_types() {
 fn body_matches_if_expected_is_missing() {
     let mut mismatches = vec![];
     let expected = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()),
         body: OptionalBody::Missing, .. Request::default_request() };
     let actual = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()),
         body: OptionalBody::Present("{}".into()), .. Request::default_request() };
     match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
     expect!(mismatches.iter()).to(be_empty());
@@ -372,15 +389,173 @@ fn body_matches_if_expected_is_missing() {
 fn body_matches_with_extended_mime_types() {
     let mut mismatches = vec![];
     let expected = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/thrift+json") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/thrift+json")] }.into()),
         body: OptionalBody::Present(r#"{"test":true}"#.into()), .. Request::default_request() };
     let actual = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/thrift+json") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/thrift+json")] }.into()),
         body: OptionalBody::Present(r#"{"test": true}"#.into()), .. Request::default_request() };
     match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
     expect!(mismatches.iter()).to(be_empty());
 }
 
+fn match_sorted_lines(expected: &[u8], actual: &[u8], _config: DiffConfig, mismatches: &mut Vec<Mismatch>,
+    _matchers: &MatchingRules) {
+    let mut expected_lines: Vec<String> = String::from_utf8_lossy(expected).lines().map(|l| s!(l)).collect();
+    let mut actual_lines: Vec<String> = String::from_utf8_lossy(actual).lines().map(|l| s!(l)).collect();
+    expected_lines.sort();
+    actual_lines.sort();
+    if expected_lines != actual_lines {
+        mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.to_vec()),
+            actual: Some(actual.to_vec()), mismatch: s!("Lines do not match") });
+    }
+}
+
+#[test]
+fn body_matches_with_a_registered_body_matcher() {
+    register_body_matcher("application/x-test-lines", Arc::new(match_sorted_lines)).unwrap();
+    let expected = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/x-test-lines")] }.into()),
+        body: OptionalBody::Present("a,1\nb,2\n".into()), .. Request::default_request() };
+    let actual = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/x-test-lines")] }.into()),
+        body: OptionalBody::Present("b,2\na,1\n".into()), .. Request::default_request() };
+    let mut mismatches = vec![];
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to(be_empty());
+
+    let actual = Request { body: OptionalBody::Present("b,2\nc,3\n".into()), .. actual };
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to_not(be_empty());
+}
+
+#[test]
+fn form_post_bodies_are_matched_by_field() {
+    let expected = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/x-www-form-urlencoded")] }.into()),
+        body: OptionalBody::Present("name=Fred+Smith&age=20".into()), .. Request::default_request() };
+    let actual = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/x-www-form-urlencoded")] }.into()),
+        body: OptionalBody::Present("age=21&name=Fred%20Smith".into()), .. Request::default_request() };
+    let mut mismatches = vec![];
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{
+        "body" => { "$.age" => [ MatchingRule::Type ] }
+    });
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("$.age -> Expected '20' to be equal to '21'")
+    ]));
+}
+
+#[test]
+fn binary_bodies_are_matched_by_the_content_type_matcher() {
+    let expected = Response { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/pdf")] }.into()),
+        body: OptionalBody::Present(b"%PDF-1.4 expected".to_vec()), .. Response::default_response() };
+    let actual = Response { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/pdf")] }.into()),
+        body: OptionalBody::Present(b"%PDF-1.7 \xE2\xE3\xCF\xD3 actual".to_vec()), .. Response::default_response() };
+    let matchers = matchingrules!{
+        "body" => { "$" => [ MatchingRule::ContentType(s!("application/pdf")) ] }
+    };
+    let mut mismatches = vec![];
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    let actual = Response { body: OptionalBody::Present(b"\x89PNG\r\n\x1a\n".to_vec()), .. actual };
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("$ -> Expected binary contents to have content type 'application/pdf' but detected contents was 'image/png'")
+    ]));
+}
+
+#[test]
+fn the_diff_config_for_requests_and_responses_can_be_changed() {
+    let json: HeaderMap = hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into();
+    let expected_request = Request { headers: Some(json.clone()),
+        body: OptionalBody::Present("{\"a\": 1}".into()), .. Request::default_request() };
+    let actual_request = Request { body: OptionalBody::Present("{\"a\": 1, \"b\": 2}".into()),
+        .. expected_request.clone() };
+    expect!(match_request(expected_request.clone(), actual_request.clone()).iter()).to_not(be_empty());
+    expect!(match_request_with_config(expected_request, actual_request,
+        DiffConfig::AllowUnexpectedKeys).iter()).to(be_empty());
+
+    let expected_response = Response { headers: Some(json),
+        body: OptionalBody::Present("{\"a\": 1}".into()), .. Response::default_response() };
+    let actual_response = Response { body: OptionalBody::Present("{\"a\": 1, \"b\": 2}".into()),
+        .. expected_response.clone() };
+    expect!(match_response(expected_response.clone(), actual_response.clone()).iter()).to(be_empty());
+    expect!(match_response_with_config(expected_response, actual_response,
+        DiffConfig::NoUnexpectedKeys).iter()).to_not(be_empty());
+}
+
+#[test]
+fn multipart_bodies_are_split_with_the_boundary_from_the_content_type() {
+    let body = |boundary: &str| format!("--{0}\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n--{0}A\r\n--{0}--\r\n", boundary);
+    let expected = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("multipart/form-data; boundary=XYZ")] }.into()),
+        body: OptionalBody::Present(body("XYZ").into()), .. Request::default_request() };
+    let actual = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("multipart/form-data; boundary=\"ABC\"")] }.into()),
+        body: OptionalBody::Present(body("ABC").replace("--ABCA", "--XYZA").into()), .. Request::default_request() };
+    let mut mismatches = vec![];
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to(be_empty());
+
+    let actual = Request { body: OptionalBody::Present(body("ABC").into()), .. actual };
+    match_body(&expected, &actual, DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("$.a -> Expected part 'a' to have body '--XYZA' but was '--ABCA'")
+    ]));
+}
+
+#[test]
+fn text_bodies_are_matched_with_a_root_body_rule() {
+    let expected = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into()),
+        body: OptionalBody::Present("Order 1234 accepted".into()), .. Request::default_request() };
+    let actual = Request { body: OptionalBody::Present("Order 9876 accepted".into()), .. expected.clone() };
+    let matchers = matchingrules!{
+        "body" => { "$" => [ MatchingRule::Regex(s!("^Order \\d+ accepted$")) ] }
+    };
+    let mut mismatches = vec![];
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    let actual = Request { body: OptionalBody::Present("Order rejected".into()), .. expected.clone() };
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("$ -> Expected 'Order rejected' to match '^Order \\d+ accepted$'")
+    ]));
+}
+
+#[test]
+fn html_bodies_are_matched_with_an_include_rule() {
+    let expected = Response { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/html")] }.into()),
+        body: OptionalBody::Present("<h1>Welcome</h1>".into()), .. Response::default_response() };
+    let actual = Response { body: OptionalBody::Present("<html><body><h1>Welcome</h1></body></html>".into()),
+        .. expected.clone() };
+    let matchers = matchingrules!{
+        "body" => { "$" => [ MatchingRule::Include(s!("Welcome")) ] }
+    };
+    let mut mismatches = vec![];
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    let actual = Response { body: OptionalBody::Present("<h1>Goodbye</h1>".into()), .. expected.clone() };
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchers);
+    expect!(mismatches.len()).to(be_equal_to(1));
+}
+
+#[test]
+fn text_body_mismatches_show_the_text() {
+    let expected = Request { headers: Some(hashmap!{ s!("Content-Type") => vec![s!("text/plain")] }.into()),
+        body: OptionalBody::Present("hello".into()), .. Request::default_request() };
+    let actual = Request { body: OptionalBody::Present("goodbye".into()), .. expected.clone() };
+    let mut mismatches = vec![];
+    match_body(&expected, &actual, DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("$ -> Expected text 'hello' but received 'goodbye'")
+    ]));
+}
+
+#[test]
+fn register_body_matcher_fails_with_an_invalid_regex() {
+    expect!(register_body_matcher("application/(x-test", Arc::new(match_sorted_lines))).to(be_err());
+}
+
 #[test]
 fn partial_equal_for_method_mismatch() {
     let mismatch = Mismatch::MethodMismatch { expected: s!("get"), actual: s!("post") };
@@ -396,7 +571,7 @@ fn partial_equal_for_method_mismatch() {
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!("") }));
-    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: DocPath::root() }));
 }
 
 #[test]
@@ -414,7 +589,7 @@ fn partial_equal_for_path_mismatch() {
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!("") }));
-    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: DocPath::root() }));
 }
 
 #[test]
@@ -432,7 +607,7 @@ fn partial_equal_for_status_mismatch() {
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!("") }));
-    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: DocPath::root() }));
 }
 
 #[test]
@@ -450,7 +625,7 @@ fn partial_equal_for_body_type_mismatch() {
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::PathMismatch { expected: s!(""), actual: s!(""), mismatch: s!("") }));
-    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: DocPath::root() }));
 }
 
 #[test]
@@ -472,7 +647,7 @@ fn partial_equal_for_query_mismatch() {
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::PathMismatch { expected: s!(""), actual: s!(""), mismatch: s!("")}));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::HeaderMismatch { key: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!("") }));
-    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: DocPath::root() }));
 }
 
 #[test]
@@ -494,17 +669,17 @@ fn partial_equal_for_header_mismatch() {
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::PathMismatch { expected: s!(""), actual: s!(""), mismatch: s!("")}));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!(""), actual: s!(""), mismatch: s!("") }));
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyTypeMismatch { expected: s!(""), actual: s!("") }));
-    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: s!("/") }));
+    expect!(&mismatch).to_not(be_equal_to(&Mismatch::BodyMismatch { expected: Some("get".into()), actual: Some("post".into()), mismatch: s!(""), path: DocPath::root() }));
 }
 
 #[test]
 fn partial_equal_for_body_mismatch() {
-    let mismatch = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
-    let mismatch2 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
-    let mismatch3 = Mismatch::BodyMismatch { path: s!("key2"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
-    let mismatch4 = Mismatch::BodyMismatch { path: s!("key"), expected: None, actual: Some("v2".into()), mismatch: s!("") };
-    let mismatch5 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: None, mismatch: s!("") };
-    let mismatch6 = Mismatch::BodyMismatch { path: s!("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("did not match") };
+    let mismatch = Mismatch::BodyMismatch { path: DocPath::root().join("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
+    let mismatch2 = Mismatch::BodyMismatch { path: DocPath::root().join("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
+    let mismatch3 = Mismatch::BodyMismatch { path: DocPath::root().join("key2"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("") };
+    let mismatch4 = Mismatch::BodyMismatch { path: DocPath::root().join("key"), expected: None, actual: Some("v2".into()), mismatch: s!("") };
+    let mismatch5 = Mismatch::BodyMismatch { path: DocPath::root().join("key"), expected: Some("v1".into()), actual: None, mismatch: s!("") };
+    let mismatch6 = Mismatch::BodyMismatch { path: DocPath::root().join("key"), expected: Some("v1".into()), actual: Some("v2".into()), mismatch: s!("did not match") };
     expect!(&mismatch).to(be_equal_to(&mismatch));
     expect!(&mismatch).to(be_equal_to(&mismatch2));
     expect!(&mismatch).to(be_equal_to(&mismatch6));
@@ -519,6 +694,47 @@ fn partial_equal_for_body_mismatch() {
     expect!(&mismatch).to_not(be_equal_to(&Mismatch::QueryMismatch { parameter: s!(""), expected: s!("get"), actual: s!("post"), mismatch: s!("") }));
 }
 
+#[test]
+fn mismatches_are_serialised_with_their_type_and_path() {
+    let mismatch = Mismatch::BodyMismatch { path: DocPath::new("$.a[1]").unwrap(), expected: Some("1".into()),
+        actual: None, mismatch: s!("Expected 1 but was missing") };
+    expect!(mismatch.to_json()).to(be_equal_to(json!({
+        "type": "BodyMismatch",
+        "path": "$.a[1]",
+        "expected": [49],
+        "actual": null,
+        "mismatch": "Expected 1 but was missing"
+    })));
+    expect!(Mismatch::QueryMismatch { parameter: s!("a"), expected: s!("b"), actual: s!("c"), mismatch: s!("d") }.to_json())
+        .to(be_equal_to(json!({
+            "type": "QueryMismatch",
+            "parameter": "a",
+            "expected": "b",
+            "actual": "c",
+            "mismatch": "d"
+        })));
+}
+
+#[test]
+fn mismatches_round_trip_through_json() {
+    let mismatches = vec![
+        Mismatch::MethodMismatch { expected: s!("GET"), actual: s!("POST") },
+        Mismatch::StatusMismatch { expected: 200, actual: 404 },
+        Mismatch::HeaderMismatch { key: s!("A"), expected: s!("b"), actual: s!("c"), mismatch: s!("d") },
+        Mismatch::BodyMismatch { path: DocPath::root().join("a b").join("c"), expected: None,
+            actual: Some("2".into()), mismatch: s!("Unexpected") }
+    ];
+    for mismatch in mismatches {
+        let result = Mismatch::from_json(&mismatch.to_json()).unwrap();
+        expect!(&result).to(be_equal_to(&mismatch));
+        expect!(result.description()).to(be_equal_to(mismatch.description()));
+    }
+    expect!(Mismatch::BodyMismatch { path: DocPath::root().join("a b").join("c"), expected: None,
+        actual: None, mismatch: s!("Unexpected") }.description()).to(be_equal_to("$['a b'].c -> Unexpected"));
+    expect!(Mismatch::from_json(&json!({ "type": "BodyMismatch", "path": "$[", "expected": null,
+        "actual": null, "mismatch": "" }))).to(be_err());
+}
+
 #[test]
 #[ignore]
 fn strip_whitespace_quickcheck() {
@@ -615,6 +831,194 @@ fn match_query_returns_a_mismatch_if_the_values_do_not_match_by_a_matcher() {
         mismatch: s!("") });
 }
 
+#[test]
+fn match_query_with_an_unordered_matcher_ignores_the_order_of_the_values() {
+    let matchers = matchingrules!{ "query" => { "ids" => [ MatchingRule::Unordered ] } };
+    let expected = Some(hashmap!{ s!("ids") => vec![s!("1"), s!("2")] });
+    let mut mismatches = vec![];
+    match_query(expected.clone(), Some(hashmap!{ s!("ids") => vec![s!("2"), s!("1")] }), &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_query(expected.clone(), Some(hashmap!{ s!("ids") => vec![s!("2"), s!("3")] }), &mut mismatches, &matchers);
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("Expected query parameter 'ids' value '1' but was missing")
+    ]));
+}
+
+#[test]
+fn match_query_with_a_min_type_matcher_allows_more_values() {
+    let matchers = matchingrules!{ "query" => { "ids" => [ MatchingRule::MinType(2) ] } };
+    let expected = Some(hashmap!{ s!("ids") => vec![s!("1"), s!("2")] });
+    let mut mismatches = vec![];
+    match_query(expected.clone(), Some(hashmap!{ s!("ids") => vec![s!("7"), s!("8"), s!("9")] }),
+        &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_query(expected.clone(), Some(hashmap!{ s!("ids") => vec![s!("7")] }), &mut mismatches, &matchers);
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("Expected query parameter 'ids' with at least 2 value(s) but received 1 value(s)")
+    ]));
+}
+
+#[test]
+fn match_query_with_a_max_type_matcher_and_value_matchers() {
+    let matchers = matchingrules!{ "query" => {
+        "ids" => [ MatchingRule::MaxType(2) ],
+        "ids[*]" => [ MatchingRule::Regex(s!("^\\d+$")) ]
+    } };
+    let expected = Some(hashmap!{ s!("ids") => vec![s!("1")] });
+    let mut mismatches = vec![];
+    match_query(expected.clone(), Some(hashmap!{ s!("ids") => vec![s!("7"), s!("8")] }), &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_query(expected.clone(), Some(hashmap!{ s!("ids") => vec![s!("7"), s!("8"), s!("9")] }),
+        &mut mismatches, &matchers);
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("Expected query parameter 'ids' with at most 2 value(s) but received 3 value(s)")
+    ]));
+}
+
+#[test]
+fn match_query_treats_bracketed_array_parameters_as_the_same_parameter() {
+    let matchers = matchingrules!{ "query" => { "ids[]" => [ MatchingRule::MinType(1) ] } };
+    let mut mismatches = vec![];
+    match_query(Some(hashmap!{ s!("ids[]") => vec![s!("1")] }),
+        Some(hashmap!{ s!("ids[]") => vec![s!("4"), s!("5")] }), &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_query(Some(hashmap!{ s!("ids") => vec![s!("1"), s!("2")] }),
+        Some(hashmap!{ s!("ids[]") => vec![s!("1"), s!("2")] }), &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to(be_empty());
+}
+
+#[test]
+fn query_parameter_name_strips_the_array_brackets() {
+    expect!(models::query_parameter_name("ids[]")).to(be_equal_to("ids"));
+    expect!(models::query_parameter_name("ids")).to(be_equal_to("ids"));
+    expect!(models::query_parameter_name("a[b]")).to(be_equal_to("a[b]"));
+}
+
+#[test]
+fn match_headers_ignores_the_case_of_header_names() {
+    let mut mismatches = vec![];
+    match_headers(Some(hashmap!{ s!("X-Request-Id") => vec![s!("1234")] }.into()),
+        Some(hashmap!{ s!("x-request-id") => vec![s!("1234")] }.into()), &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to(be_empty());
+}
+
+#[test]
+fn match_headers_matches_each_value_in_order() {
+    let expected = Some(hashmap!{ s!("Vary") => vec![s!("Accept"), s!("Origin")] }.into());
+    let mut mismatches = vec![];
+    match_headers(expected.clone(), Some(hashmap!{ s!("Vary") => vec![s!("Accept"), s!("Origin")] }.into()),
+        &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_headers(expected.clone(), Some(hashmap!{ s!("Vary") => vec![s!("Origin"), s!("Accept")] }.into()),
+        &mut mismatches, &matchingrules!{});
+    expect!(mismatches.len()).to(be_equal_to(2));
+
+    let mut mismatches = vec![];
+    match_headers(expected.clone(), Some(hashmap!{ s!("Vary") => vec![s!("Accept")] }.into()),
+        &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("Expected header 'Vary' to have 2 value(s) but received 1 value(s)")
+    ]));
+}
+
+#[test]
+fn match_headers_applies_a_header_matcher_to_the_joined_values() {
+    let matchers = matchingrules!{
+        "header" => { "Accept" => [ MatchingRule::Regex(s!("^application/json, text/plain$")) ] }
+    };
+    let expected = Some(hashmap!{ s!("Accept") => vec![s!("application/json"), s!("text/plain")] }.into());
+    let mut mismatches = vec![];
+    match_headers(expected.clone(), Some(hashmap!{ s!("accept") => vec![s!("application/json"), s!("text/plain")] }.into()),
+        &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_headers(expected.clone(), Some(hashmap!{ s!("Accept") => vec![s!("text/plain"), s!("application/json")] }.into()),
+        &mut mismatches, &matchers);
+    expect!(mismatches).to(be_equal_to(vec![Mismatch::HeaderMismatch {
+        key: s!("Accept"),
+        expected: s!("application/json, text/plain"),
+        actual: s!("text/plain, application/json"),
+        mismatch: s!("Mismatch with header 'Accept': Expected 'text/plain, application/json' to match '^application/json, text/plain$'")
+    }]));
+}
+
+#[test]
+fn parse_header_only_splits_headers_that_are_lists() {
+    expect!(models::parse_header("Accept", "application/json, text/plain")).to(
+        be_equal_to(vec![s!("application/json"), s!("text/plain")]));
+    expect!(models::parse_header("X-Custom", "a, b")).to(be_equal_to(vec![s!("a, b")]));
+    expect!(models::parse_header("Cache-Control", "no-cache, private=\"a, b\"")).to(
+        be_equal_to(vec![s!("no-cache"), s!("private=\"a, b\"")]));
+}
+
+#[test]
+fn match_headers_compares_structured_headers_ignoring_order() {
+    let mut mismatches = vec![];
+    match_headers(Some(hashmap!{
+        s!("Cache-Control") => vec![s!("no-cache"), s!("max-age=0")],
+        s!("Content-Disposition") => vec![s!("attachment; filename=\"report.pdf\"; size=100")],
+        s!("Authorization") => vec![s!("Bearer abc123")]
+    }.into()), Some(hashmap!{
+        s!("cache-control") => vec![s!("max-age=0"), s!("no-cache")],
+        s!("Content-Disposition") => vec![s!("attachment; size=100; filename=report.pdf")],
+        s!("Authorization") => vec![s!("bearer abc123")]
+    }.into()), &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_headers(Some(hashmap!{ s!("Cache-Control") => vec![s!("no-cache"), s!("max-age=0")] }.into()),
+        Some(hashmap!{ s!("Cache-Control") => vec![s!("no-store")] }.into()), &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("Mismatch with header 'Cache-Control': Expected Cache-Control directive 'no-cache' but was missing"),
+        s!("Mismatch with header 'Cache-Control': Expected Cache-Control directive 'max-age=0' but was missing"),
+        s!("Mismatch with header 'Cache-Control': Unexpected Cache-Control directive 'no-store'")
+    ]));
+}
+
+#[test]
+fn match_headers_matches_date_headers_with_a_timestamp_matcher() {
+    let matchers = matchingrules!{
+        "header" => { "Expires" => [ MatchingRule::Timestamp(s!("EEE, dd MMM yyyy HH:mm:ss zzz")) ] }
+    };
+    let expected = Some(hashmap!{
+        s!("Date") => vec![s!("Sun, 06 Nov 1994 08:49:37 GMT")],
+        s!("Expires") => vec![s!("Sun, 06 Nov 1994 09:49:37 GMT")]
+    }.into());
+    let mut mismatches = vec![];
+    match_headers(expected.clone(), Some(hashmap!{
+        s!("Date") => vec![s!("Sunday, 06-Nov-94 08:49:37 GMT")],
+        s!("Expires") => vec![s!("Wed, 21 Oct 2015 07:28:00 GMT")]
+    }.into()), &mut mismatches, &matchers);
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_headers(expected.clone(), Some(hashmap!{
+        s!("Date") => vec![s!("Sun, 06 Nov 1994 08:49:37 GMT")],
+        s!("Expires") => vec![s!("tomorrow")]
+    }.into()), &mut mismatches, &matchers);
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("Mismatch with header 'Expires': Expected 'tomorrow' to be an HTTP-date")
+    ]));
+}
+
+#[test]
+fn match_headers_matches_expires_headers_that_are_not_dates() {
+    let expected = Some(hashmap!{ s!("Expires") => vec![s!("0")] }.into());
+    let mut mismatches = vec![];
+    match_headers(expected.clone(), Some(hashmap!{ s!("Expires") => vec![s!("0")] }.into()),
+        &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter()).to(be_empty());
+
+    match_headers(expected.clone(), Some(hashmap!{ s!("Expires") => vec![s!("Wed, 21 Oct 2015 07:28:00 GMT")] }.into()),
+        &mut mismatches, &matchingrules!{});
+    expect!(mismatches.iter().map(|m| m.description()).collect::<Vec<String>>()).to(be_equal_to(vec![
+        s!("Mismatch with header 'Expires': Expected '0' to be equal to 'Wed, 21 Oct 2015 07:28:00 GMT'")
+    ]));
+}
+
 #[test]
 fn matching_headers_be_true_when_headers_match_by_matcher() {
     let mut mismatches = vec![];
@@ -0,0 +1,123 @@
+//! Support for validating dates and times against the date/time formats used by the timestamp, date
+//! and time matchers. The formats are the ones used by Java's `SimpleDateFormat` (i.e.
+//! `yyyy-MM-dd'T'HH:mm:ss`), which is what pact files from the other implementations use.
+
+use regex::{Regex, escape};
+
+/// Returns the regular expression for a run of the same pattern letter
+fn pattern_letter_regex(letter: char, count: usize) -> Result<String, String> {
+    let digits = |max: usize| if count >= max { format!("\\d{{{}}}", count) } else { format!("\\d{{{},{}}}", count, max) };
+    match letter {
+        'G' => Ok(s!("(?:AD|BC)")),
+        'y' | 'Y' | 'u' => Ok(if count == 2 { s!("\\d{2}") } else { format!("\\d{{{},}}", count.max(4)) }),
+        'M' | 'L' => Ok(match count {
+            1 | 2 => digits(2),
+            3 => s!("[A-Za-z]{3}"),
+            _ => s!("[A-Za-z]+")
+        }),
+        'w' | 'd' | 'H' | 'k' | 'K' | 'h' | 'm' | 's' => Ok(digits(2)),
+        'W' | 'F' => Ok(digits(1)),
+        'D' => Ok(digits(3)),
+        'S' => Ok(format!("\\d{{{}}}", count)),
+        'E' => Ok(if count <= 3 { s!("[A-Za-z]{3}") } else { s!("[A-Za-z]+") }),
+        'a' => Ok(s!("(?:AM|PM|am|pm)")),
+        'z' => Ok(s!("(?:[A-Za-z]+(?:[+-]\\d{1,2}(?::?\\d{2})?)?|[+-]\\d{2}:?\\d{2})")),
+        'Z' => Ok(s!("[+-]\\d{4}")),
+        'X' => Ok(match count {
+            1 => s!("(?:Z|[+-]\\d{2}(?:\\d{2})?)"),
+            2 => s!("(?:Z|[+-]\\d{4})"),
+            _ => s!("(?:Z|[+-]\\d{2}:\\d{2})")
+        }),
+        _ => Err(format!("'{}' is not a supported date/time pattern letter", letter))
+    }
+}
+
+/// Converts a date/time format to a regular expression that matches values in that format.
+pub fn format_to_regex(format: &str) -> Result<String, String> {
+    let chars: Vec<char> = format.chars().collect();
+    let mut regex = s!("^");
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        if ch == '\'' {
+            index += 1;
+            if chars.get(index) == Some(&'\'') {
+                regex.push('\'');
+                index += 1;
+                continue;
+            }
+            let mut literal = String::new();
+            loop {
+                match chars.get(index) {
+                    None => return Err(format!("'{}' has an unterminated quoted literal", format)),
+                    Some(&'\'') if chars.get(index + 1) == Some(&'\'') => {
+                        literal.push('\'');
+                        index += 2;
+                    },
+                    Some(&'\'') => {
+                        index += 1;
+                        break;
+                    },
+                    Some(&c) => {
+                        literal.push(c);
+                        index += 1;
+                    }
+                }
+            }
+            regex.push_str(&escape(&literal));
+        } else if ch.is_ascii_alphabetic() {
+            let count = chars[index..].iter().take_while(|&&c| c == ch).count();
+            regex.push_str(&pattern_letter_regex(ch, count)?);
+            index += count;
+        } else {
+            regex.push_str(&escape(&ch.to_string()));
+            index += 1;
+        }
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+/// Validates that the value is in the given date/time format.
+pub fn validate_datetime(value: &str, format: &str) -> Result<(), String> {
+    let regex = format_to_regex(format)?;
+    match Regex::new(&regex) {
+        Ok(re) => if re.is_match(value) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' to match a date/time of format '{}'", value, format))
+        },
+        Err(err) => Err(format!("'{}' is not a valid date/time format - {}", format, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expectest::prelude::*;
+
+    #[test]
+    fn validates_values_against_the_format() {
+        expect!(validate_datetime("2018-06-01T10:15:30", "yyyy-MM-dd'T'HH:mm:ss")).to(be_ok());
+        expect!(validate_datetime("2018-06-01 10:15:30", "yyyy-MM-dd'T'HH:mm:ss")).to(be_err());
+        expect!(validate_datetime("2018-06-01T10:15:30.123+10:00", "yyyy-MM-dd'T'HH:mm:ss.SSSXXX")).to(be_ok());
+        expect!(validate_datetime("10:15", "HH:mm")).to(be_ok());
+        expect!(validate_datetime("10:15pm", "HH:mm")).to(be_err());
+        expect!(validate_datetime("Tue, 15 Nov 1994 08:12:31 GMT", "EEE, dd MMM yyyy HH:mm:ss zzz")).to(be_ok());
+        expect!(validate_datetime("15 Nov 1994", "EEE, dd MMM yyyy HH:mm:ss zzz")).to(be_err());
+    }
+
+    #[test]
+    fn quoted_literals_in_the_format() {
+        expect!(validate_datetime("10 at 11", "HH 'at' mm")).to(be_ok());
+        expect!(validate_datetime("10 o'clock", "HH 'o''clock'")).to(be_ok());
+        expect!(validate_datetime("10'", "HH''")).to(be_ok());
+        expect!(validate_datetime("10 in 11", "HH 'at' mm")).to(be_err());
+    }
+
+    #[test]
+    fn invalid_formats_are_errors() {
+        expect!(validate_datetime("10", "HH 'at")).to(be_err().value(s!("'HH 'at' has an unterminated quoted literal")));
+        expect!(validate_datetime("10", "qq")).to(be_err().value(s!("'q' is not a supported date/time pattern letter")));
+    }
+}
@@ -7,8 +7,9 @@ use models::matchingrules::*;
 use matchers::*;
 use regex::Regex;
 use models::xml_utils::parse_bytes;
+use path_exp::DocPath;
 
-pub fn match_xml(expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
+pub fn match_xml(expected: &[u8], actual: &[u8], config: DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
     let expected_result = parse_bytes(expected);
     let actual_result = parse_bytes(actual);
@@ -16,16 +17,16 @@ pub fn match_xml(expected: &Vec<u8>, actual: &Vec<u8>, config: DiffConfig,
     if expected_result.is_err() || actual_result.is_err() {
         match expected_result {
             Err(e) => {
-                mismatches.push(Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.clone().into()),
-                    actual: Some(actual.clone().into()),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.to_vec()),
+                    actual: Some(actual.to_vec()),
                     mismatch: format!("Failed to parse the expected body: '{:?}'", e)});
             },
             _ => ()
         }
         match actual_result {
             Err(e) => {
-                mismatches.push(Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.clone().into()),
-                    actual: Some(actual.clone().into()),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.to_vec()),
+                    actual: Some(actual.to_vec()),
                     mismatch: format!("Failed to parse the actual body: '{:?}'", e)});
             },
             _ => ()
@@ -63,19 +64,19 @@ impl<'a> Matches<Element<'a>> for Element<'a> {
              Err(format!("Expected '{}' to be the same type as '{}'", self.name().local_part(),
                   actual.name().local_part()))
           },
-          MatchingRule::MinType(min) => if actual.children().len() < min {
+          MatchingRule::MinType(min) => if children(actual).len() < min {
              Err(format!("Expected '{}' to have at least {} children", actual.name().local_part(), min))
           } else {
              Ok(())
           },
-          MatchingRule::MaxType(max) => if actual.children().len() > max {
+          MatchingRule::MaxType(max) => if children(actual).len() > max {
              Err(format!("Expected '{}' to have at most {} children", actual.name().local_part(), max))
           } else {
              Ok(())
           },
-          MatchingRule::MinMaxType(min, max) => if actual.children().len() < min {
+          MatchingRule::MinMaxType(min, max) => if children(actual).len() < min {
             Err(format!("Expected '{}' to have at least {} children", actual.name().local_part(), min))
-          } else if actual.children().len() > max {
+          } else if children(actual).len() > max {
             Err(format!("Expected '{}' to have at most {} children", actual.name().local_part(), max))
           } else {
             Ok(())
@@ -95,16 +96,6 @@ impl<'a> Matches<Element<'a>> for Element<'a> {
     }
 }
 
-fn path_to_string(path: &Vec<String>) -> String {
-    path.iter().enumerate().map(|(i, p)| {
-        if i > 0 && !p.starts_with("[") {
-            s!(".") + p
-        } else {
-            p.clone()
-        }
-    }).collect()
-}
-
 fn compare_element(path: &Vec<String>, expected: &Element, actual: &Element, config: DiffConfig,
     mismatches: &mut Vec<super::Mismatch>, matchers: &MatchingRules) {
     let matcher_result = if matchers.matcher_is_defined("body", &path) {
@@ -113,12 +104,12 @@ fn compare_element(path: &Vec<String>, expected: &Element, actual: &Element, con
     } else {
       expected.matches(actual, &MatchingRule::Equality).map_err(|err| vec![err])
     };
-    debug!("Comparing '{:?}' to '{:?}' at path '{}' -> {:?}", expected, actual, path_to_string(path), matcher_result);
+    debug!("Comparing '{:?}' to '{:?}' at path '{}' -> {:?}", expected, actual, DocPath::from_segments(path), matcher_result);
     match matcher_result {
         Err(messages) => {
           for message in messages {
             mismatches.push(Mismatch::BodyMismatch {
-              path: path_to_string(path),
+              path: DocPath::from_segments(path),
               expected: Some(expected.name().local_part().into()),
               actual: Some(actual.name().local_part().into()),
               mismatch: message.clone()
@@ -142,21 +133,21 @@ fn compare_attributes(path: &Vec<String>, expected: &Element, actual: &Element,
     let actual_attributes: BTreeMap<String, String> = actual.attributes()
         .iter().map(|attr| (s!(attr.name().local_part()), s!(attr.value()))).collect();
     if expected_attributes.is_empty() && !actual_attributes.is_empty() && config == DiffConfig::NoUnexpectedKeys {
-      mismatches.push(Mismatch::BodyMismatch { path: path_to_string(path),
+      mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
           expected: Some(format!("{:?}", expected_attributes).into()),
           actual: Some(format!("{:?}", actual_attributes).into()),
           mismatch: format!("Did not expect any attributes but received {:?}", actual_attributes)});
     } else {
         match config {
             DiffConfig::AllowUnexpectedKeys if expected_attributes.len() > actual_attributes.len() => {
-                mismatches.push(Mismatch::BodyMismatch { path: path_to_string(path),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
                     expected: Some(format!("{:?}", expected_attributes).into()),
                     actual: Some(format!("{:?}", actual_attributes).into()),
                     mismatch: format!("Expected at least {} attribute(s) but received {} attribute(s)",
                     expected_attributes.len(), actual_attributes.len())});
             },
             DiffConfig::NoUnexpectedKeys if expected_attributes.len() != actual_attributes.len() => {
-                mismatches.push(Mismatch::BodyMismatch { path: path_to_string(path),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
                     expected: Some(format!("{:?}", expected_attributes).into()),
                     actual: Some(format!("{:?}", actual_attributes).into()),
                     mismatch: format!("Expected {} attribute(s) but received {} attribute(s)",
@@ -171,7 +162,7 @@ fn compare_attributes(path: &Vec<String>, expected: &Element, actual: &Element,
                 p.push(s!("@") + key);
                 compare_value(&p, value, &actual_attributes[key], mismatches, matchers);
             } else {
-                mismatches.push(Mismatch::BodyMismatch { path: path_to_string(path),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
                     expected: Some(format!("{:?}", expected_attributes).into()),
                     actual: Some(format!("{:?}", actual_attributes).into()),
                     mismatch: format!("Expected attribute '{}'='{}' but was missing", key, value)});
@@ -193,26 +184,38 @@ fn compare_children(path: &Vec<String>, expected: &Element, actual: &Element, co
     let mut expected_children = children(expected);
     let actual_children = children(actual);
     if matchers.matcher_is_defined("body", &path) {
+        let size_rules = matchers.resolve_body_matchers_by_path(path)
+            .map(|rules| rules.rules).unwrap_or_default().into_iter()
+            .filter(|rule| matches!(*rule,
+                MatchingRule::MinType(_) | MatchingRule::MaxType(_) | MatchingRule::MinMaxType(_, _)));
+        for rule in size_rules {
+            if let Err(message) = expected.matches(actual, &rule) {
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
+                    expected: Some(desc_children(&expected_children).into()),
+                    actual: Some(desc_children(&actual_children).into()),
+                    mismatch: message});
+            }
+        }
         if !expected_children.is_empty() {
             let expected_example = expected_children[0].clone();
             expected_children.resize(actual_children.len(), expected_example);
         }
     } else {
         if expected_children.is_empty() && !actual_children.is_empty() && config == DiffConfig::NoUnexpectedKeys {
-          mismatches.push(Mismatch::BodyMismatch { path: path_to_string(path),
+          mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
               expected: Some(desc_children(&expected_children).into()),
               actual: Some(desc_children(&actual_children).into()),
               mismatch: format!("Expected an empty List but received [{}]", desc_children(&actual_children))});
         } else if expected_children.len() != actual_children.len() {
             if config == DiffConfig::AllowUnexpectedKeys && expected_children.len() > actual_children.len() {
-                mismatches.push(Mismatch::BodyMismatch { path: path_to_string(path),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
                     expected: Some(desc_children(&expected_children).into()),
                     actual: Some(desc_children(&actual_children).into()),
                     mismatch: format!("Expected a List with at least {} element(s) but received {} element(s)",
                         expected_children.len(), actual_children.len())});
 
             } else if config == DiffConfig::NoUnexpectedKeys {
-                mismatches.push(Mismatch::BodyMismatch { path: path_to_string(path),
+                mismatches.push(Mismatch::BodyMismatch { path: DocPath::from_segments(path),
                     expected: Some(desc_children(&expected_children).into()),
                     actual: Some(desc_children(&actual_children).into()),
                     mismatch: format!("Expected a List with {} element(s) but received {} element(s)",
@@ -248,12 +251,12 @@ fn compare_text(path: &Vec<String>, expected: &Element, actual: &Element,
       expected_text.matches(&actual_text, &MatchingRule::Equality).map_err(|err| vec![err])
     };
     debug!("Comparing text '{}' to '{}' at path '{}' -> {:?}", expected_text, actual_text,
-        path_to_string(path), matcher_result);
+        DocPath::from_segments(path), matcher_result);
     match matcher_result {
         Err(messages) => {
           for message in messages {
             mismatches.push(Mismatch::BodyMismatch {
-              path: path_to_string(path) + ".#text",
+              path: DocPath::from_segments(path).join("#text"),
               expected: Some(expected_text.clone().into()),
               actual: Some(actual_text.clone().into()),
               mismatch: message.clone()
@@ -271,12 +274,12 @@ fn compare_value(path: &Vec<String>, expected: &String, actual: &String,
     } else {
       expected.matches(actual, &MatchingRule::Equality).map_err(|err| vec![err])
     };
-    debug!("Comparing '{}' to '{}' at path '{}' -> {:?}", expected, actual, path_to_string(path), matcher_result);
+    debug!("Comparing '{}' to '{}' at path '{}' -> {:?}", expected, actual, DocPath::from_segments(path), matcher_result);
     match matcher_result {
         Err(messages) => {
           for message in messages {
             mismatches.push(Mismatch::BodyMismatch {
-              path: path_to_string(path),
+              path: DocPath::from_segments(path),
               expected: Some(expected.clone().into()),
               actual: Some(actual.clone().into()),
               mismatch: message.clone()
@@ -300,10 +303,10 @@ mod tests {
         let mut mismatches = vec![];
         let expected = "";
         let actual = "";
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(2));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(vec![]),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(vec![]),
             actual: Some(vec![]), mismatch: s!("")}));
     }
 
@@ -312,10 +315,10 @@ mod tests {
         let mut mismatches = vec![];
         let expected = r#"<xml-is-bad"#;
         let actual = r#"<?xml version="1.0" encoding="UTF-8"?> <blah/>"#;
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.into()),
             actual: Some(actual.into()), mismatch: s!("")}));
     }
 
@@ -324,10 +327,10 @@ mod tests {
         let mut mismatches = vec![];
         let expected = r#"<?xml version="1.0" encoding="UTF-8"?> <blah/>"#;
         let actual = r#"{json: "is bad"}"#;
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some(expected.into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some(expected.into()),
             actual: Some(actual.into()), mismatch: s!("")}));
     }
 
@@ -343,7 +346,7 @@ mod tests {
         let mut mismatches = vec![];
         let expected = s!(r#"<?xml version="1.0" encoding="UTF-8"?> <blah/>"#);
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?> <blah/>"#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -358,7 +361,7 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><bar></bar></foo>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -371,10 +374,10 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <bar/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$"), expected: Some("foo".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::root(), expected: Some("foo".into()),
             actual: Some("bar".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'foo' to be equal to 'bar'")));
     }
@@ -388,7 +391,7 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <blah a="b" c="d"/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -401,19 +404,19 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <blah a="b"/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(3));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.blah"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.blah").unwrap(),
             expected: Some("{\"a\": \"c\", \"c\": \"b\"}".into()),
             actual: Some("{\"a\": \"b\"}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected at least 2 attribute(s) but received 1 attribute(s)")));
         let mismatch = mismatches[1].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.blah.@a"), expected: Some("c".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.blah['@a']").unwrap(), expected: Some("c".into()),
             actual: Some("b".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'c' to be equal to 'b'")));
         let mismatch = mismatches[2].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.blah"), expected: Some("{\"a\": \"c\", \"c\": \"b\"}".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.blah").unwrap(), expected: Some("{\"a\": \"c\", \"c\": \"b\"}".into()),
             actual: Some("{\"a\": \"b\"}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected attribute \'c\'=\'b\' but was missing")));
     }
@@ -427,10 +430,10 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <blah a="b" c="d"/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.blah"), expected: Some("{}".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.blah").unwrap(), expected: Some("{}".into()),
             actual: Some("{\"a\": \"b\", \"c\": \"d\"}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Did not expect any attributes but received {\"a\": \"b\", \"c\": \"d\"}")));
     }
@@ -444,7 +447,7 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <blah a="b" c="d"/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -457,10 +460,10 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo something="100" somethingElse="101"/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo"), expected: Some("{\"something\": \"100\"}".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo").unwrap(), expected: Some("{\"something\": \"100\"}".into()),
             actual: Some("{\"something\": \"100\", \"somethingElse\": \"101\"}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 1 attribute(s) but received 2 attribute(s)")));
     }
@@ -474,10 +477,10 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo something="100" somethingDifferent="100"/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo"), expected: Some("{\"something\": \"100\", \"somethingElse\": \"100\"}".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo").unwrap(), expected: Some("{\"something\": \"100\", \"somethingElse\": \"100\"}".into()),
             actual: Some("{\"something\": \"100\", \"somethingDifferent\": \"100\"}".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected attribute \'somethingElse\'=\'100\' but was missing")));
     }
@@ -491,15 +494,15 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo something="100" somethingElse="101"/>
         "#);
-        match_xml(&expected.clone().into(), &actual.clone().into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.@somethingElse"), expected: Some("100".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo['@somethingElse']").unwrap(), expected: Some("100".into()),
             actual: Some("101".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected \'100\' to be equal to \'101\'")));
 
         mismatches.clear();
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{
             "body" => {
                 "$.foo.*" => [ MatchingRule::Type ]
             }
@@ -516,10 +519,10 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><bar></bar></foo>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo"), expected: Some(vec![]),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo").unwrap(), expected: Some(vec![]),
             actual: Some("bar".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected an empty List but received [bar]")));
     }
@@ -533,7 +536,7 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><bar></bar></foo>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -546,7 +549,7 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><item1/><item2/></foo>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -559,10 +562,10 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo/>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo"), expected: Some("bar".into()),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo").unwrap(), expected: Some("bar".into()),
             actual: Some(vec![]), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected a List with 1 element(s) but received 0 element(s)")));
     }
@@ -576,10 +579,10 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><one/><two/><three/></foo>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo").unwrap(),
             expected: Some("one, two, three, four".into()),
             actual: Some("one, two, three".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected a List with 4 element(s) but received 3 element(s)")));
@@ -596,23 +599,23 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><one/><two/><four/></foo>
         "#);
-        match_xml(&expected.clone().into(), &actual.clone().into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.2"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo[2]").unwrap(),
             expected: Some("three".into()),
             actual: Some("four".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'three' to be equal to 'four'")));
 
         mismatches.clear();
-        match_xml(&expected.clone().into(), &actual.clone().into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{
             "body" => {
                 "$.foo" => [ MatchingRule::Type ]
             }
         });
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.2"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo[2]").unwrap(),
             expected: Some("three".into()),
             actual: Some("four".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'three' to be the same type as 'four'")));
@@ -627,15 +630,15 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><two/><one/></foo>
         "#);
-        match_xml(&expected.clone().into(), &actual.clone().into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(2));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.0"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo[0]").unwrap(),
             expected: Some("one".into()),
             actual: Some("two".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'one' to be equal to 'two'")));
         let mismatch = mismatches[1].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.1"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo[1]").unwrap(),
             expected: Some("two".into()),
             actual: Some("one".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'two' to be equal to 'one'")));
@@ -650,7 +653,7 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo>hello world</foo>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -663,7 +666,7 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo>hello<bar/>world</foo>
         "#);
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(be_empty());
     }
 
@@ -677,16 +680,16 @@ mod tests {
         let actual = s!(r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo>hello mars</foo>
         "#);
-        match_xml(&expected.clone().into(), &actual.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.#text"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo['#text']").unwrap(),
             expected: Some("hello world".into()),
             actual: Some("hello mars".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'hello world' to be equal to 'hello mars'")));
 
         mismatches.clear();
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchingrules!{
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchingrules!{
             "body" => {
                 "$.foo['#text']" => [ MatchingRule::Regex(s!("[a-z]+")) ]
             }
@@ -703,16 +706,16 @@ mod tests {
         let actual = r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo>hello<bar/>mars </foo>
         "#;
-        match_xml(&expected.clone().into(), &actual.clone().into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &MatchingRules::default());
         expect!(mismatches.iter()).to(have_count(1));
         let mismatch = mismatches[0].clone();
-        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: s!("$.foo.#text"),
+        expect!(&mismatch).to(be_equal_to(&Mismatch::BodyMismatch { path: DocPath::new("$.foo['#text']").unwrap(),
             expected: Some("helloworld".into()),
             actual: Some("hellomars".into()), mismatch: s!("")}));
         expect!(mismatch_message(&mismatch)).to(be_equal_to(s!("Expected 'helloworld' to be equal to 'hellomars'")));
 
         mismatches.clear();
-        match_xml(&expected.into(), &actual.into(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchingrules!{
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::AllowUnexpectedKeys, &mut mismatches, &matchingrules!{
             "body" => {
                 "$.foo['#text']" => [ MatchingRule::Regex(s!("[a-z]+")) ]
             }
@@ -720,6 +723,33 @@ mod tests {
         expect!(mismatches.iter()).to(be_empty());
     }
 
+    #[test]
+    fn match_xml_checks_the_number_of_child_elements_against_min_and_max() {
+        let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <foo><one id="1"/></foo>
+        "#;
+        let matchers = matchingrules!{
+            "body" => {
+                "$.foo" => [ MatchingRule::MinMaxType(2, 3) ],
+                "$.foo[*]" => [ MatchingRule::Type ]
+            }
+        };
+
+        let mut mismatches = vec![];
+        let actual = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <foo> <one id="2"/> <one id="3"/> </foo>
+        "#;
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.iter()).to(be_empty());
+
+        let actual = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <foo> <one id="2"/> </foo>
+        "#;
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchers);
+        expect!(mismatches.iter().map(mismatch_message).collect::<Vec<String>>()).to(
+            be_equal_to(vec![s!("Expected 'foo' to have at least 2 children")]));
+    }
+
     #[test]
     fn match_xml_with_a_matcher() {
         let mut mismatches = vec![];
@@ -729,7 +759,7 @@ mod tests {
         let actual = r#"<?xml version="1.0" encoding="UTF-8"?>
         <foo><one/><one/><one/></foo>
         "#;
-        match_xml(&expected.into(), &actual.into(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{
+        match_xml(expected.as_bytes(), actual.as_bytes(), DiffConfig::NoUnexpectedKeys, &mut mismatches, &matchingrules!{
             "body" => {
                 "$.foo" => [ MatchingRule::Type ]
             }
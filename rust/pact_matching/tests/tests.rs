@@ -2,7 +2,7 @@
 #[macro_use(expect)] extern crate expectest;
 #[allow(unused_imports)] #[macro_use] extern crate p_macro;
 extern crate env_logger;
-extern crate serde_json;
+#[macro_use] extern crate serde_json;
 
 mod spec_testcases;
 
@@ -497,6 +497,36 @@ fn test_load_pact_with_binary_body() {
 
   let pact_result = Pact::read_pact(&pact_file);
 
+  match pact_result {
+    Ok(ref pact) => {
+      let mut f = File::open(pact_file).unwrap();
+      let pact_json_from_file : serde_json::Value = serde_json::de::from_reader(&mut f).unwrap();
+      let pact_json = pact.to_json(PactSpecification::V2);
+
+      let interaction = pact.interactions.first().unwrap();
+      expect!(interaction.response.body.value()).to(be_equal_to(buffer));
+      let pact_interactions = pact_json.get("interactions").unwrap().as_array().unwrap();
+      let pact_interactions_from_file = pact_json_from_file.get("interactions").unwrap().as_array().unwrap();
+      expect!(pact_interactions[0]["response"]["body"].clone()).to(be_equal_to(json!({
+        "content": pact_interactions_from_file[0]["response"]["body"].clone(),
+        "contentType": "image/gif",
+        "encoded": "base64"
+      })));
+    },
+    Err(err) => panic!("Failed to load pact from '{:?}' - {}", pact_file, err)
+  }
+}
+
+#[test]
+fn test_load_pact_with_encoded_binary_body() {
+  let pact_file = fixture_path("test_pact_encoded_binary_body.json");
+
+  let mut buffer = Vec::new();
+  let gif_file = fixture_path("1px.gif");
+  File::open(gif_file).unwrap().read_to_end(&mut buffer).unwrap();
+
+  let pact_result = Pact::read_pact(&pact_file);
+
   match pact_result {
     Ok(ref pact) => {
       let mut f = File::open(pact_file).unwrap();
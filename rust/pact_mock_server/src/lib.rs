@@ -68,10 +68,10 @@ use std::ffi::CString;
 use std::str;
 use std::panic::catch_unwind;
 use pact_matching::models::{Pact, Interaction, Request, OptionalBody, PactSpecification};
-use pact_matching::models::parse_query_string;
+use pact_matching::models::{parse_query_string, parse_header, HeaderMap};
 use pact_matching::models::matchingrules::*;
 use pact_matching::models::generators::*;
-use pact_matching::Mismatch;
+use pact_matching::{DiffConfig, Mismatch};
 use std::collections::{BTreeMap, HashMap};
 use std::thread;
 use std::sync::Mutex;
@@ -148,6 +148,44 @@ fn mismatches_to_json(request: &Request, mismatches: &Vec<Mismatch>) -> serde_js
     })
 }
 
+/// Configuration for how a mock server matches the requests it receives
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchingConfig {
+    /// How unexpected keys in request bodies are handled, defaults to `NoUnexpectedKeys`
+    pub diff_config: DiffConfig,
+    /// Overrides of the diff config for individual interactions, keyed by interaction description
+    /// and provider states, as interactions in a pact can share the same description. The provider
+    /// states are keyed by their JSON form, which has the parameters in a fixed order.
+    pub interaction_diff_configs: HashMap<(String, Vec<String>), DiffConfig>
+}
+
+impl MatchingConfig {
+    /// Returns the diff config to use when matching requests against the interaction
+    pub fn diff_config_for(&self, interaction: &Interaction) -> DiffConfig {
+        self.interaction_diff_configs.get(&interaction_key(interaction)).cloned()
+            .unwrap_or(self.diff_config)
+    }
+
+    /// Overrides the diff config to use when matching requests against the interaction
+    pub fn set_diff_config_for(&mut self, interaction: &Interaction, diff_config: DiffConfig) {
+        self.interaction_diff_configs.insert(interaction_key(interaction), diff_config);
+    }
+}
+
+fn interaction_key(interaction: &Interaction) -> (String, Vec<String>) {
+    (interaction.description.clone(), interaction.provider_states.iter()
+        .map(|state| state.to_json().to_string()).collect())
+}
+
+impl Default for MatchingConfig {
+    fn default() -> MatchingConfig {
+        MatchingConfig {
+            diff_config: DiffConfig::NoUnexpectedKeys,
+            interaction_diff_configs: HashMap::new()
+        }
+    }
+}
+
 /// Struct to represent a mock server
 #[derive(Debug, Clone)]
 pub struct MockServer {
@@ -249,10 +287,11 @@ lazy_static! {
     static ref MOCK_SERVERS: Mutex<BTreeMap<String, Box<MockServer>>> = Mutex::new(BTreeMap::new());
 }
 
-fn match_request(req: &Request, interactions: &Vec<Interaction>) -> MatchResult {
+fn match_request(req: &Request, interactions: &Vec<Interaction>, config: &MatchingConfig) -> MatchResult {
     let match_results = interactions
         .into_iter()
-        .map(|i| (i.clone(), pact_matching::match_request(i.request.clone(), req.clone())))
+        .map(|i| (i.clone(), pact_matching::match_request_with_config(i.request.clone(), req.clone(),
+            config.diff_config_for(i))))
         .sorted_by(|i1, i2| {
             let list1 = i1.1.clone().into_iter().map(|m| m.mismatch_type()).unique().count();
             let list2 = i2.1.clone().into_iter().map(|m| m.mismatch_type()).unique().count();
@@ -307,9 +346,15 @@ fn extract_query_string(uri: &RequestUri) -> Option<HashMap<String, Vec<String>>
     }
 }
 
-fn extract_headers(headers: &Headers) -> Option<HashMap<String, String>> {
+fn extract_headers(headers: &Headers) -> Option<HeaderMap> {
     if headers.len() > 0 {
-        Some(headers.iter().map(|h| (s!(h.name()), h.value_string()) ).collect())
+        Some(headers.iter().fold(HeaderMap::new(), |mut map, h| {
+            let values = headers.get_raw(h.name()).unwrap_or(&[]).iter()
+                .flat_map(|line| parse_header(h.name(), &String::from_utf8_lossy(line)))
+                .collect();
+            map.add(h.name(), values);
+            map
+        }))
     } else {
         None
     }
@@ -382,12 +427,24 @@ fn record_result(id: &String, match_result: &MatchResult) {
 ///
 /// - If a mock server is not able to be started
 pub fn start_mock_server(id: String, pact: Pact, port: i32) -> Result<i32, String> {
+    start_mock_server_with_config(id, pact, port, MatchingConfig::default())
+}
+
+/// Starts a mock server like `start_mock_server`, using the config to match the requests it
+/// receives.
+///
+/// # Errors
+///
+/// An error with a message will be returned in the following conditions:
+///
+/// - If a mock server is not able to be started
+pub fn start_mock_server_with_config(id: String, pact: Pact, port: i32, config: MatchingConfig) -> Result<i32, String> {
     insert_new_mock_server(&id, &pact);
     let (out_tx, out_rx) = channel();
     let (in_tx, in_rx) = channel();
-    in_tx.send((id.clone(), pact, port)).unwrap();
+    in_tx.send((id.clone(), pact, port, config)).unwrap();
     thread::spawn(move || {
-        let (mock_server_id, pact, port) = in_rx.recv().unwrap();
+        let (mock_server_id, pact, port, config) = in_rx.recv().unwrap();
         let server = Server::http(format!("0.0.0.0:{}", port).as_str()).unwrap();
         let server_result = server.handle(move |mut req: hyper::server::Request, mut res: hyper::server::Response| {
             debug!("--> Hyper request to mock server {}", mock_server_id);
@@ -401,11 +458,14 @@ pub fn start_mock_server(id: String, pact: Pact, port: i32) -> Result<i32, Strin
                     debug!("Creating pact request from hyper request");
                     let req = hyper_request_to_pact_request(&mut req);
                     info!("Received request {:?}", req);
-                    let match_result = match_request(&req, &pact.interactions);
+                    let match_result = match_request(&req, &pact.interactions, &config);
                     record_result(&mock_server_id, &match_result);
                     match match_result {
                         MatchResult::RequestMatch(ref interaction) => {
-                            let response = pact_matching::generate_response(&interaction.response);
+                            // The provider state parameters are the values for any provider state generators
+                            let context = interaction.provider_states.iter()
+                                .flat_map(|state| state.params.clone()).collect();
+                            let response = pact_matching::generate_response_with_context(&interaction.response, &context);
                             info!("Request matched, sending response {:?}", response);
                             info!("     body: '{}'\n\n", interaction.response.body.str_value());
                             info!("     body: '{}'\n\n", interaction.response.body.str_value());
@@ -414,7 +474,7 @@ pub fn start_mock_server(id: String, pact: Pact, port: i32) -> Result<i32, Strin
                             match response.headers {
                                 Some(ref headers) => {
                                     for (k, v) in headers.clone() {
-                                        res.headers_mut().set_raw(k, vec![v.into_bytes()]);
+                                        res.headers_mut().set_raw(k, v.into_iter().map(|value| value.into_bytes()).collect());
                                     }
                                 },
                                 None => ()
@@ -2,7 +2,9 @@ use expectest::prelude::*;
 use super::*;
 use super::match_request;
 use pact_matching::models::{Interaction, Request, Response, OptionalBody};
-use pact_matching::Mismatch;
+use pact_matching::models::provider_states::ProviderState;
+use pact_matching::{DiffConfig, Mismatch};
+use pact_matching::path_exp::DocPath;
 
 #[test]
 fn match_request_returns_a_match_for_identical_requests() {
@@ -11,7 +13,7 @@ fn match_request_returns_a_match_for_identical_requests() {
     let interaction = Interaction { description: s!("test"), provider_states: vec![],
         request: request.clone(), response: response.clone() };
     let interactions = vec![interaction.clone()];
-    let result = match_request(&request, &interactions);
+    let result = match_request(&request, &interactions, &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestMatch(interaction)));
 }
 
@@ -19,7 +21,7 @@ fn match_request_returns_a_match_for_identical_requests() {
 fn match_request_returns_a_not_found_for_no_interactions() {
     let request = Request::default_request();
     let interactions = vec![];
-    let result = match_request(&request, &interactions);
+    let result = match_request(&request, &interactions, &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestNotFound(request)));
 }
 
@@ -32,7 +34,7 @@ fn match_request_returns_a_match_for_multiple_identical_requests() {
     let interactions = vec![interaction.clone(),
         Interaction { description: s!("test2"), provider_states: vec![],
             request: request.clone(), response: response.clone() }];
-    let result = match_request(&request, &interactions);
+    let result = match_request(&request, &interactions, &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestMatch(interaction)));
 }
 
@@ -46,7 +48,7 @@ fn match_request_returns_a_match_for_multiple_requests() {
     let interactions = vec![interaction.clone(),
         Interaction { description: s!("test2"), provider_states: vec![],
             request: request2.clone(), response: response.clone() }];
-    let result = match_request(&request, &interactions);
+    let result = match_request(&request, &interactions, &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestMatch(interaction)));
 }
 
@@ -58,7 +60,7 @@ fn match_request_returns_a_mismatch_for_incorrect_request() {
         .. Request::default_request() };
     let interactions = vec![Interaction { description: s!("test"), provider_states: vec![],
         request: expected_request, response: response.clone() }];
-    let result = match_request(&request, &interactions);
+    let result = match_request(&request, &interactions, &MatchingConfig::default());
     expect!(result.match_key()).to(be_equal_to(s!("Request-Mismatch")));
 }
 
@@ -70,7 +72,7 @@ fn match_request_returns_request_not_found_if_method_or_path_do_not_match() {
         .. Request::default_request() };
     let interactions = vec![Interaction { description: s!("test"), provider_states: vec![],
         request: expected_request, response: response.clone() }];
-    let result = match_request(&request, &interactions);
+    let result = match_request(&request, &interactions, &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestNotFound(request)));
 }
 
@@ -91,16 +93,16 @@ fn match_request_returns_the_most_appropriate_mismatch_for_multiple_requests() {
     let interaction2 = Interaction { description: s!("test2"), provider_states: vec![],
             request: request2.clone(), response: response.clone() };
     let interactions = vec![interaction.clone(), interaction2.clone()];
-    let result = match_request(&request3, &interactions);
+    let result = match_request(&request3, &interactions, &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestMismatch(interaction2,
-        vec![Mismatch::BodyMismatch { path: s!("/"), expected: Some("This is a body".into()), actual: None,
+        vec![Mismatch::BodyMismatch { path: DocPath::root(), expected: Some("This is a body".into()), actual: None,
         mismatch: s!("Expected body \'This is a body\' but was missing") }])));
 }
 
 #[test]
 fn match_request_supports_v2_matchers() {
     let request = Request { method: s!("GET"), path: s!("/"),
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }), body: OptionalBody::Present(
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()), body: OptionalBody::Present(
             r#"
             {
                 "a": 100,
@@ -110,7 +112,7 @@ fn match_request_supports_v2_matchers() {
         ), .. Request::default_request() };
     let response = Response::default_response();
     let expected_request = Request { method: s!("GET"), path: s!("/"),
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/json") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()),
         body: OptionalBody::Present(
             r#"
             {
@@ -127,21 +129,21 @@ fn match_request_supports_v2_matchers() {
     };
     let interaction = Interaction { description: s!("test"), provider_states: vec![],
         request: expected_request, response: response.clone() };
-    let result = match_request(&request, &vec![interaction.clone()]);
+    let result = match_request(&request, &vec![interaction.clone()], &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestMatch(interaction)));
 }
 
 #[test]
 fn match_request_supports_v2_matchers_with_xml() {
     let request = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/xml") }), body: OptionalBody::Present(
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/xml")] }.into()), body: OptionalBody::Present(
             r#"<?xml version="1.0" encoding="UTF-8"?>
             <foo>hello<bar/>world</foo>
             "#.into()
         ), .. Request::default_request() };
     let response = Response::default_response();
     let expected_request = Request { method: s!("GET"), path: s!("/"), query: None,
-        headers: Some(hashmap!{ s!("Content-Type") => s!("application/xml") }),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/xml")] }.into()),
         body: OptionalBody::Present(
             r#"<?xml version="1.0" encoding="UTF-8"?>
             <foo>hello<bar/>mars </foo>
@@ -155,6 +157,51 @@ fn match_request_supports_v2_matchers_with_xml() {
     };
     let interaction = Interaction { description: s!("test"), provider_states: vec![],
         request: expected_request, response: response.clone() };
-    let result = match_request(&request, &vec![interaction.clone()]);
+    let result = match_request(&request, &vec![interaction.clone()], &MatchingConfig::default());
     expect!(result).to(be_equal_to(MatchResult::RequestMatch(interaction)));
 }
+
+#[test]
+fn match_request_uses_the_diff_config_for_the_interaction() {
+    let expected_request = Request { method: s!("POST"), path: s!("/"),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()),
+        body: OptionalBody::Present(r#"{"a": 1}"#.into()), .. Request::default_request() };
+    let request = Request { body: OptionalBody::Present(r#"{"a": 1, "b": 2}"#.into()), .. expected_request.clone() };
+    let interaction = Interaction { description: s!("test"), provider_states: vec![],
+        request: expected_request, response: Response::default_response() };
+    let interactions = vec![interaction.clone()];
+
+    let result = match_request(&request, &interactions, &MatchingConfig::default());
+    expect!(result.match_key()).to(be_equal_to(s!("Request-Mismatch")));
+
+    let lenient = MatchingConfig { diff_config: DiffConfig::AllowUnexpectedKeys, .. MatchingConfig::default() };
+    let result = match_request(&request, &interactions, &lenient);
+    expect!(result).to(be_equal_to(MatchResult::RequestMatch(interaction.clone())));
+
+    let mut strict_interaction = lenient.clone();
+    strict_interaction.set_diff_config_for(&interaction, DiffConfig::NoUnexpectedKeys);
+    let result = match_request(&request, &interactions, &strict_interaction);
+    expect!(result.match_key()).to(be_equal_to(s!("Request-Mismatch")));
+
+    // An interaction with the same description but a different provider state is not overridden
+    let other_state = Interaction { provider_states: vec![ProviderState::default(&s!("other state"))],
+        .. interaction.clone() };
+    let result = match_request(&request, &vec![other_state.clone()], &strict_interaction);
+    expect!(result).to(be_equal_to(MatchResult::RequestMatch(other_state)));
+}
+
+#[test]
+fn diff_config_overrides_do_not_depend_on_the_order_of_the_provider_state_params() {
+    let state = |params: &[(&str, i64)]| ProviderState { name: s!("orders exist"),
+        params: params.iter().map(|&(k, v)| (s!(k), json!(v))).collect() };
+    let params = [("a", 1), ("b", 2), ("c", 3), ("d", 4), ("e", 5), ("f", 6), ("g", 7), ("h", 8)];
+    let mut reversed = params;
+    reversed.reverse();
+    let interaction = Interaction { description: s!("test"), provider_states: vec![state(&params)],
+        .. Interaction::default() };
+    let same_interaction = Interaction { provider_states: vec![state(&reversed)], .. interaction.clone() };
+
+    let mut config = MatchingConfig::default();
+    config.set_diff_config_for(&interaction, DiffConfig::AllowUnexpectedKeys);
+    expect!(config.diff_config_for(&same_interaction)).to(be_equal_to(DiffConfig::AllowUnexpectedKeys));
+}
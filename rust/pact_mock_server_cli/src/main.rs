@@ -191,6 +191,9 @@ fn handle_command_args() -> Result<(), i32> {
                   .takes_value(true)
                   .use_delimiter(false)
                   .help("the server key to use to authenticate shutdown requests (defaults to a random generated one)"))
+                .arg(Arg::with_name("allow-unexpected-keys")
+                  .long("allow-unexpected-keys")
+                  .help("allow keys in request bodies that are not in the pact (by default they are mismatches)"))
                 .setting(AppSettings::ColoredHelp))
         .subcommand(SubCommand::with_name("list")
                 .about("Lists all the running mock servers")
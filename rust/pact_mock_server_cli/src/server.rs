@@ -1,10 +1,12 @@
 use hyper::server::{Handler, Server, Request, Response};
 use pact_matching::models::Pact;
+use pact_matching::DiffConfig;
 use pact_mock_server::{
-    start_mock_server,
+    start_mock_server_with_config,
     iterate_mock_servers,
     lookup_mock_server,
     shutdown_mock_server,
+    MatchingConfig,
     MockServer
 };
 use uuid::Uuid;
@@ -50,14 +52,14 @@ fn get_next_port(base_port: Option<u16>) -> u16 {
   }
 }
 
-fn start_provider(context: &mut WebmachineContext, base_port: Option<u16>) -> Result<bool, u16> {
+fn start_provider(context: &mut WebmachineContext, base_port: Option<u16>, config: MatchingConfig) -> Result<bool, u16> {
     match context.request.body {
         Some(ref body) if !body.is_empty() => {
             match serde_json::from_str(body) {
                 Ok(ref json) => {
                     let pact = Pact::from_json(&context.request.request_path, json);
                     let mock_server_id = Uuid::new_v4().simple().to_string();
-                    match start_mock_server(mock_server_id.clone(), pact, get_next_port(base_port) as i32) {
+                    match start_mock_server_with_config(mock_server_id.clone(), pact, get_next_port(base_port) as i32, config) {
                         Ok(mock_server) => {
                             let mock_server_json = json!({
                                 s!("id") : json!(mock_server_id.clone()),
@@ -117,7 +119,7 @@ pub fn verify_mock_server_request(context: &mut WebmachineContext, output_path:
     }
 }
 
-fn main_resource(base_port: Arc<Option<u16>>) -> WebmachineResource {
+fn main_resource(base_port: Arc<Option<u16>>, config: Arc<MatchingConfig>) -> WebmachineResource {
     WebmachineResource {
         allowed_methods: vec![s!("OPTIONS"), s!("GET"), s!("HEAD"), s!("POST")],
         resource_exists: Box::new(|context| context.request.request_path == "/"),
@@ -130,7 +132,7 @@ fn main_resource(base_port: Arc<Option<u16>>) -> WebmachineResource {
             let json_response = json!({ s!("mockServers") : json!(mock_servers) });
             Some(json_response.to_string())
         }),
-        process_post: Box::new(move |context| start_provider(context, base_port.deref().clone())),
+        process_post: Box::new(move |context| start_provider(context, base_port.deref().clone(), config.deref().clone())),
         .. WebmachineResource::default()
     }
 }
@@ -243,15 +245,18 @@ fn mock_server_resource(output_path: Arc<Option<String>>) -> WebmachineResource
 struct ServerHandler {
   output_path: Arc<Option<String>>,
   base_port: Arc<Option<u16>>,
-  server_key: Arc<String>
+  server_key: Arc<String>,
+  config: Arc<MatchingConfig>
 }
 
 impl ServerHandler {
-    fn new(output_path: Option<String>, base_port: Option<u16>, server_key: String) -> ServerHandler {
+    fn new(output_path: Option<String>, base_port: Option<u16>, server_key: String,
+        config: MatchingConfig) -> ServerHandler {
         ServerHandler {
             output_path: Arc::new(output_path),
             base_port: Arc::new(base_port),
-            server_key: Arc::new(server_key)
+            server_key: Arc::new(server_key),
+            config: Arc::new(config)
         }
     }
 }
@@ -260,7 +265,7 @@ impl Handler for ServerHandler {
   fn handle(&self, req: Request, res: Response) {
     let dispatcher = WebmachineDispatcher::new(
       btreemap! {
-            s!("/") => Arc::new(main_resource(self.base_port.clone())),
+            s!("/") => Arc::new(main_resource(self.base_port.clone(), self.config.clone())),
             s!("/mockserver") => Arc::new(mock_server_resource(self.output_path.clone())),
             s!("/shutdown") => Arc::new(shutdown_resource(self.server_key.clone()))
         }
@@ -277,10 +282,14 @@ pub fn start_server(port: u16, matches: &ArgMatches) -> Result<(), i32> {
     let base_port = matches.value_of("base-port").map(|s| s.parse::<u16>().unwrap_or(0));
     let server_key = matches.value_of("server-key").map(|s| s.to_owned())
       .unwrap_or(rand::thread_rng().gen_ascii_chars().take(16).collect::<String>());
+    let mut config = MatchingConfig::default();
+    if matches.is_present("allow-unexpected-keys") {
+        config.diff_config = DiffConfig::AllowUnexpectedKeys;
+    }
     match Server::http(format!("0.0.0.0:{}", port).as_str()) {
         Ok(mut server) => {
             server.keep_alive(None);
-            match server.handle(ServerHandler::new(output_path, base_port, server_key.clone())) {
+            match server.handle(ServerHandler::new(output_path, base_port, server_key.clone(), config)) {
                 Ok(listener) => {
                     info!("Master server started on port {}", listener.socket.port());
                     info!("Server key: '{}'", server_key);
@@ -24,20 +24,27 @@ extern crate env_logger;
 
 mod provider_client;
 mod pact_broker;
+pub mod report;
 
 use std::path::Path;
 use std::error::Error;
 use std::io;
 use std::fs;
+use std::fmt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::cmp;
 use pact_matching::*;
 use pact_matching::models::*;
 use pact_matching::models::provider_states::*;
+use pact_matching::path_exp::DocPath;
 use ansi_term::*;
 use ansi_term::Colour::*;
-use std::collections::HashMap;
-use provider_client::{make_provider_request, make_state_change_request};
+use provider_client::{make_provider_request, make_state_change_request, call_request_handler};
 use regex::Regex;
 use serde_json::Value;
+use report::{VerificationReport, PactResult, InteractionResult};
 
 /// Source for loading pacts
 #[derive(Debug, Clone)]
@@ -52,8 +59,41 @@ pub enum PactSource {
     BrokerUrl(String, String)
 }
 
+/// Filter that is applied to each request before it is sent to the provider. It can be used to
+/// add or replace headers, like authentication tokens that can not be stored in the pact files.
+pub type RequestFilter = Arc<Fn(&mut Request) + Send + Sync>;
+
+/// Handler for the requests of a provider that runs in the same process as the verifier. When
+/// one is set on the `ProviderInfo`, the interactions are verified by calling it directly instead of
+/// making HTTP requests.
+pub trait RequestHandler: Send + Sync {
+    /// Handles the request, returning the response from the provider
+    fn handle(&self, request: Request) -> Response;
+}
+
+impl<F> RequestHandler for F where F: Fn(Request) -> Response + Send + Sync {
+    fn handle(&self, request: Request) -> Response {
+        self(request)
+    }
+}
+
+/// Handler for provider state changes of a provider that runs in the same process as the
+/// verifier. When one is set on the `ProviderInfo`, it is used instead of the state change URL.
+pub trait ProviderStateHandler: Send + Sync {
+    /// Sets up (if `setup` is true) or tears down the provider state. Any values returned from the
+    /// setup are used by provider state generators when generating the request. Any error will fail
+    /// the verification of the interaction.
+    fn change_state(&self, provider_state: &ProviderState, setup: bool) -> Result<HashMap<String, Value>, String>;
+}
+
+impl<F> ProviderStateHandler for F where F: Fn(&ProviderState, bool) -> Result<HashMap<String, Value>, String> + Send + Sync {
+    fn change_state(&self, provider_state: &ProviderState, setup: bool) -> Result<HashMap<String, Value>, String> {
+        self(provider_state, setup)
+    }
+}
+
 /// Information about the Provider to verify
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ProviderInfo {
     /// Provider Name
     pub name: String,
@@ -70,7 +110,13 @@ pub struct ProviderInfo {
     /// If teardown state change requests should be made (default is false)
     pub state_change_teardown: bool,
     /// If state change request data should be sent in the body (true) or as query parameters (false)
-    pub state_change_body: bool
+    pub state_change_body: bool,
+    /// Filter to apply to each request before it is sent to the provider
+    pub request_filter: Option<RequestFilter>,
+    /// Handler to call with the requests instead of sending them to the provider over HTTP
+    pub request_handler: Option<Arc<RequestHandler>>,
+    /// Handler to call for provider state changes instead of the state change URL
+    pub state_handler: Option<Arc<ProviderStateHandler>>
 }
 
 impl ProviderInfo {
@@ -84,11 +130,40 @@ impl ProviderInfo {
             path: s!("/"),
             state_change_url: None,
             state_change_teardown: false,
-            state_change_body: true
+            state_change_body: true,
+            request_filter: None,
+            request_handler: None,
+            state_handler: None
         }
     }
 }
 
+impl fmt::Debug for ProviderInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProviderInfo")
+            .field("name", &self.name)
+            .field("protocol", &self.protocol)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("path", &self.path)
+            .field("state_change_url", &self.state_change_url)
+            .field("state_change_teardown", &self.state_change_teardown)
+            .field("state_change_body", &self.state_change_body)
+            .field("request_filter", &self.request_filter.as_ref().map(|_| "<filter>"))
+            .field("request_handler", &self.request_handler.as_ref().map(|_| "<handler>"))
+            .field("state_handler", &self.state_handler.as_ref().map(|_| "<handler>"))
+            .finish()
+    }
+}
+
+/// Sets the header on the request, replacing any existing header with the same name. Header names
+/// are compared case-insensitively.
+pub fn set_request_header(request: &mut Request, name: &str, value: &str) {
+    let mut headers = request.headers.clone().unwrap_or_default();
+    headers.insert(name, vec![s!(value)]);
+    request.headers = Some(headers);
+}
+
 /// Result of performing a match
 #[derive(Debug, Clone)]
 pub enum MismatchResult {
@@ -98,11 +173,18 @@ pub enum MismatchResult {
     Error(String)
 }
 
-fn verify_response_from_provider(provider: &ProviderInfo, interaction: &Interaction) -> Result<(), MismatchResult> {
+fn verify_response_from_provider(provider: &ProviderInfo, interaction: &Interaction,
+  options: &VerificationOptions, context: &HashMap<String, Value>) -> Result<(), MismatchResult> {
   let ref expected_response = interaction.response;
-  match make_provider_request(provider, &pact_matching::generate_request(&interaction.request)) {
+  let request = pact_matching::generate_request_with_context(&interaction.request, context);
+  let response = match provider.request_handler {
+      Some(ref handler) => Ok(call_request_handler(provider, handler.as_ref(), &request)),
+      None => make_provider_request(provider, &request).map_err(|err| s!(err.description()))
+  };
+  match response {
       Ok(ref actual_response) => {
-          let mismatches = match_response(expected_response.clone(), actual_response.clone());
+          let mismatches = match_response_with_config(expected_response.clone(), actual_response.clone(),
+            options.diff_config);
           if mismatches.is_empty() {
               Ok(())
           } else {
@@ -110,17 +192,16 @@ fn verify_response_from_provider(provider: &ProviderInfo, interaction: &Interact
           }
       },
       Err(err) => {
-          Err(MismatchResult::Error(s!(err.description())))
+          Err(MismatchResult::Error(err))
       }
   }
 }
 
-fn execute_state_change(provider_state: &ProviderState, provider: &ProviderInfo, setup: bool) -> Result<(), MismatchResult> {
-    if setup {
-        println!("  Given {}", Style::new().bold().paint(provider_state.name.clone()));
-    }
-    let result = match provider.state_change_url {
-        Some(_) => {
+fn execute_state_change(provider_state: &ProviderState, provider: &ProviderInfo, setup: bool)
+    -> Result<HashMap<String, Value>, MismatchResult> {
+    let result = match (provider.state_handler.as_ref(), provider.state_change_url.as_ref()) {
+        (Some(handler), _) => handler.change_state(provider_state, setup).map_err(MismatchResult::Error),
+        (None, Some(_)) => {
             let mut state_change_request = Request { method: s!("POST"), .. Request::default_request() };
             if provider.state_change_body {
               let mut json_body = json!({
@@ -138,7 +219,7 @@ fn execute_state_change(provider_state: &ProviderState, provider: &ProviderInfo,
                 }
               }
               state_change_request.body = OptionalBody::Present(json_body.to_string().into());
-              state_change_request.headers = Some(hashmap!{ s!("Content-Type") => s!("application/json") });
+              state_change_request.headers = Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into());
             } else {
               let mut query = hashmap!{ s!("state") => vec![provider_state.name.clone()] };
               if setup {
@@ -155,38 +236,133 @@ fn execute_state_change(provider_state: &ProviderState, provider: &ProviderInfo,
               state_change_request.query = Some(query);
             }
             match make_state_change_request(provider, &state_change_request) {
-                Ok(_) => Ok(()),
+                Ok(values) => Ok(values),
                 Err(err) => Err(MismatchResult::Error(err))
             }
         },
-        None => {
-            if setup {
-                println!("    {}", Yellow.paint("WARNING: State Change ignored as there is no state change URL"));
-            }
-            Ok(())
-        }
+        (None, None) => Ok(HashMap::new())
     };
 
     debug!("State Change: \"{:?}\" -> {:?}", provider_state, result);
     result
 }
 
-fn verify_interaction(provider: &ProviderInfo, interaction: &Interaction) -> Result<(), MismatchResult> {
+fn verify_interaction(provider: &ProviderInfo, interaction: &Interaction,
+    options: &VerificationOptions) -> Result<(), MismatchResult> {
+    let mut context = HashMap::new();
     for state in interaction.provider_states.clone() {
-      execute_state_change(&state, provider, true)?
+      context.extend(state.params.clone());
+      context.extend(execute_state_change(&state, provider, true)?);
     }
 
-    let result = verify_response_from_provider(provider, interaction);
+    let result = verify_response_from_provider(provider, interaction, options, &context);
 
     if provider.state_change_teardown {
       for state in interaction.provider_states.clone() {
-        execute_state_change(&state, provider, false)?
+        execute_state_change(&state, provider, false)?;
       }
     }
 
     result
 }
 
+fn display_provider_states(provider: &ProviderInfo, interaction: &Interaction) {
+    for state in &interaction.provider_states {
+        println!("  Given {}", Style::new().bold().paint(state.name.clone()));
+        if provider.state_handler.is_none() && provider.state_change_url.is_none() {
+            println!("    {}", Yellow.paint("WARNING: State Change ignored as there is no state change URL"));
+        }
+    }
+}
+
+/// Groups the interactions so that interactions that share a provider state are in the same group.
+/// The groups, and the interactions within each group, keep the order of the interactions.
+fn group_by_provider_state(interactions: &[Interaction]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut state_groups: HashMap<String, usize> = HashMap::new();
+    for (index, interaction) in interactions.iter().enumerate() {
+        let mut matched: Vec<usize> = interaction.provider_states.iter()
+            .filter_map(|state| state_groups.get(&state.name).cloned())
+            .collect();
+        matched.sort();
+        matched.dedup();
+        let group = match matched.split_first() {
+            Some((&first, others)) => {
+                for &other in others {
+                    let merged = groups[other].drain(..).collect::<Vec<usize>>();
+                    groups[first].extend(merged);
+                    for group in state_groups.values_mut() {
+                        if *group == other {
+                            *group = first;
+                        }
+                    }
+                }
+                first
+            },
+            None => {
+                groups.push(vec![]);
+                groups.len() - 1
+            }
+        };
+        groups[group].push(index);
+        for state in &interaction.provider_states {
+            state_groups.insert(state.name.clone(), group);
+        }
+    }
+    groups.into_iter()
+        .filter(|group| !group.is_empty())
+        .map(|mut group| {
+            group.sort();
+            group
+        })
+        .collect()
+}
+
+/// Verifies the interactions using a pool of threads, as many as the concurrency of the options.
+/// Interactions that share a provider state are verified one after the other on the same thread.
+/// The results are returned in the same order as the interactions.
+fn verify_interactions_in_parallel(provider_info: &ProviderInfo, interactions: Vec<Interaction>,
+    options: &VerificationOptions) -> Vec<Result<(), MismatchResult>> {
+    let groups = group_by_provider_state(&interactions);
+    let workers = cmp::min(options.concurrency, groups.len());
+    debug!("Verifying {} interactions in {} groups with {} threads", interactions.len(), groups.len(), workers);
+
+    let queue = Arc::new(Mutex::new(groups.into_iter()));
+    let interactions = Arc::new(interactions);
+    let (sender, receiver) = mpsc::channel();
+    let handles: Vec<thread::JoinHandle<()>> = (0..workers).map(|_| {
+        let queue = queue.clone();
+        let interactions = interactions.clone();
+        let provider_info = provider_info.clone();
+        let options = options.clone();
+        let sender = sender.clone();
+        thread::spawn(move || loop {
+            let group = queue.lock().unwrap().next();
+            match group {
+                Some(group) => for index in group {
+                    let result = verify_interaction(&provider_info, &interactions[index], &options);
+                    sender.send((index, result)).unwrap();
+                },
+                None => break
+            }
+        })
+    }).collect();
+    drop(sender);
+
+    let mut results: Vec<Option<Result<(), MismatchResult>>> = vec![None; interactions.len()];
+    for (index, result) in receiver {
+        results[index] = Some(result);
+    }
+    for handle in handles {
+        if handle.join().is_err() {
+            error!("A verification thread failed");
+        }
+    }
+    results.into_iter()
+        .map(|result| result.unwrap_or_else(|| Err(MismatchResult::Error(s!("Verification of the interaction did not complete")))))
+        .collect()
+}
+
 fn display_result(status: u16, status_result: ANSIGenericString<str>,
     header_results: Option<Vec<(String, String, ANSIGenericString<str>)>>,
     body_result: ANSIGenericString<str>) {
@@ -221,7 +397,7 @@ fn walkdir(dir: &Path) -> io::Result<Vec<io::Result<Pact>>> {
     Ok(pacts)
 }
 
-fn display_body_mismatch(expected: &Response, actual: &Response, path: &String) {
+fn display_body_mismatch(expected: &Response, actual: &Response, path: &DocPath) {
     match expected.content_type_enum() {
         DetectedContentType::Json => println!("{}", pact_matching::json::display_diff(&expected.body.str_value().to_string(),
             &actual.body.str_value().to_string(), path)),
@@ -324,9 +500,31 @@ fn filter_consumers(consumers: &Vec<String>, res: &Result<Pact, String>) -> bool
     consumers.is_empty() || res.is_err() || consumers.contains(&res.clone().unwrap().consumer.name)
 }
 
-/// Verify the provider with the given pact sources
+/// Options used when running the verification
+#[derive(Debug, Clone)]
+pub struct VerificationOptions {
+    /// Number of interactions to verify in parallel. Interactions that share a provider state are
+    /// always verified one after the other. Defaults to 1, which verifies all interactions sequentially.
+    pub concurrency: usize,
+    /// How keys in response bodies that are not in the pact are handled. Defaults to allowing them.
+    pub diff_config: DiffConfig
+}
+
+impl VerificationOptions {
+    /// Create default verification options
+    pub fn default() -> VerificationOptions {
+        VerificationOptions {
+            concurrency: 1,
+            diff_config: DiffConfig::AllowUnexpectedKeys
+        }
+    }
+}
+
+/// Verify the provider with the given pact sources. The results are displayed on the console, and
+/// also returned as a `VerificationReport`. When the concurrency of the options is more than one,
+/// all the interactions are verified before any of the results are displayed.
 pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, filter: &FilterInfo,
-    consumers: &Vec<String>) -> bool {
+    consumers: &Vec<String>, options: &VerificationOptions) -> VerificationReport {
     let pacts = source.iter().flat_map(|s| {
         match s {
             &PactSource::File(ref file) => vec![Pact::read_pact(Path::new(&file))
@@ -356,25 +554,52 @@ pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, fi
     .filter(|res| filter_consumers(consumers, res))
     .collect::<Vec<Result<Pact, String>>>();
 
-    let mut verify_provider_result = true;
+    let mut parallel_results: HashMap<usize, Vec<(Interaction, Result<(), MismatchResult>)>> = HashMap::new();
+    if options.concurrency > 1 {
+        let interactions: Vec<(usize, Interaction)> = pacts.iter().enumerate()
+            .flat_map(|(index, pact)| match *pact {
+                Ok(ref pact) => pact.interactions.iter()
+                    .filter(|interaction| filter_interaction(interaction, filter))
+                    .map(|interaction| (index, interaction.clone()))
+                    .collect(),
+                Err(_) => vec![]
+            })
+            .collect();
+        let results = verify_interactions_in_parallel(provider_info,
+            interactions.iter().map(|(_, interaction)| interaction.clone()).collect(), options);
+        for ((index, interaction), result) in interactions.into_iter().zip(results) {
+            parallel_results.entry(index).or_default().push((interaction, result));
+        }
+    }
+
+    let mut pact_results = vec![];
     let mut all_errors: Vec<(String, MismatchResult)> = vec![];
-    for pact in pacts {
+    for (index, pact) in pacts.into_iter().enumerate() {
         match pact {
             Ok(ref pact) => {
                 println!("\nVerifying a pact between {} and {}",
                     Style::new().bold().paint(pact.consumer.name.clone()),
                     Style::new().bold().paint(pact.provider.name.clone()));
 
+                let mut interaction_results = vec![];
                 if pact.interactions.is_empty() {
                     println!("         {}", Yellow.paint("WARNING: Pact file has no interactions"));
                 } else {
-                    let results: HashMap<Interaction, Result<(), MismatchResult>> = pact.interactions.iter()
-                    .filter(|interaction| filter_interaction(interaction, filter))
-                    .map(|interaction| {
-                        (interaction.clone(), verify_interaction(provider_info, interaction))
-                    }).collect();
-
-                    for (interaction, result) in results.clone() {
+                    let parallel = parallel_results.remove(&index);
+                    let verified_in_parallel = parallel.is_some();
+                    let results: Box<Iterator<Item = (Interaction, Result<(), MismatchResult>)>> = match parallel {
+                        Some(results) => Box::new(results.into_iter()),
+                        // Each interaction is verified as it is reported, so its provider states are
+                        // displayed before the state change requests are made
+                        None => Box::new(pact.interactions.iter()
+                            .filter(|interaction| filter_interaction(interaction, filter))
+                            .map(|interaction| {
+                                display_provider_states(provider_info, interaction);
+                                (interaction.clone(), verify_interaction(provider_info, interaction, options))
+                            }))
+                    };
+
+                    for (interaction, result) in results {
                         let mut description = format!("Verifying a pact between {} and {}",
                             pact.consumer.name.clone(), pact.provider.name.clone());
                         if let Some((first, elements)) = interaction.provider_states.split_first() {
@@ -385,37 +610,37 @@ pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, fi
                         }
                         description.push_str(" - ");
                         description.push_str(&interaction.description);
+                        if verified_in_parallel {
+                            display_provider_states(provider_info, &interaction);
+                        }
                         println!("  {}", interaction.description);
                         match result {
                             Ok(()) => {
                                 display_result(interaction.response.status, Green.paint("OK"),
-                                    interaction.response.headers.map(|h| h.iter().map(|(k, v)| {
-                                        (k.clone(), v.clone(), Green.paint("OK"))
+                                    interaction.response.headers.clone().map(|h| h.iter().map(|(k, v)| {
+                                        (k.clone(), v.join(", "), Green.paint("OK"))
                                     }).collect()), Green.paint("OK"))
                             },
                             Err(ref err) => match err {
                                 &MismatchResult::Error(ref err_des) => {
                                     println!("      {}", Red.paint(format!("Request Failed - {}", err_des)));
                                     all_errors.push((description, MismatchResult::Error(err_des.clone())));
-                                    verify_provider_result = false;
                                 },
                                 &MismatchResult::Mismatches(ref mismatches, ref expected_response, ref actual_response) => {
                                     description.push_str(" returns a response which ");
                                     let status_result = if mismatches.iter().any(|m| m.mismatch_type() == s!("StatusMismatch")) {
-                                        verify_provider_result = false;
                                         Red.paint("FAILED")
                                     } else {
                                         Green.paint("OK")
                                     };
                                     let header_results = match interaction.response.headers {
                                         Some(ref h) => Some(h.iter().map(|(k, v)| {
-                                            (k.clone(), v.clone(), if mismatches.iter().any(|m| {
+                                            (k.clone(), v.join(", "), if mismatches.iter().any(|m| {
                                                 match m {
                                                     &Mismatch::HeaderMismatch{ ref key, .. } => k == key,
                                                     _ => false
                                                 }
                                             }) {
-                                                verify_provider_result = false;
                                                 Red.paint("FAILED")
                                             } else {
                                                 Green.paint("OK")
@@ -425,7 +650,6 @@ pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, fi
                                     };
                                     let body_result = if mismatches.iter().any(|m| m.mismatch_type() == s!("BodyMismatch") ||
                                         m.mismatch_type() == s!("BodyTypeMismatch")) {
-                                        verify_provider_result = false;
                                         Red.paint("FAILED")
                                     } else {
                                         Green.paint("OK")
@@ -442,14 +666,28 @@ pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, fi
                                 }
                             }
                         }
+                        interaction_results.push(InteractionResult {
+                            description: interaction.description.clone(),
+                            provider_states: interaction.provider_states.iter().map(|state| state.name.clone()).collect(),
+                            result
+                        });
                     }
                     println!();
                 }
+                pact_results.push(PactResult {
+                    consumer: Some(pact.consumer.name.clone()),
+                    provider: Some(pact.provider.name.clone()),
+                    result: Ok(interaction_results)
+                });
             },
             Err(err) => {
                 error!("Failed to load pact - {}", Red.paint(format!("{}", err)));
-                verify_provider_result = false;
                 all_errors.push((s!("Failed to load pact"), MismatchResult::Error(format!("{}", err))));
+                pact_results.push(PactResult {
+                    consumer: None,
+                    provider: None,
+                    result: Err(err)
+                });
             }
         }
     };
@@ -476,15 +714,21 @@ pub fn verify_provider(provider_info: &ProviderInfo, source: Vec<PactSource>, fi
         println!("\nThere were {} pact failures\n", all_errors.len());
     }
 
-    verify_provider_result
+    VerificationReport { pacts: pact_results }
 }
 
 #[cfg(test)]
 mod tests {
   use expectest::prelude::*;
-  use super::{FilterInfo, filter_interaction, filter_consumers, execute_state_change, ProviderInfo};
+  use super::{FilterInfo, filter_interaction, filter_consumers, execute_state_change, ProviderInfo,
+    verify_response_from_provider, set_request_header, verify_interaction, group_by_provider_state,
+    verify_interactions_in_parallel, MismatchResult, VerificationOptions};
+  use std::sync::{Arc, Mutex};
+  use pact_matching::DiffConfig;
   use pact_matching::models::*;
   use pact_matching::models::provider_states::*;
+  use pact_matching::models::generators::*;
+  use std::str::FromStr;
   use pact_consumer::prelude::*;
   use env_logger::*;
 
@@ -650,4 +894,239 @@ mod tests {
     let result = execute_state_change(&provider_state, &provider, true);
     expect!(result.clone()).to(be_ok());
   }
+
+  #[test]
+  fn set_request_header_replaces_headers_ignoring_case() {
+    let mut request = Request { headers: Some(hashmap!{
+      s!("authorization") => vec![s!("Bearer old")],
+      s!("Accept") => vec![s!("application/json")]
+    }.into()), .. Request::default_request() };
+    set_request_header(&mut request, "Authorization", "Bearer new");
+    set_request_header(&mut request, "X-Trace", "1234");
+    expect!(request.headers).to(be_some().value(hashmap!{
+      s!("Authorization") => vec![s!("Bearer new")],
+      s!("Accept") => vec![s!("application/json")],
+      s!("X-Trace") => vec![s!("1234")]
+    }.into()));
+  }
+
+  #[test]
+  fn test_request_filter_is_applied_to_provider_requests() {
+    init().unwrap_or(());
+
+    let server = PactBuilder::new("RustPactVerifier", "SomeRunningProvider")
+      .interaction("a request with an auth token", |i| {
+        i.request.path("/data");
+        i.request.header("Authorization", "Bearer 1234");
+        i.response.status(200);
+      })
+      .start_mock_server();
+
+    let interaction = Interaction {
+      request: Request { path: s!("/data"), .. Request::default_request() },
+      response: Response { status: 200, .. Response::default_response() },
+      .. Interaction::default()
+    };
+    let provider = ProviderInfo {
+      port: server.url().port().unwrap(),
+      request_filter: Some(Arc::new(|request: &mut Request| set_request_header(request, "Authorization", "Bearer 1234"))),
+      .. ProviderInfo::default()
+    };
+    let result = verify_response_from_provider(&provider, &interaction, &VerificationOptions::default(), &hashmap!{});
+    expect!(result).to(be_ok());
+  }
+
+  #[test]
+  fn test_verify_interaction_with_an_in_process_provider() {
+    let states = Arc::new(Mutex::new(vec![]));
+    let state_calls = states.clone();
+    let provider = ProviderInfo {
+      request_handler: Some(Arc::new(|request: Request| {
+        if request.path == "/data" {
+          Response { status: 200, body: OptionalBody::Present("{\"id\":100}".into()),
+            headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()), .. Response::default_response() }
+        } else {
+          Response { status: 404, .. Response::default_response() }
+        }
+      })),
+      state_handler: Some(Arc::new(move |state: &ProviderState, setup: bool| {
+        state_calls.lock().unwrap().push((state.name.clone(), setup));
+        Ok(hashmap!{})
+      })),
+      state_change_teardown: true,
+      .. ProviderInfo::default()
+    };
+    let interaction = Interaction {
+      provider_states: vec![ ProviderState::default(&s!("there is some data")) ],
+      request: Request { path: s!("/data"), .. Request::default_request() },
+      response: Response { status: 200, body: OptionalBody::Present("{\"id\":100}".into()),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()), .. Response::default_response() },
+      .. Interaction::default()
+    };
+
+    expect!(verify_interaction(&provider, &interaction, &VerificationOptions::default())).to(be_ok());
+    expect!(states.lock().unwrap().clone()).to(be_equal_to(vec![
+      (s!("there is some data"), true), (s!("there is some data"), false)
+    ]));
+
+    let interaction = Interaction { request: Request { path: s!("/other"), .. Request::default_request() }, .. interaction };
+    expect!(verify_interaction(&provider, &interaction, &VerificationOptions::default())).to(be_err());
+  }
+
+  #[test]
+  fn test_verify_interaction_fails_if_the_provider_state_handler_fails() {
+    let provider = ProviderInfo {
+      request_handler: Some(Arc::new(|_: Request| Response::default_response())),
+      state_handler: Some(Arc::new(|_: &ProviderState, _: bool| Err(s!("no such state")))),
+      .. ProviderInfo::default()
+    };
+    let interaction = Interaction {
+      provider_states: vec![ ProviderState::default(&s!("an unknown state")) ],
+      .. Interaction::default()
+    };
+
+    expect!(verify_interaction(&provider, &interaction, &VerificationOptions::default())).to(be_err());
+  }
+
+  #[test]
+  fn test_state_change_returns_the_values_from_the_response_body() {
+    init().unwrap_or(());
+
+    let server = PactBuilder::new("RustPactVerifier", "SomeRunningProvider")
+      .interaction("a state change request that returns values", |i| {
+        i.request.method("POST");
+        i.request.path("/");
+        i.request.header("Content-Type", "application/json");
+        i.request.json_body(json_pattern!({
+          "state": "an order exists",
+          "action": "setup"
+        }));
+        i.response.status(200);
+        i.response.header("Content-Type", "application/json");
+        i.response.json_body(json_pattern!({ "id": 100 }));
+      })
+      .start_mock_server();
+
+    let provider = ProviderInfo { state_change_url: Some(server.url().to_string()), .. ProviderInfo::default() };
+    let result = execute_state_change(&ProviderState::default(&s!("an order exists")), &provider, true);
+    expect!(result.unwrap()).to(be_equal_to(hashmap!{ s!("id") => json!(100) }));
+  }
+
+  #[test]
+  fn test_provider_state_values_are_used_to_generate_the_request() {
+    let provider = ProviderInfo {
+      request_handler: Some(Arc::new(|request: Request| {
+        if request.path == "/orders/100" {
+          Response { status: 200, .. Response::default_response() }
+        } else {
+          Response { status: 404, .. Response::default_response() }
+        }
+      })),
+      state_handler: Some(Arc::new(|_: &ProviderState, _: bool| Ok(hashmap!{ s!("id") => json!(100) }))),
+      .. ProviderInfo::default()
+    };
+    let interaction = Interaction {
+      provider_states: vec![ ProviderState::default(&s!("an order exists")) ],
+      request: Request { path: s!("/orders/1"), generators: generators! {
+        "PATH" => Generator::ProviderStateGenerator(s!("/orders/${id}"))
+      }, .. Request::default_request() },
+      response: Response { status: 200, .. Response::default_response() },
+      .. Interaction::default()
+    };
+
+    expect!(verify_interaction(&provider, &interaction, &VerificationOptions::default())).to(be_ok());
+  }
+
+  #[test]
+  fn test_verify_interaction_checks_for_unexpected_keys_if_configured() {
+    let provider = ProviderInfo {
+      request_handler: Some(Arc::new(|_: Request| {
+        Response { status: 200, body: OptionalBody::Present("{\"id\":100,\"name\":\"Fred\"}".into()),
+          headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()), .. Response::default_response() }
+      })),
+      .. ProviderInfo::default()
+    };
+    let interaction = Interaction {
+      response: Response { status: 200, body: OptionalBody::Present("{\"id\":100}".into()),
+        headers: Some(hashmap!{ s!("Content-Type") => vec![s!("application/json")] }.into()), .. Response::default_response() },
+      .. Interaction::default()
+    };
+
+    expect!(verify_interaction(&provider, &interaction, &VerificationOptions::default())).to(be_ok());
+    let options = VerificationOptions { diff_config: DiffConfig::NoUnexpectedKeys, .. VerificationOptions::default() };
+    expect!(verify_interaction(&provider, &interaction, &options)).to(be_err());
+  }
+
+  fn interaction_with_states(description: &str, states: Vec<&str>) -> Interaction {
+    Interaction {
+      description: s!(description),
+      provider_states: states.iter().map(|state| ProviderState::default(&s!(*state))).collect(),
+      request: Request { path: format!("/{}", description), .. Request::default_request() },
+      response: Response { status: 200, .. Response::default_response() }
+    }
+  }
+
+  #[test]
+  fn group_by_provider_state_keeps_interactions_with_shared_states_together() {
+    let interactions = vec![
+      interaction_with_states("a", vec![]),
+      interaction_with_states("b", vec!["state 1"]),
+      interaction_with_states("c", vec![]),
+      interaction_with_states("d", vec!["state 2"]),
+      interaction_with_states("e", vec!["state 1"]),
+      interaction_with_states("f", vec!["state 3", "state 2"]),
+      interaction_with_states("g", vec!["state 3", "state 1"])
+    ];
+    expect!(group_by_provider_state(&interactions)).to(be_equal_to(vec![
+      vec![0], vec![1, 3, 4, 5, 6], vec![2]
+    ]));
+    expect!(group_by_provider_state(&interactions[0..5])).to(be_equal_to(vec![
+      vec![0], vec![1, 4], vec![2], vec![3]
+    ]));
+  }
+
+  #[test]
+  fn verify_interactions_in_parallel_returns_the_results_in_order() {
+    let states = Arc::new(Mutex::new(vec![]));
+    let state_calls = states.clone();
+    let provider = ProviderInfo {
+      request_handler: Some(Arc::new(|request: Request| {
+        if request.path.ends_with("fail") {
+          Response { status: 500, .. Response::default_response() }
+        } else {
+          Response { status: 200, .. Response::default_response() }
+        }
+      })),
+      state_handler: Some(Arc::new(move |state: &ProviderState, setup: bool| {
+        state_calls.lock().unwrap().push((state.name.clone(), setup));
+        Ok(hashmap!{})
+      })),
+      state_change_teardown: true,
+      .. ProviderInfo::default()
+    };
+    let interactions: Vec<Interaction> = (0..20).map(|i| {
+      if i % 3 == 0 {
+        interaction_with_states(&format!("{}-fail", i), vec!["shared state"])
+      } else {
+        interaction_with_states(&format!("{}", i), vec![])
+      }
+    }).collect();
+
+    let results = verify_interactions_in_parallel(&provider, interactions,
+      &VerificationOptions { concurrency: 4, .. VerificationOptions::default() });
+    expect!(results.len()).to(be_equal_to(20));
+    for (i, result) in results.iter().enumerate() {
+      match *result {
+        Ok(()) => { expect!(i % 3).to_not(be_equal_to(0)); },
+        Err(MismatchResult::Mismatches(..)) => { expect!(i % 3).to(be_equal_to(0)); },
+        Err(MismatchResult::Error(ref err)) => panic!("Unexpected error {}", err)
+      }
+    }
+
+    let states = states.lock().unwrap().clone();
+    expect!(states.len()).to(be_equal_to(14));
+    for pair in states.chunks(2) {
+      expect!(pair.to_vec()).to(be_equal_to(vec![(s!("shared state"), true), (s!("shared state"), false)]));
+    }
+  }
 }
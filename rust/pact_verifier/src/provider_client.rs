@@ -11,6 +11,7 @@ use hyper::error::Error as HyperError;
 use hyper::method::Method;
 use hyper::header::{Headers, ContentType};
 use hyper::mime::{Mime, TopLevel, SubLevel};
+use serde_json::{self, Value};
 
 pub fn join_paths(base: &String, path: String) -> String {
     let mut full_path = s!(base.trim_right_matches("/"));
@@ -19,15 +20,15 @@ pub fn join_paths(base: &String, path: String) -> String {
     full_path
 }
 
-fn setup_headers(headers: &Option<HashMap<String, String>>) -> Headers {
+fn setup_headers(headers: &Option<HeaderMap>) -> Headers {
     let mut hyper_headers = Headers::new();
     if headers.is_some() {
         let headers = headers.clone().unwrap();
         for (k, v) in headers.clone() {
-            hyper_headers.set_raw(k.clone(), vec![v.bytes().collect()]);
+            hyper_headers.set_raw(k.clone(), v.iter().map(|value| value.bytes().collect()).collect());
         }
 
-        if !headers.keys().any(|k| k.to_lowercase() == "content-type") {
+        if !headers.contains_key("content-type") {
             hyper_headers.set(ContentType(Mime(TopLevel::Application, SubLevel::Json,
                 vec![])));
         }
@@ -62,9 +63,15 @@ fn make_request(base_url: &String, request: &Request, client: &Client) -> Result
     }
 }
 
-fn extract_headers(headers: &Headers) -> Option<HashMap<String, String>> {
+fn extract_headers(headers: &Headers) -> Option<HeaderMap> {
     if headers.len() > 0 {
-        Some(headers.iter().map(|h| (s!(h.name()), h.value_string()) ).collect())
+        Some(headers.iter().fold(HeaderMap::new(), |mut map, h| {
+            let values = headers.get_raw(h.name()).unwrap_or(&[]).iter()
+                .flat_map(|line| parse_header(h.name(), &String::from_utf8_lossy(line)))
+                .collect();
+            map.add(h.name(), values);
+            map
+        }))
     } else {
         None
     }
@@ -95,11 +102,20 @@ fn hyper_response_to_pact_response(response: &mut HyperResponse) -> Response {
     }
 }
 
+fn filter_request(provider: &ProviderInfo, request: &Request) -> Request {
+    let mut request = request.clone();
+    if let Some(ref filter) = provider.request_filter {
+        filter(&mut request);
+    }
+    request
+}
+
 pub fn make_provider_request(provider: &ProviderInfo, request: &Request) -> Result<Response, HyperError> {
+    let request = filter_request(provider, request);
     debug!("Sending {:?} to provider", request);
     let client = Client::new();
     match make_request(&format!("{}://{}:{}{}", provider.protocol, provider.host, provider.port,
-        provider.path), request, &client) {
+        provider.path), &request, &client) {
         Ok(ref mut response) => {
             debug!("Received response: {:?}", response);
             Ok(hyper_response_to_pact_response(response))
@@ -111,14 +127,36 @@ pub fn make_provider_request(provider: &ProviderInfo, request: &Request) -> Resu
     }
 }
 
-pub fn make_state_change_request(provider: &ProviderInfo, request: &Request) -> Result<(), String> {
+pub fn call_request_handler(provider: &ProviderInfo, handler: &RequestHandler, request: &Request) -> Response {
+    let request = filter_request(provider, request);
+    debug!("Sending {:?} to provider request handler", request);
+    let response = handler.handle(request);
+    debug!("Received response: {:?}", response);
+    response
+}
+
+fn state_change_values(response: &mut HyperResponse) -> HashMap<String, Value> {
+    match extract_body(response) {
+        OptionalBody::Present(ref body) => match serde_json::from_slice(body) {
+            Ok(Value::Object(ref map)) => map.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            Ok(_) => HashMap::new(),
+            Err(err) => {
+                debug!("State change response body is not a JSON object, ignoring it: {}", err);
+                HashMap::new()
+            }
+        },
+        _ => HashMap::new()
+    }
+}
+
+pub fn make_state_change_request(provider: &ProviderInfo, request: &Request) -> Result<HashMap<String, Value>, String> {
     debug!("Sending {:?} to state change handler", request);
     let client = Client::new();
     match make_request(&provider.state_change_url.clone().unwrap(), request, &client) {
         Ok(ref mut response) => {
             debug!("Received response: {:?}", response);
             if response.status.is_success() {
-                Ok(())
+                Ok(state_change_values(response))
             } else {
                 debug!("Request failed: {}", response.status);
                 Err(format!("State change request failed: {}", response.status))
@@ -0,0 +1,282 @@
+//! The `report` module provides a structured report of the results of a verification run. The
+//! report can be converted to JSON or JUnit XML so that it can be consumed by other tools.
+
+use serde_json::Value;
+use super::MismatchResult;
+
+/// Result of verifying a single interaction
+#[derive(Debug, Clone)]
+pub struct InteractionResult {
+    /// Description of the interaction
+    pub description: String,
+    /// Names of the provider states of the interaction
+    pub provider_states: Vec<String>,
+    /// Result of the verification
+    pub result: Result<(), MismatchResult>
+}
+
+impl InteractionResult {
+
+    /// If the interaction was verified successfully
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Full name of the interaction, including any provider states
+    pub fn name(&self) -> String {
+        let mut name = String::new();
+        if let Some((first, elements)) = self.provider_states.split_first() {
+            name.push_str(&format!("Given {}", first));
+            for state in elements {
+                name.push_str(&format!(" And {}", state));
+            }
+            name.push_str(" - ");
+        }
+        name.push_str(&self.description);
+        name
+    }
+
+    /// Converts this result to a `Value` struct.
+    pub fn to_json(&self) -> Value {
+        let mut json = json!({
+            s!("description"): json!(self.description),
+            s!("providerStates"): json!(self.provider_states),
+            s!("result"): json!(self.is_success())
+        });
+        {
+            let map = json.as_object_mut().unwrap();
+            match self.result {
+                Ok(()) => (),
+                Err(MismatchResult::Error(ref err)) => {
+                    map.insert(s!("error"), json!(err));
+                },
+                Err(MismatchResult::Mismatches(ref mismatches, _, _)) => {
+                    map.insert(s!("mismatches"), Value::Array(mismatches.iter().map(|m| m.to_json()).collect()));
+                }
+            }
+        }
+        json
+    }
+}
+
+/// Result of verifying all the interactions of a pact
+#[derive(Debug, Clone)]
+pub struct PactResult {
+    /// Name of the consumer. Will be `None` if the pact could not be loaded.
+    pub consumer: Option<String>,
+    /// Name of the provider. Will be `None` if the pact could not be loaded.
+    pub provider: Option<String>,
+    /// Results of the interactions that were verified, or the error if the pact could not be loaded
+    pub result: Result<Vec<InteractionResult>, String>
+}
+
+impl PactResult {
+
+    /// If the pact was loaded and all the interactions were verified successfully
+    pub fn is_success(&self) -> bool {
+        match self.result {
+            Ok(ref interactions) => interactions.iter().all(|i| i.is_success()),
+            Err(_) => false
+        }
+    }
+
+    /// Name of the pact, which is based on the consumer and provider names
+    pub fn name(&self) -> String {
+        match (self.consumer.as_ref(), self.provider.as_ref()) {
+            (Some(consumer), Some(provider)) => format!("Verifying a pact between {} and {}", consumer, provider),
+            _ => s!("Failed to load pact")
+        }
+    }
+
+    /// Converts this result to a `Value` struct.
+    pub fn to_json(&self) -> Value {
+        let mut json = json!({
+            s!("result"): json!(self.is_success())
+        });
+        {
+            let map = json.as_object_mut().unwrap();
+            if let Some(ref consumer) = self.consumer {
+                map.insert(s!("consumer"), json!(consumer));
+            }
+            if let Some(ref provider) = self.provider {
+                map.insert(s!("provider"), json!(provider));
+            }
+            match self.result {
+                Ok(ref interactions) => {
+                    map.insert(s!("interactions"), Value::Array(interactions.iter().map(|i| i.to_json()).collect()));
+                },
+                Err(ref err) => {
+                    map.insert(s!("error"), json!(err));
+                }
+            }
+        }
+        json
+    }
+}
+
+/// Report of a verification run. Contains the results for every pact that was verified.
+#[derive(Debug, Clone)]
+pub struct VerificationReport {
+    /// Results for each of the pacts, in the order they were verified
+    pub pacts: Vec<PactResult>
+}
+
+impl VerificationReport {
+
+    /// If all the pacts were verified successfully
+    pub fn is_success(&self) -> bool {
+        self.pacts.iter().all(|p| p.is_success())
+    }
+
+    /// Converts this report to a `Value` struct.
+    pub fn to_json(&self) -> Value {
+        json!({
+            s!("result"): json!(self.is_success()),
+            s!("pacts"): Value::Array(self.pacts.iter().map(|p| p.to_json()).collect())
+        })
+    }
+
+    /// Converts this report to a JUnit XML document. Each pact is written as a test suite, and each
+    /// interaction as a test case within that suite.
+    pub fn to_junit_xml(&self) -> String {
+        let mut xml = s!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let (tests, failures, errors) = self.pacts.iter().fold((0, 0, 0), |acc, pact| {
+            let counts = test_counts(pact);
+            (acc.0 + counts.0, acc.1 + counts.1, acc.2 + counts.2)
+        });
+        xml.push_str(&format!("<testsuites name=\"pact verification\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+            tests, failures, errors));
+        for pact in &self.pacts {
+            let counts = test_counts(pact);
+            xml.push_str(&format!("  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+                escape_xml(&pact.name()), counts.0, counts.1, counts.2));
+            match pact.result {
+                Ok(ref interactions) => for interaction in interactions {
+                    xml.push_str(&format!("    <testcase classname=\"{}\" name=\"{}\"",
+                        escape_xml(&pact.name()), escape_xml(&interaction.name())));
+                    match interaction.result {
+                        Ok(()) => xml.push_str("/>\n"),
+                        Err(MismatchResult::Error(ref err)) => {
+                            xml.push_str(">\n");
+                            xml.push_str(&format!("      <error message=\"{}\">{}</error>\n",
+                                escape_xml(&format!("Request Failed - {}", err)), escape_xml(err)));
+                            xml.push_str("    </testcase>\n");
+                        },
+                        Err(MismatchResult::Mismatches(ref mismatches, _, _)) => {
+                            xml.push_str(">\n");
+                            for mismatch in mismatches {
+                                xml.push_str(&format!("      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                                    mismatch.mismatch_type(), escape_xml(&mismatch.summary()),
+                                    escape_xml(&mismatch.description())));
+                            }
+                            xml.push_str("    </testcase>\n");
+                        }
+                    }
+                },
+                Err(ref err) => {
+                    xml.push_str(&format!("    <testcase classname=\"{}\" name=\"{}\">\n", escape_xml(&pact.name()),
+                        escape_xml(&pact.name())));
+                    xml.push_str(&format!("      <error message=\"{}\">{}</error>\n", escape_xml(err), escape_xml(err)));
+                    xml.push_str("    </testcase>\n");
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Returns the number of tests, failures and errors for the pact result
+fn test_counts(pact: &PactResult) -> (usize, usize, usize) {
+    match pact.result {
+        Ok(ref interactions) => interactions.iter().fold((0, 0, 0), |acc, interaction| {
+            match interaction.result {
+                Ok(()) => (acc.0 + 1, acc.1, acc.2),
+                Err(MismatchResult::Mismatches(_, _, _)) => (acc.0 + 1, acc.1 + 1, acc.2),
+                Err(MismatchResult::Error(_)) => (acc.0 + 1, acc.1, acc.2 + 1)
+            }
+        }),
+        Err(_) => (1, 0, 1)
+    }
+}
+
+/// Escapes the characters that are not allowed in XML attributes and text
+fn escape_xml(value: &str) -> String {
+    value.chars().map(|ch| {
+        match ch {
+            '&' => s!("&amp;"),
+            '<' => s!("&lt;"),
+            '>' => s!("&gt;"),
+            '"' => s!("&quot;"),
+            '\'' => s!("&apos;"),
+            _ => ch.to_string()
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use expectest::prelude::*;
+    use super::*;
+    use pact_matching::Mismatch;
+    use pact_matching::models::Response;
+
+    fn report() -> VerificationReport {
+        VerificationReport {
+            pacts: vec![
+                PactResult {
+                    consumer: Some(s!("Consumer")),
+                    provider: Some(s!("Provider")),
+                    result: Ok(vec![
+                        InteractionResult {
+                            description: s!("a request for <stuff>"),
+                            provider_states: vec![s!("some state")],
+                            result: Ok(())
+                        },
+                        InteractionResult {
+                            description: s!("a request for other stuff"),
+                            provider_states: vec![],
+                            result: Err(MismatchResult::Mismatches(vec![
+                                Mismatch::StatusMismatch { expected: 200, actual: 404 }
+                            ], Response::default_response(), Response::default_response()))
+                        }
+                    ])
+                },
+                PactResult {
+                    consumer: None,
+                    provider: None,
+                    result: Err(s!("Failed to load pact 'missing.json'"))
+                }
+            ]
+        }
+    }
+
+    #[test]
+    fn report_is_only_successful_if_all_pacts_are_successful() {
+        expect!(VerificationReport { pacts: vec![] }.is_success()).to(be_true());
+        expect!(report().is_success()).to(be_false());
+        expect!(report().pacts[0].result.clone().unwrap()[0].is_success()).to(be_true());
+    }
+
+    #[test]
+    fn report_to_json_includes_the_mismatches() {
+        let json = report().to_json();
+        expect!(json["result"].clone()).to(be_equal_to(json!(false)));
+        expect!(json["pacts"][0]["consumer"].clone()).to(be_equal_to(json!("Consumer")));
+        expect!(json["pacts"][0]["interactions"][0]["providerStates"].clone()).to(be_equal_to(json!(["some state"])));
+        expect!(json["pacts"][0]["interactions"][1]["mismatches"].clone()).to(be_equal_to(json!([
+            { "type": "StatusMismatch", "expected": 200, "actual": 404 }
+        ])));
+        expect!(json["pacts"][1]["error"].clone()).to(be_equal_to(json!("Failed to load pact 'missing.json'")));
+    }
+
+    #[test]
+    fn report_to_junit_xml() {
+        let xml = report().to_junit_xml();
+        expect!(xml.contains("<testsuites name=\"pact verification\" tests=\"3\" failures=\"1\" errors=\"1\">")).to(be_true());
+        expect!(xml.contains("<testcase classname=\"Verifying a pact between Consumer and Provider\" name=\"Given some state - a request for &lt;stuff&gt;\"/>")).to(be_true());
+        expect!(xml.contains("<failure type=\"StatusMismatch\" message=\"has status code 200\">expected 200 but was 404</failure>")).to(be_true());
+        expect!(xml.contains("<error message=\"Failed to load pact &apos;missing.json&apos;\">")).to(be_true());
+    }
+}
@@ -20,12 +20,14 @@
 //! FLAGS:
 //!         --filter-no-state          Only validate interactions that have no defined provider state
 //!         --help                     Prints help information
+//!         --no-unexpected-keys       Fail the verification if a response body has keys that are not in the pact
 //!         --state-change-as-query    State change request data will be sent as query parameters instead of in the request body
 //!         --state-change-teardown    State change teardown requests are to be made after each interaction
 //!     -v, --version                  Prints version information
 //!
 //! OPTIONS:
 //!     -b, --broker-url <broker-url>                    URL of the pact broker to fetch pacts from to verify (requires the provider name parameter)
+//!         --concurrency <concurrency>                  Number of interactions to verify in parallel (defaults to 1). Interactions that share a provider state are verified one after the other
 //!     -d, --dir <dir>                                  Directory of pact files to verify (can be repeated)
 //!     -f, --file <file>                                Pact file to verify (can be repeated)
 //!     -c, --filter-consumer <filter-consumer>       Consumer name to filter the pacts to be verified (can be repeated)
@@ -37,6 +39,10 @@
 //!     -n, --provider-name <provider-name>              Provider name (defaults to provider)
 //!     -s, --state-change-url <state-change-url>        URL to post state change requests to
 //!     -u, --url <url>                                  URL of pact file to verify (can be repeated)
+//!         --json <json>                                File to write the verification report to in JSON format
+//!         --junit <junit>                              File to write the verification report to in JUnit XML format
+//!         --custom-header <custom-header>              Header to add to, or replace in, each request to the provider, in the form 'Name: Value' (can be repeated)
+//!         --header-from-env <header-from-env>          Header to add to, or replace in, each request to the provider with the value of an environment variable, in the form 'Name=ENVIRONMENT_VARIABLE' (can be repeated)
 //! ```
 //!
 //! ## Options
@@ -102,6 +108,43 @@
 //!
 //! This option will cause the verifier to also make a tear down request after the main request is made. It will receive a second field in the body or a query parameter named `action` with the value `teardown`.
 //!
+//! ### Verification reports
+//!
+//! The results of the verification can be written to a file as a machine-readable report, for use with CI servers and dashboards.
+//! Both options can be given together.
+//!
+//! #### `--json <json>`
+//!
+//! Writes a JSON report to the given file. Each pact is listed with the result of each interaction, and any mismatches are included in the same JSON format as the mock server uses.
+//!
+//! #### `--junit <junit>`
+//!
+//! Writes a JUnit XML report to the given file. Each pact is written as a test suite, and each interaction as a test case. Mismatches are reported as failures and request errors as errors.
+//!
+//! ### Custom request headers
+//!
+//! Some providers require values in the requests that can not be stored in the pact files, like authentication tokens. These options add headers to every request made to the provider before it is sent. If the request already has a header with the same name (ignoring case), it will be replaced. They do not apply to the state change requests.
+//!
+//! #### `--custom-header <custom-header>`
+//!
+//! Adds the header to each request. The value must be in the form `Name: Value`, for example `--custom-header 'Authorization: Bearer 1234'`. Can be repeated to set multiple headers.
+//!
+//! #### `--header-from-env <header-from-env>`
+//!
+//! Adds the header to each request, using the value of an environment variable. The value must be in the form `Name=ENVIRONMENT_VARIABLE`, for example `--header-from-env Authorization=AUTH_TOKEN`. The verifier will exit with an error if the environment variable is not set. Can be repeated to set multiple headers.
+//!
+//! ### Parallel verification
+//!
+//! #### `--concurrency <concurrency>`
+//!
+//! Sets the number of interactions that will be verified in parallel. It defaults to 1, which verifies all the interactions one after the other. Interactions that share a provider state are always verified one after the other, as the state change requests for them could interfere with each other. The results are displayed once all the interactions have been verified, in the same order as the interactions in the pact files.
+//!
+//! ### Response matching
+//!
+//! #### `--no-unexpected-keys`
+//!
+//! By default, keys in the response bodies that are not in the pact are ignored, so the provider can return more data than the consumer uses. With this flag, any unexpected keys are reported as mismatches.
+//!
 //! ## Example run
 //!
 //! This will verify all the pacts for the `happy_provider` found in the pact broker (running on localhost) against the provider running on localhost port 5050. Only the pacts for the consumers `Consumer` and `Consumer2` will be verified.
@@ -201,6 +244,7 @@ extern crate pact_verifier;
 extern crate simplelog;
 extern crate rand;
 extern crate regex;
+extern crate serde_json;
 
 #[cfg(test)]
 #[macro_use(expect)]
@@ -211,13 +255,18 @@ extern crate quickcheck;
 
 use std::env;
 use clap::{Arg, App, AppSettings, ErrorKind, ArgMatches};
-use pact_matching::models::PactSpecification;
+use pact_matching::DiffConfig;
+use pact_matching::models::{PactSpecification, Request};
 use pact_verifier::*;
+use pact_verifier::report::VerificationReport;
 use log::LogLevelFilter;
 use simplelog::TermLogger;
 use std::str::FromStr;
 use std::error::Error;
 use regex::Regex;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Arc;
 
 fn main() {
     match handle_command_args() {
@@ -235,6 +284,67 @@ fn integer_value(v: String) -> Result<(), String> {
     v.parse::<u16>().map(|_| ()).map_err(|e| format!("'{}' is not a valid port value: {}", v, e) )
 }
 
+fn concurrency_value(v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(()),
+        Ok(_) => Err(format!("'{}' is not a valid concurrency value, it must be at least 1", v)),
+        Err(e) => Err(format!("'{}' is not a valid concurrency value: {}", v, e))
+    }
+}
+
+fn split_header(v: &str) -> Option<(String, String)> {
+    let mut parts = v.splitn(2, ':');
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(value)) if !name.trim().is_empty() => Some((s!(name.trim()), s!(value.trim()))),
+        _ => None
+    }
+}
+
+fn header_value(v: String) -> Result<(), String> {
+    split_header(&v).map(|_| ()).ok_or(format!("'{}' is not a valid header, it must be in the form 'Name: Value'", v))
+}
+
+fn split_header_from_env(v: &str) -> Option<(String, String)> {
+    let mut parts = v.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(var)) if !name.trim().is_empty() && !var.trim().is_empty() =>
+            Some((s!(name.trim()), s!(var.trim()))),
+        _ => None
+    }
+}
+
+fn header_from_env_value(v: String) -> Result<(), String> {
+    split_header_from_env(&v).map(|_| ())
+        .ok_or(format!("'{}' is not a valid header, it must be in the form 'Name=ENVIRONMENT_VARIABLE'", v))
+}
+
+fn request_filter(matches: &ArgMatches) -> Result<Option<RequestFilter>, i32> {
+    let mut headers = vec![];
+    if let Some(values) = matches.values_of("custom-header") {
+        headers.extend(values.filter_map(split_header));
+    }
+    if let Some(values) = matches.values_of("header-from-env") {
+        for (name, var) in values.filter_map(split_header_from_env) {
+            match env::var(&var) {
+                Ok(value) => headers.push((name, value)),
+                Err(err) => {
+                    error!("Could not get the value for header '{}' from environment variable '{}' - {}", name, var, err);
+                    return Err(1);
+                }
+            }
+        }
+    }
+    if headers.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(Arc::new(move |request: &mut Request| {
+            for (name, value) in &headers {
+                set_request_header(request, name, value);
+            }
+        })))
+    }
+}
+
 fn pact_source(matches: &ArgMatches) -> Vec<PactSource> {
     let mut sources = vec![];
     match matches.values_of("file") {
@@ -278,6 +388,24 @@ fn interaction_filter(matches: &ArgMatches) -> FilterInfo {
     }
 }
 
+fn write_report(file: &str, contents: String) -> Result<(), i32> {
+    File::create(file).and_then(|mut f| f.write_all(contents.as_bytes()))
+        .map_err(|err| {
+            error!("Failed to write the verification report to '{}' - {}", file, err);
+            3
+        })
+}
+
+fn write_reports(report: &VerificationReport, matches: &ArgMatches) -> Result<(), i32> {
+    if let Some(file) = matches.value_of("json") {
+        write_report(file, serde_json::to_string_pretty(&report.to_json()).unwrap())?;
+    }
+    if let Some(file) = matches.value_of("junit") {
+        write_report(file, report.to_junit_xml())?;
+    }
+    Ok(())
+}
+
 fn handle_command_args() -> Result<(), i32> {
     let args: Vec<String> = env::args().collect();
     let program = args[0].clone();
@@ -396,6 +524,43 @@ fn handle_command_args() -> Result<(), i32> {
             .multiple(true)
             .empty_values(false)
             .help("Consumer name to filter the pacts to be verified (can be repeated)"))
+        .arg(Arg::with_name("json")
+            .long("json")
+            .takes_value(true)
+            .use_delimiter(false)
+            .empty_values(false)
+            .help("File to write the verification report to in JSON format"))
+        .arg(Arg::with_name("junit")
+            .long("junit")
+            .takes_value(true)
+            .use_delimiter(false)
+            .empty_values(false)
+            .help("File to write the verification report to in JUnit XML format"))
+        .arg(Arg::with_name("custom-header")
+            .long("custom-header")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(header_value)
+            .help("Header to add to, or replace in, each request to the provider, in the form 'Name: Value' (can be repeated)"))
+        .arg(Arg::with_name("header-from-env")
+            .long("header-from-env")
+            .takes_value(true)
+            .use_delimiter(false)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(header_from_env_value)
+            .help("Header to add to, or replace in, each request to the provider with the value of an environment variable, in the form 'Name=ENVIRONMENT_VARIABLE' (can be repeated)"))
+        .arg(Arg::with_name("concurrency")
+            .long("concurrency")
+            .takes_value(true)
+            .use_delimiter(false)
+            .validator(concurrency_value)
+            .help("Number of interactions to verify in parallel (defaults to 1). Interactions that share a provider state are verified one after the other"))
+        .arg(Arg::with_name("no-unexpected-keys")
+            .long("no-unexpected-keys")
+            .help("Fail the verification if a response body has keys that are not in the pact"))
         ;
 
     let matches = app.get_matches_safe();
@@ -413,11 +578,23 @@ fn handle_command_args() -> Result<(), i32> {
                 state_change_url: matches.value_of("state-change-url").map(|s| s.to_string()),
                 state_change_body: !matches.is_present("state-change-as-query"),
                 state_change_teardown: matches.is_present("state-change-teardown"),
+                request_filter: request_filter(matches)?,
                 .. ProviderInfo::default()
             };
             let source = pact_source(matches);
             let filter = interaction_filter(matches);
-            if verify_provider(&provider, source, &filter, &matches.values_of_lossy("filter-consumer").unwrap_or(vec![])) {
+            let options = VerificationOptions {
+                concurrency: matches.value_of("concurrency").unwrap_or("1").parse::<usize>().unwrap(),
+                diff_config: if matches.is_present("no-unexpected-keys") {
+                    DiffConfig::NoUnexpectedKeys
+                } else {
+                    DiffConfig::AllowUnexpectedKeys
+                }
+            };
+            let report = verify_provider(&provider, source, &filter, &matches.values_of_lossy("filter-consumer").unwrap_or(vec![]),
+                &options);
+            write_reports(&report, matches)?;
+            if report.is_success() {
                 Ok(())
             } else {
                 Err(2)
@@ -447,7 +624,7 @@ mod test {
 
     use quickcheck::{TestResult, quickcheck};
     use rand::Rng;
-    use super::integer_value;
+    use super::{integer_value, concurrency_value, split_header, header_value, split_header_from_env, header_from_env_value};
     use expectest::prelude::*;
 
     #[test]
@@ -469,4 +646,31 @@ mod test {
         expect!(integer_value(s!("1234"))).to(be_ok());
         expect!(integer_value(s!("1234x"))).to(be_err());
     }
+
+    #[test]
+    fn validates_custom_header_values() {
+        expect!(header_value(s!("Authorization: Bearer 1234"))).to(be_ok());
+        expect!(header_value(s!("X-Empty:"))).to(be_ok());
+        expect!(header_value(s!("Authorization"))).to(be_err());
+        expect!(header_value(s!(": Bearer 1234"))).to(be_err());
+        expect!(split_header("Authorization: Bearer a:b")).to(be_some().value((s!("Authorization"), s!("Bearer a:b"))));
+    }
+
+    #[test]
+    fn validates_header_from_env_values() {
+        expect!(header_from_env_value(s!("Authorization=AUTH_TOKEN"))).to(be_ok());
+        expect!(header_from_env_value(s!("Authorization"))).to(be_err());
+        expect!(header_from_env_value(s!("Authorization="))).to(be_err());
+        expect!(header_from_env_value(s!("=AUTH_TOKEN"))).to(be_err());
+        expect!(split_header_from_env("Authorization = AUTH_TOKEN")).to(be_some().value((s!("Authorization"), s!("AUTH_TOKEN"))));
+    }
+
+    #[test]
+    fn validates_concurrency_value() {
+        expect!(concurrency_value(s!("1"))).to(be_ok());
+        expect!(concurrency_value(s!("8"))).to(be_ok());
+        expect!(concurrency_value(s!("0"))).to(be_err());
+        expect!(concurrency_value(s!("-1"))).to(be_err());
+        expect!(concurrency_value(s!("many"))).to(be_err());
+    }
 }